@@ -1,6 +1,6 @@
 use std::fmt;
 
-#[derive(Default, Debug, Clone, PartialEq, Eq)]
+#[derive(Default, Debug, Clone, PartialEq)]
 pub enum EngineMessage<C, E>
 where
     C: fmt::Debug,
@@ -28,14 +28,123 @@ pub const ENGINE_EVENT_TOPIC: &str = "engine_event";
 pub const APP_COMMAND_TOPIC: &str = "app_command";
 pub const APP_EVENT_TOPIC: &str = "app_event";
 
-#[derive(Default, Debug, Clone, PartialEq, Eq)]
+#[derive(Default, Debug, Clone, PartialEq)]
 pub enum EngineCommand {
     #[default]
     Empty,
+    /// Frames a scene's camera around a graph node and its descendants
+    FocusNode {
+        scene_index: usize,
+        graph_node_index: usize,
+    },
+    /// Plays a clip registered with the audio service,
+    /// from a node's position or without spatialization when there is no node
+    PlaySound {
+        clip: String,
+        node_index: Option<usize>,
+        volume: f32,
+        looping: bool,
+    },
+    /// Stops the sounds playing from a node, or every sound when there is no node
+    StopSounds { node_index: Option<usize> },
 }
 
-#[derive(Default, Debug, Clone, PartialEq, Eq)]
+#[derive(Default, Debug, Clone, PartialEq)]
 pub enum EngineEvent {
     #[default]
     Empty,
+    WorldChanged {
+        change: WorldChange,
+    },
+    Input {
+        event: InputEvent,
+    },
+}
+
+/// Window input that was not consumed by the gui.
+/// Releases are reported even when the gui consumed them, so nothing pressed beforehand stays held.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum InputEvent {
+    KeyPressed {
+        key: Key,
+    },
+    KeyReleased {
+        key: Key,
+    },
+    MouseButtonPressed {
+        button: MouseButton,
+    },
+    MouseButtonReleased {
+        button: MouseButton,
+    },
+    /// Raw mouse motion in pixels, reported even when the cursor is at the edge of the window
+    MouseMoved {
+        delta_x: f32,
+        delta_y: f32,
+    },
+    /// Scroll wheel motion in lines, positive when scrolling away from the user
+    MouseScrolled {
+        delta: f32,
+    },
+    /// The window lost focus, so keys and buttons held until now will not report their release
+    FocusLost,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Key {
+    W,
+    A,
+    S,
+    D,
+    Q,
+    E,
+    F,
+    Space,
+    Shift,
+    Control,
+    Up,
+    Down,
+    Left,
+    Right,
+    Other,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+    Other,
+}
+
+/// Describes an edit made to the world so that views can refresh
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum WorldChange {
+    NodeAdded {
+        scene_index: usize,
+        node_index: usize,
+    },
+    NodeRemoved {
+        scene_index: usize,
+        node_index: usize,
+    },
+    TransformChanged {
+        node_index: usize,
+    },
+    MaterialChanged {
+        mesh_index: usize,
+        primitive_index: usize,
+    },
+    CameraChanged {
+        node_index: usize,
+    },
+    PrimitiveMeshChanged {
+        node_index: usize,
+    },
+}
+
+impl From<WorldChange> for EngineEvent {
+    fn from(change: WorldChange) -> Self {
+        Self::WorldChanged { change }
+    }
 }
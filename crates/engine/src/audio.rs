@@ -0,0 +1,730 @@
+use contract::{EngineCommand, EngineMessage};
+use service::{client::Client, Broker, Service};
+use std::{cell::RefCell, collections::HashMap, f32::consts::FRAC_PI_4, fmt, rc::Rc};
+use world::World;
+
+/// The longest stretch of audio rendered in one update, so a stalled frame does not produce a burst
+const MAX_UPDATE_SECONDS: f64 = 0.25;
+
+#[derive(thiserror::Error, Debug)]
+pub enum AudioError {
+    #[error("Not a RIFF WAVE file")]
+    NotWave,
+
+    #[error("The {0} chunk is missing")]
+    MissingChunk(&'static str),
+
+    #[error("The audio data ends unexpectedly")]
+    Truncated,
+
+    #[error("Unsupported audio format: {0}")]
+    UnsupportedFormat(String),
+
+    #[error("Failed to decode Ogg Vorbis audio: {0}")]
+    Vorbis(#[from] lewton::VorbisError),
+}
+
+/// Decoded audio as interleaved samples from -1 to 1
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct AudioClip {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub samples: Vec<f32>,
+}
+
+impl AudioClip {
+    /// Decodes a WAV or Ogg Vorbis clip, detecting its format from its contents
+    pub fn decode(bytes: &[u8]) -> Result<Self, AudioError> {
+        let clip = if bytes.starts_with(b"OggS") {
+            Self::from_ogg(bytes)?
+        } else {
+            Self::from_wav(bytes)?
+        };
+        if clip.channels == 0 || clip.sample_rate == 0 {
+            return Err(AudioError::UnsupportedFormat(format!(
+                "audio with {} channels at {} Hz",
+                clip.channels, clip.sample_rate
+            )));
+        }
+        Ok(clip)
+    }
+
+    /// Decodes an Ogg Vorbis stream
+    pub fn from_ogg(bytes: &[u8]) -> Result<Self, AudioError> {
+        let mut reader = lewton::inside_ogg::OggStreamReader::new(std::io::Cursor::new(bytes))?;
+        let mut samples = Vec::new();
+        while let Some(packet) =
+            reader.read_dec_packet_generic::<lewton::samples::InterleavedSamples<f32>>()?
+        {
+            samples.extend(packet.samples);
+        }
+        Ok(Self {
+            sample_rate: reader.ident_hdr.audio_sample_rate,
+            channels: reader.ident_hdr.audio_channels as u16,
+            samples,
+        })
+    }
+
+    /// Decodes 8, 16, 24 or 32 bit integer PCM or 32 or 64 bit float WAV data
+    pub fn from_wav(bytes: &[u8]) -> Result<Self, AudioError> {
+        if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+            return Err(AudioError::NotWave);
+        }
+
+        let mut format = None;
+        let mut data = None;
+        let mut offset = 12;
+        while offset + 8 <= bytes.len() {
+            let id = &bytes[offset..offset + 4];
+            let size = read_u32(bytes, offset + 4)? as usize;
+            let end = (offset + 8)
+                .checked_add(size)
+                .ok_or(AudioError::Truncated)?;
+            let body = bytes.get(offset + 8..end).ok_or(AudioError::Truncated)?;
+            match id {
+                b"fmt " => format = Some(WavFormat::parse(body)?),
+                b"data" => data = Some(body),
+                _ => {}
+            }
+            // Chunks are padded to an even length
+            offset = end + size % 2;
+        }
+        let format = format.ok_or(AudioError::MissingChunk("fmt"))?;
+        let data = data.ok_or(AudioError::MissingChunk("data"))?;
+
+        let bytes_per_sample = format.bits_per_sample as usize / 8;
+        let samples = data
+            .chunks_exact(bytes_per_sample)
+            .map(|sample| match (format.float, format.bits_per_sample) {
+                (false, 8) => (sample[0] as f32 - 128.0) / 128.0,
+                (false, 16) => i16::from_le_bytes([sample[0], sample[1]]) as f32 / 32768.0,
+                // Shifting into the top of an i32 sign extends the 24 bit sample
+                (false, 24) => {
+                    i32::from_le_bytes([0, sample[0], sample[1], sample[2]]) as f32 / 2147483648.0
+                }
+                (false, _) => i32::from_le_bytes(sample.try_into().unwrap()) as f32 / 2147483648.0,
+                (true, 32) => f32::from_le_bytes(sample.try_into().unwrap()),
+                (true, _) => f64::from_le_bytes(sample.try_into().unwrap()) as f32,
+            })
+            .collect();
+
+        Ok(Self {
+            sample_rate: format.sample_rate,
+            channels: format.channels,
+            samples,
+        })
+    }
+
+    pub fn number_of_frames(&self) -> usize {
+        self.samples.len() / self.channels.max(1) as usize
+    }
+
+    fn sample(&self, frame: usize, channel: u16) -> f32 {
+        // Clips without channels can still be built by hand, but have nothing to play
+        if self.channels == 0 {
+            return 0.0;
+        }
+        let channel = channel.min(self.channels - 1) as usize;
+        self.samples[frame * self.channels as usize + channel]
+    }
+}
+
+struct WavFormat {
+    channels: u16,
+    sample_rate: u32,
+    bits_per_sample: u16,
+    float: bool,
+}
+
+impl WavFormat {
+    const PCM: u16 = 1;
+    const IEEE_FLOAT: u16 = 3;
+    const EXTENSIBLE: u16 = 0xFFFE;
+
+    fn parse(body: &[u8]) -> Result<Self, AudioError> {
+        let mut tag = read_u16(body, 0)?;
+        let channels = read_u16(body, 2)?;
+        let sample_rate = read_u32(body, 4)?;
+        let bits_per_sample = read_u16(body, 14)?;
+        if tag == Self::EXTENSIBLE {
+            // The sub format GUID starts with the actual format tag
+            tag = read_u16(body, 24)?;
+        }
+        let float = match (tag, bits_per_sample) {
+            (Self::PCM, 8 | 16 | 24 | 32) => false,
+            (Self::IEEE_FLOAT, 32 | 64) => true,
+            _ => {
+                return Err(AudioError::UnsupportedFormat(format!(
+                    "WAV format {tag} with {bits_per_sample} bits per sample"
+                )))
+            }
+        };
+        if channels == 0 || sample_rate == 0 {
+            return Err(AudioError::UnsupportedFormat(format!(
+                "WAV with {channels} channels at {sample_rate} Hz"
+            )));
+        }
+        Ok(Self {
+            channels,
+            sample_rate,
+            bits_per_sample,
+            float,
+        })
+    }
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Result<u16, AudioError> {
+    bytes
+        .get(offset..offset + 2)
+        .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+        .ok_or(AudioError::Truncated)
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Result<u32, AudioError> {
+    bytes
+        .get(offset..offset + 4)
+        .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .ok_or(AudioError::Truncated)
+}
+
+/// Where mixed stereo frames go
+pub trait OutputDevice {
+    fn sample_rate(&self) -> u32;
+    fn write(&mut self, frames: &[[f32; 2]]);
+}
+
+/// Collects mixed frames in memory instead of playing them,
+/// for machines without audio hardware and for tests
+#[derive(Debug, Clone)]
+pub struct NullOutput {
+    pub sample_rate: u32,
+    pub frames: Vec<[f32; 2]>,
+}
+
+impl Default for NullOutput {
+    fn default() -> Self {
+        Self {
+            sample_rate: 48000,
+            frames: Vec::new(),
+        }
+    }
+}
+
+impl OutputDevice for NullOutput {
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn write(&mut self, frames: &[[f32; 2]]) {
+        self.frames.extend_from_slice(frames);
+    }
+}
+
+/// How a sound gets quieter with distance, following the inverse distance clamped model.
+/// Sounds are at full volume within the reference distance and stop fading past the max distance.
+#[derive(Debug, Copy, Clone)]
+pub struct Attenuation {
+    pub reference_distance: f32,
+    pub max_distance: f32,
+    pub rolloff: f32,
+}
+
+impl Default for Attenuation {
+    fn default() -> Self {
+        Self {
+            reference_distance: 1.0,
+            max_distance: 100.0,
+            rolloff: 1.0,
+        }
+    }
+}
+
+impl Attenuation {
+    pub fn gain(&self, distance: f32) -> f32 {
+        let distance = distance.clamp(self.reference_distance, self.max_distance);
+        self.reference_distance
+            / (self.reference_distance + self.rolloff * (distance - self.reference_distance))
+    }
+
+    /// Returns the left and right gains for a sound at a position relative to the listener,
+    /// panning with constant power so that a sound keeps its loudness as it moves across
+    pub fn stereo_gains(&self, relative_position: &nalgebra_glm::Vec3) -> [f32; 2] {
+        let distance = relative_position.norm();
+        let pan = if distance > f32::EPSILON {
+            relative_position.x / distance
+        } else {
+            0.0
+        };
+        let angle = (pan + 1.0) * FRAC_PI_4;
+        let gain = self.gain(distance);
+        [angle.cos() * gain, angle.sin() * gain]
+    }
+}
+
+struct Voice {
+    clip: Rc<AudioClip>,
+    node_index: Option<usize>,
+    volume: f32,
+    looping: bool,
+    cursor: f64,
+    gains: Option<[f32; 2]>,
+}
+
+/// Mixes the playing sounds into stereo frames
+pub struct Mixer {
+    pub attenuation: Attenuation,
+    pub master_volume: f32,
+    clips: HashMap<String, Rc<AudioClip>>,
+    voices: Vec<Voice>,
+}
+
+impl Default for Mixer {
+    fn default() -> Self {
+        Self {
+            attenuation: Attenuation::default(),
+            master_volume: 1.0,
+            clips: HashMap::new(),
+            voices: Vec::new(),
+        }
+    }
+}
+
+impl Mixer {
+    pub fn add_clip(&mut self, name: impl Into<String>, clip: AudioClip) {
+        self.clips.insert(name.into(), Rc::new(clip));
+    }
+
+    /// Starts playing a clip, returning false if no clip has that name
+    pub fn play(
+        &mut self,
+        clip: &str,
+        node_index: Option<usize>,
+        volume: f32,
+        looping: bool,
+    ) -> bool {
+        let Some(clip) = self.clips.get(clip) else {
+            return false;
+        };
+        if clip.number_of_frames() == 0 {
+            return true;
+        }
+        self.voices.push(Voice {
+            clip: clip.clone(),
+            node_index,
+            volume,
+            looping,
+            cursor: 0.0,
+            gains: None,
+        });
+        true
+    }
+
+    /// Stops the sounds playing from a node, or every sound when there is no node
+    pub fn stop(&mut self, node_index: Option<usize>) {
+        match node_index {
+            Some(node_index) => self
+                .voices
+                .retain(|voice| voice.node_index != Some(node_index)),
+            None => self.voices.clear(),
+        }
+    }
+
+    pub fn number_of_voices(&self) -> usize {
+        self.voices.len()
+    }
+
+    pub fn emitter_node_indices(&self) -> impl Iterator<Item = usize> + '_ {
+        self.voices.iter().filter_map(|voice| voice.node_index)
+    }
+
+    /// Mixes the next frames at the output's sample rate.
+    /// Emitters are heard from the listener's transform, and emitters without a position are silent.
+    pub fn render(
+        &mut self,
+        frames: &mut [[f32; 2]],
+        sample_rate: u32,
+        listener: &nalgebra_glm::Mat4,
+        emitter_position: impl Fn(usize) -> Option<nalgebra_glm::Vec3>,
+    ) {
+        frames.fill([0.0, 0.0]);
+        let inverse_listener = nalgebra_glm::inverse(listener);
+        let number_of_frames = frames.len().max(1) as f32;
+        for voice in self.voices.iter_mut() {
+            let spatial = voice.node_index.is_some();
+            let target_gains = match voice.node_index {
+                None => [1.0, 1.0],
+                Some(node_index) => emitter_position(node_index).map_or([0.0, 0.0], |position| {
+                    let relative = inverse_listener * position.push(1.0);
+                    self.attenuation.stereo_gains(&relative.xyz())
+                }),
+            }
+            .map(|gain| gain * voice.volume * self.master_volume);
+            // Gains ramp across the block to avoid clicks as emitters move
+            let start_gains = voice.gains.unwrap_or(target_gains);
+            voice.gains = Some(target_gains);
+
+            let clip = voice.clip.clone();
+            let step = clip.sample_rate as f64 / sample_rate as f64;
+            let clip_frames = clip.number_of_frames();
+            for (index, frame) in frames.iter_mut().enumerate() {
+                if voice.cursor >= clip_frames as f64 {
+                    break;
+                }
+                let position = voice.cursor.floor() as usize;
+                let fraction = (voice.cursor - position as f64) as f32;
+                let next = match position + 1 {
+                    next if next < clip_frames => Some(next),
+                    _ if voice.looping => Some(0),
+                    _ => None,
+                };
+                let sample = |channel: u16| {
+                    let current = clip.sample(position, channel);
+                    let next = next.map_or(0.0, |next| clip.sample(next, channel));
+                    current + (next - current) * fraction
+                };
+                // Spatial sounds come from a single point, so their channels are mixed down
+                let (left, right) = if spatial {
+                    let mono = (0..clip.channels).map(sample).sum::<f32>() / clip.channels as f32;
+                    (mono, mono)
+                } else {
+                    (sample(0), sample(1))
+                };
+                let t = index as f32 / number_of_frames;
+                let gain = |channel: usize| {
+                    start_gains[channel] + (target_gains[channel] - start_gains[channel]) * t
+                };
+                frame[0] += left * gain(0);
+                frame[1] += right * gain(1);
+
+                voice.cursor += step;
+                if voice.looping && voice.cursor >= clip_frames as f64 {
+                    voice.cursor -= clip_frames as f64;
+                }
+            }
+        }
+        self.voices
+            .retain(|voice| voice.cursor < voice.clip.number_of_frames() as f64);
+        frames
+            .iter_mut()
+            .flatten()
+            .for_each(|sample| *sample = sample.clamp(-1.0, 1.0));
+    }
+}
+
+/// Plays sounds in response to `EngineCommand::PlaySound` and `EngineCommand::StopSounds`,
+/// hearing emitters on a scene's nodes from the scene's camera
+pub struct AudioService<O, C, E>
+where
+    O: OutputDevice,
+    C: Clone + fmt::Debug + 'static,
+    E: Clone + fmt::Debug + 'static,
+{
+    pub mixer: Mixer,
+    pub scene_index: usize,
+    world: Rc<RefCell<World>>,
+    output: O,
+    client: Client<C, E>,
+    subscribed: bool,
+    last_update: Option<crate::Instant>,
+    pending_frames: f64,
+}
+
+impl<O, C, E> AudioService<O, C, E>
+where
+    O: OutputDevice,
+    C: Clone + fmt::Debug + 'static,
+    E: Clone + fmt::Debug + 'static,
+{
+    pub fn new(world: Rc<RefCell<World>>, scene_index: usize, output: O) -> Self {
+        Self {
+            mixer: Mixer::default(),
+            scene_index,
+            world,
+            output,
+            client: Client::default(),
+            subscribed: false,
+            last_update: None,
+            pending_frames: 0.0,
+        }
+    }
+
+    pub fn output(&self) -> &O {
+        &self.output
+    }
+
+    pub fn output_mut(&mut self) -> &mut O {
+        &mut self.output
+    }
+
+    /// Mixes the given number of frames and writes them to the output
+    pub fn render(&mut self, number_of_frames: usize) {
+        let world = self.world.borrow();
+        let scene = &world.scenes[self.scene_index];
+        let listener = scene
+            .default_camera_graph_node_index
+            .filter(|graph_node_index| scene.graph.contains_node(*graph_node_index))
+            .map_or(nalgebra_glm::Mat4::identity(), |graph_node_index| {
+                world.global_transform(&scene.graph, graph_node_index)
+            });
+        let emitters = self.mixer.emitter_node_indices().collect::<Vec<_>>();
+        let positions = if emitters.is_empty() {
+            HashMap::new()
+        } else {
+            world
+                .depth_first(self.scene_index)
+                .filter(|scene_node| emitters.contains(&scene_node.node_index))
+                .map(|scene_node| {
+                    let transform = scene_node.global_transform;
+                    (
+                        scene_node.node_index,
+                        nalgebra_glm::vec3(transform.m14, transform.m24, transform.m34),
+                    )
+                })
+                .collect::<HashMap<_, _>>()
+        };
+
+        let mut frames = vec![[0.0, 0.0]; number_of_frames];
+        self.mixer.render(
+            &mut frames,
+            self.output.sample_rate(),
+            &listener,
+            |node_index| positions.get(&node_index).copied(),
+        );
+        self.output.write(&frames);
+    }
+}
+
+impl<O, C, E> Service<C, E> for AudioService<O, C, E>
+where
+    O: OutputDevice,
+    C: Clone + fmt::Debug + 'static,
+    E: Clone + fmt::Debug + 'static,
+{
+    fn update(&mut self, broker: &mut Broker<C, E>) {
+        if !self.subscribed {
+            self.client
+                .subscribe_to_topic(contract::ENGINE_COMMAND_TOPIC, broker);
+            self.subscribed = true;
+        }
+        while let Some(message) = self.client.next_message() {
+            match message {
+                EngineMessage::EngineCommand {
+                    command:
+                        EngineCommand::PlaySound {
+                            clip,
+                            node_index,
+                            volume,
+                            looping,
+                        },
+                } => {
+                    if !self.mixer.play(&clip, node_index, volume, looping) {
+                        log::warn!("No audio clip named {clip}");
+                    }
+                }
+                EngineMessage::EngineCommand {
+                    command: EngineCommand::StopSounds { node_index },
+                } => self.mixer.stop(node_index),
+                _ => {}
+            }
+        }
+
+        let now = crate::Instant::now();
+        let elapsed = self
+            .last_update
+            .map_or(0.0, |last_update| (now - last_update).as_secs_f64());
+        self.last_update = Some(now);
+        // Fractions of a frame carry over so that the output keeps pace with real time
+        self.pending_frames += elapsed.min(MAX_UPDATE_SECONDS) * self.output.sample_rate() as f64;
+        let number_of_frames = self.pending_frames.floor();
+        self.pending_frames -= number_of_frames;
+        if number_of_frames > 0.0 {
+            self.render(number_of_frames as usize);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AudioClip, AudioError, AudioService, Mixer, NullOutput};
+    use contract::{EngineCommand, EngineMessage};
+    use service::{Broker, Service};
+    use std::{cell::RefCell, rc::Rc};
+    use world::{Scene, World};
+
+    fn wav(format: u16, channels: u16, sample_rate: u32, bits: u16, data: &[u8]) -> Vec<u8> {
+        let mut bytes = b"RIFF".to_vec();
+        bytes.extend((4 + 24 + 8 + data.len() as u32).to_le_bytes());
+        bytes.extend(b"WAVEfmt ");
+        bytes.extend(16_u32.to_le_bytes());
+        bytes.extend(format.to_le_bytes());
+        bytes.extend(channels.to_le_bytes());
+        bytes.extend(sample_rate.to_le_bytes());
+        let block_align = channels * bits / 8;
+        bytes.extend((sample_rate * block_align as u32).to_le_bytes());
+        bytes.extend(block_align.to_le_bytes());
+        bytes.extend(bits.to_le_bytes());
+        bytes.extend(b"data");
+        bytes.extend((data.len() as u32).to_le_bytes());
+        bytes.extend(data);
+        bytes
+    }
+
+    // A second of a constant signal, which makes gains easy to read from the output
+    fn constant_clip(sample_rate: u32) -> AudioClip {
+        AudioClip {
+            sample_rate,
+            channels: 1,
+            samples: vec![0.5; sample_rate as usize],
+        }
+    }
+
+    #[test]
+    fn wav_files_are_decoded() {
+        let data = [0_i16, 16384, -32768, 32767]
+            .iter()
+            .flat_map(|sample| sample.to_le_bytes())
+            .collect::<Vec<_>>();
+        let clip = AudioClip::decode(&wav(1, 2, 44100, 16, &data)).unwrap();
+        assert_eq!((clip.sample_rate, clip.channels), (44100, 2));
+        assert_eq!(clip.number_of_frames(), 2);
+        assert_eq!(clip.samples[..3], [0.0, 0.5, -1.0]);
+
+        let data = [-0.25_f32, 0.75]
+            .iter()
+            .flat_map(|sample| sample.to_le_bytes())
+            .collect::<Vec<_>>();
+        let clip = AudioClip::decode(&wav(3, 1, 8000, 32, &data)).unwrap();
+        assert_eq!(clip.samples, vec![-0.25, 0.75]);
+
+        let clip = AudioClip::decode(&wav(1, 1, 8000, 24, &[0, 0, 0x80, 0, 0, 0x40])).unwrap();
+        assert_eq!(clip.samples, vec![-1.0, 0.5]);
+
+        assert!(matches!(
+            AudioClip::decode(b"OggS\0\x02"),
+            Err(AudioError::Vorbis(_))
+        ));
+        assert!(matches!(
+            AudioClip::decode(&wav(2, 1, 8000, 4, &[])),
+            Err(AudioError::UnsupportedFormat(_))
+        ));
+        assert!(matches!(
+            AudioClip::decode(&wav(1, 1, 8000, 16, &[])[..30]),
+            Err(AudioError::Truncated)
+        ));
+
+        // A chunk claiming to reach past the end of memory
+        let mut oversized = wav(1, 1, 8000, 16, &[]);
+        let data_size = oversized.len() - 4;
+        oversized[data_size..].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(
+            AudioClip::decode(&oversized),
+            Err(AudioError::Truncated)
+        ));
+
+        let silent = AudioClip {
+            channels: 0,
+            ..constant_clip(8000)
+        };
+        assert_eq!(silent.sample(0, 1), 0.0);
+    }
+
+    #[test]
+    fn mixer_attenuates_and_pans_emitters() {
+        let mut mixer = Mixer::default();
+        mixer.add_clip("tone", constant_clip(48000));
+        let listener = nalgebra_glm::Mat4::identity();
+        let mut render = |position: nalgebra_glm::Vec3| {
+            mixer.stop(None);
+            assert!(mixer.play("tone", Some(0), 1.0, false));
+            let mut frames = vec![[0.0, 0.0]; 16];
+            mixer.render(&mut frames, 48000, &listener, |_| Some(position));
+            frames[8]
+        };
+
+        let [left, right] = render(nalgebra_glm::vec3(0.0, 0.0, -1.0));
+        assert!((left - right).abs() < 1e-6);
+        // Constant power panning keeps the total power of a centered sound
+        assert!((left * left + right * right - 0.25).abs() < 1e-5);
+
+        let [left, right] = render(nalgebra_glm::vec3(5.0, 0.0, 0.0));
+        assert!(right > 0.0 && left.abs() < 1e-6);
+        assert!((right - 0.5 / 5.0).abs() < 1e-5);
+
+        let [near, _] = render(nalgebra_glm::vec3(-2.0, 0.0, 0.0));
+        let [far, _] = render(nalgebra_glm::vec3(-8.0, 0.0, 0.0));
+        assert!((near / far - 4.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn clips_are_resampled_and_finish() {
+        let mut mixer = Mixer::default();
+        mixer.add_clip("tone", constant_clip(24000));
+        mixer.play("tone", None, 1.0, false);
+        let mut frames = vec![[0.0, 0.0]; 48000 + 100];
+        mixer.render(&mut frames, 48000, &nalgebra_glm::Mat4::identity(), |_| {
+            None
+        });
+        // Playing at half the output rate makes the clip last twice as long
+        assert_eq!(frames[47990], [0.5, 0.5]);
+        assert_eq!(frames[48050], [0.0, 0.0]);
+        assert_eq!(mixer.number_of_voices(), 0);
+
+        mixer.play("tone", None, 1.0, true);
+        mixer.render(&mut frames, 48000, &nalgebra_glm::Mat4::identity(), |_| {
+            None
+        });
+        assert_eq!(frames[48050], [0.5, 0.5]);
+        assert_eq!(mixer.number_of_voices(), 1);
+    }
+
+    #[test]
+    fn service_plays_sounds_from_nodes_relative_to_the_camera() {
+        let mut world = World::default();
+        let mut scene = Scene::default();
+        let camera_node_index = world.add_node();
+        world.add_camera_to_node(camera_node_index);
+        world.transforms[camera_node_index].translation = nalgebra_glm::vec3(10.0, 0.0, 0.0);
+        scene.default_camera_graph_node_index = Some(scene.graph.add_node(camera_node_index));
+        let emitter_node_index = world.add_node();
+        world.transforms[emitter_node_index].translation = nalgebra_glm::vec3(12.0, 0.0, 0.0);
+        scene.graph.add_node(emitter_node_index);
+        world.scenes.push(scene);
+
+        let mut service =
+            AudioService::<_, (), ()>::new(Rc::new(RefCell::new(world)), 0, NullOutput::default());
+        service.mixer.add_clip("tone", constant_clip(48000));
+        let mut broker = Broker::<(), ()>::default();
+        service.update(&mut broker);
+        broker.publish(
+            contract::ENGINE_COMMAND_TOPIC,
+            EngineMessage::EngineCommand {
+                command: EngineCommand::PlaySound {
+                    clip: "tone".to_string(),
+                    node_index: Some(emitter_node_index),
+                    volume: 1.0,
+                    looping: true,
+                },
+            },
+        );
+        service.update(&mut broker);
+        assert_eq!(service.mixer.number_of_voices(), 1);
+
+        service.output_mut().frames.clear();
+        service.render(64);
+        let [left, right] = service.output().frames[32];
+        // The emitter is two units to the camera's right
+        assert!(left.abs() < 1e-6);
+        assert!((right - 0.25).abs() < 1e-5);
+
+        broker.publish(
+            contract::ENGINE_COMMAND_TOPIC,
+            EngineMessage::EngineCommand {
+                command: EngineCommand::StopSounds {
+                    node_index: Some(emitter_node_index),
+                },
+            },
+        );
+        service.update(&mut broker);
+        assert_eq!(service.mixer.number_of_voices(), 0);
+    }
+}
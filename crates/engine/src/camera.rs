@@ -0,0 +1,698 @@
+use contract::{EngineCommand, EngineEvent, EngineMessage, InputEvent, Key, MouseButton};
+use service::{client::Client, Broker, Service};
+use std::{cell::RefCell, collections::HashSet, fmt, rc::Rc};
+use world::{AxisAlignedBoundingBox, Orientation, Transform, World};
+
+/// Input accumulated from the bus between two controller updates
+#[derive(Default, Debug, Clone)]
+pub struct InputState {
+    pub keys: HashSet<Key>,
+    pub mouse_buttons: HashSet<MouseButton>,
+    pub mouse_delta: nalgebra_glm::Vec2,
+    pub scroll_delta: f32,
+}
+
+impl InputState {
+    pub fn handle_event(&mut self, event: &InputEvent) {
+        match *event {
+            InputEvent::KeyPressed { key } => {
+                self.keys.insert(key);
+            }
+            InputEvent::KeyReleased { key } => {
+                self.keys.remove(&key);
+            }
+            InputEvent::MouseButtonPressed { button } => {
+                self.mouse_buttons.insert(button);
+            }
+            InputEvent::MouseButtonReleased { button } => {
+                self.mouse_buttons.remove(&button);
+            }
+            InputEvent::MouseMoved { delta_x, delta_y } => {
+                self.mouse_delta += nalgebra_glm::vec2(delta_x, delta_y);
+            }
+            InputEvent::MouseScrolled { delta } => self.scroll_delta += delta,
+            InputEvent::FocusLost => *self = Self::default(),
+        }
+    }
+
+    pub fn is_key_down(&self, key: Key) -> bool {
+        self.keys.contains(&key)
+    }
+
+    pub fn is_mouse_button_down(&self, button: MouseButton) -> bool {
+        self.mouse_buttons.contains(&button)
+    }
+
+    /// Clears the motion deltas once they have been consumed, keeping held keys and buttons
+    pub fn end_frame(&mut self) {
+        self.mouse_delta = nalgebra_glm::Vec2::zeros();
+        self.scroll_delta = 0.0;
+    }
+
+    // Movement along the camera's right, up and forward axes, from -1 to 1 on each
+    fn movement(&self) -> nalgebra_glm::Vec3 {
+        let axis = |positive: Key, negative: Key| {
+            (self.is_key_down(positive) as i32 - self.is_key_down(negative) as i32) as f32
+        };
+        nalgebra_glm::vec3(
+            axis(Key::D, Key::A),
+            axis(Key::E, Key::Q),
+            axis(Key::W, Key::S),
+        )
+    }
+}
+
+pub trait CameraController {
+    /// Advances the controller and returns the camera's new global transform
+    fn update(&mut self, input: &InputState, delta_time: f32) -> Transform;
+
+    /// Moves the camera so that the box fills the view
+    fn focus(&mut self, aabb: &AxisAlignedBoundingBox, y_fov_rad: f32);
+
+    /// Starts from the camera's current global transform, without smoothing towards it
+    fn set_transform(&mut self, transform: &Transform);
+}
+
+/// Orbits around a target using `Orientation`.
+/// Dragging with the left mouse button rotates, the middle mouse button pans and scrolling zooms.
+#[derive(Debug, Clone)]
+pub struct OrbitController {
+    pub target: Orientation,
+    pub current: Orientation,
+    pub rotate_speed: f32,
+    pub pan_speed: f32,
+    pub zoom_speed: f32,
+    pub smoothing: f32,
+}
+
+impl Default for OrbitController {
+    fn default() -> Self {
+        Self {
+            target: Orientation::default(),
+            current: Orientation::default(),
+            rotate_speed: 0.005,
+            pan_speed: 0.001,
+            zoom_speed: 0.5,
+            smoothing: 15.0,
+        }
+    }
+}
+
+impl CameraController for OrbitController {
+    fn update(&mut self, input: &InputState, delta_time: f32) -> Transform {
+        if input.is_mouse_button_down(MouseButton::Left) {
+            self.target
+                .rotate(&(-input.mouse_delta * self.rotate_speed));
+        }
+        if input.is_mouse_button_down(MouseButton::Middle) {
+            let pan = nalgebra_glm::vec2(-input.mouse_delta.x, input.mouse_delta.y);
+            self.target
+                .pan(&(pan * self.pan_speed * self.target.radius));
+        }
+        if input.scroll_delta != 0.0 {
+            self.target
+                .zoom(input.scroll_delta * self.zoom_speed * self.target.radius.sqrt());
+        }
+
+        let t = smoothing_factor(self.smoothing, delta_time);
+        self.current.direction =
+            nalgebra_glm::lerp(&self.current.direction, &self.target.direction, t);
+        self.current.offset = nalgebra_glm::lerp(&self.current.offset, &self.target.offset, t);
+        self.current.radius = nalgebra_glm::lerp_scalar(self.current.radius, self.target.radius, t);
+
+        let position = self.current.position();
+        Transform {
+            translation: position,
+            rotation: look_rotation(&(self.current.offset - position)),
+            ..Default::default()
+        }
+    }
+
+    fn focus(&mut self, aabb: &AxisAlignedBoundingBox, y_fov_rad: f32) {
+        let radius = framing_distance(aabb, y_fov_rad);
+        self.target.offset = aabb.center();
+        self.target.max_radius = self.target.max_radius.max(radius);
+        self.target.min_radius = self.target.min_radius.min(radius);
+        self.target.radius = radius;
+    }
+
+    /// Keeps the orbit radius and moves the target in front of the camera
+    fn set_transform(&mut self, transform: &Transform) {
+        let forward = nalgebra_glm::quat_rotate_vec3(
+            &transform.rotation.normalize(),
+            &-nalgebra_glm::Vec3::z(),
+        );
+        let direction = -forward.normalize();
+        self.target.offset = transform.translation + forward * self.target.radius;
+        self.target.direction = nalgebra_glm::vec2(
+            direction.x.atan2(direction.z),
+            direction
+                .y
+                .clamp(-1.0, 1.0)
+                .acos()
+                .clamp(10_f32.to_radians(), 170_f32.to_radians()),
+        );
+        self.current = self.target.clone();
+    }
+}
+
+/// Flies freely in the direction the camera is looking.
+/// Looking around requires holding the right mouse button, `WASD` moves, `Q` and `E` lower and raise,
+/// `Shift` moves faster and scrolling changes the speed.
+#[derive(Debug, Clone)]
+pub struct FlyController {
+    pub position: nalgebra_glm::Vec3,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub speed: f32,
+    pub boost: f32,
+    pub look_speed: f32,
+    pub smoothing: f32,
+    current_position: nalgebra_glm::Vec3,
+    current_yaw: f32,
+    current_pitch: f32,
+}
+
+impl Default for FlyController {
+    fn default() -> Self {
+        Self::new(nalgebra_glm::vec3(0.0, 0.0, 5.0), 0.0, 0.0)
+    }
+}
+
+impl FlyController {
+    pub fn new(position: nalgebra_glm::Vec3, yaw: f32, pitch: f32) -> Self {
+        Self {
+            position,
+            yaw,
+            pitch,
+            speed: 5.0,
+            boost: 4.0,
+            look_speed: 0.003,
+            smoothing: 15.0,
+            current_position: position,
+            current_yaw: yaw,
+            current_pitch: pitch,
+        }
+    }
+
+    fn look(&mut self, input: &InputState) {
+        self.yaw -= input.mouse_delta.x * self.look_speed;
+        self.pitch =
+            (self.pitch - input.mouse_delta.y * self.look_speed).clamp(-MAX_PITCH, MAX_PITCH);
+    }
+
+    fn speed(&self, input: &InputState) -> f32 {
+        if input.is_key_down(Key::Shift) {
+            self.speed * self.boost
+        } else {
+            self.speed
+        }
+    }
+
+    fn smoothed_transform(&mut self, delta_time: f32) -> Transform {
+        let t = smoothing_factor(self.smoothing, delta_time);
+        self.current_position = nalgebra_glm::lerp(&self.current_position, &self.position, t);
+        self.current_yaw = nalgebra_glm::lerp_scalar(self.current_yaw, self.yaw, t);
+        self.current_pitch = nalgebra_glm::lerp_scalar(self.current_pitch, self.pitch, t);
+        Transform {
+            translation: self.current_position,
+            rotation: look_rotation(&forward(self.current_yaw, self.current_pitch)),
+            ..Default::default()
+        }
+    }
+}
+
+impl CameraController for FlyController {
+    fn update(&mut self, input: &InputState, delta_time: f32) -> Transform {
+        if input.is_mouse_button_down(MouseButton::Right) {
+            self.look(input);
+        }
+        self.speed = (self.speed * 1.1_f32.powf(input.scroll_delta)).max(0.01);
+
+        let movement = input.movement();
+        let forward = forward(self.yaw, self.pitch);
+        let right = right(self.yaw);
+        let direction =
+            right * movement.x + nalgebra_glm::Vec3::y() * movement.y + forward * movement.z;
+        if direction != nalgebra_glm::Vec3::zeros() {
+            self.position += direction.normalize() * self.speed(input) * delta_time;
+        }
+        self.smoothed_transform(delta_time)
+    }
+
+    fn focus(&mut self, aabb: &AxisAlignedBoundingBox, y_fov_rad: f32) {
+        self.position =
+            aabb.center() - forward(self.yaw, self.pitch) * framing_distance(aabb, y_fov_rad);
+    }
+
+    fn set_transform(&mut self, transform: &Transform) {
+        let forward = nalgebra_glm::quat_rotate_vec3(
+            &transform.rotation.normalize(),
+            &-nalgebra_glm::Vec3::z(),
+        )
+        .normalize();
+        self.position = transform.translation;
+        self.yaw = (-forward.x).atan2(-forward.z);
+        self.pitch = forward
+            .y
+            .clamp(-1.0, 1.0)
+            .asin()
+            .clamp(-MAX_PITCH, MAX_PITCH);
+        self.current_position = self.position;
+        self.current_yaw = self.yaw;
+        self.current_pitch = self.pitch;
+    }
+}
+
+/// Walks on the horizontal plane at a fixed eye height, always looking with the mouse.
+/// `WASD` moves and `Shift` runs. Mouse motion only arrives without a held button
+/// while the app grabs the cursor, see `State::cursor_grabbed`.
+#[derive(Debug, Clone, Default)]
+pub struct FirstPersonController {
+    pub fly: FlyController,
+}
+
+impl FirstPersonController {
+    pub fn new(position: nalgebra_glm::Vec3, yaw: f32) -> Self {
+        Self {
+            fly: FlyController::new(position, yaw, 0.0),
+        }
+    }
+}
+
+impl CameraController for FirstPersonController {
+    fn update(&mut self, input: &InputState, delta_time: f32) -> Transform {
+        let fly = &mut self.fly;
+        fly.look(input);
+
+        let movement = input.movement();
+        let forward = forward(fly.yaw, 0.0);
+        let direction = right(fly.yaw) * movement.x + forward * movement.z;
+        if direction != nalgebra_glm::Vec3::zeros() {
+            fly.position += direction.normalize() * fly.speed(input) * delta_time;
+        }
+        fly.smoothed_transform(delta_time)
+    }
+
+    /// Turns to face the box without leaving the ground
+    fn focus(&mut self, aabb: &AxisAlignedBoundingBox, _y_fov_rad: f32) {
+        let fly = &mut self.fly;
+        let offset = aabb.center() - fly.position;
+        if offset.norm() <= f32::EPSILON {
+            return;
+        }
+        let offset = offset.normalize();
+        // Mouse look lets the yaw wind past a full turn, so the smoothing
+        // would spin the camera around unless the target is the nearest equivalent angle
+        let yaw = (-offset.x).atan2(-offset.z);
+        let turn = (yaw - fly.current_yaw + std::f32::consts::PI).rem_euclid(std::f32::consts::TAU)
+            - std::f32::consts::PI;
+        fly.yaw = fly.current_yaw + turn;
+        fly.pitch = offset.y.asin().clamp(-MAX_PITCH, MAX_PITCH);
+    }
+
+    fn set_transform(&mut self, transform: &Transform) {
+        self.fly.set_transform(transform);
+    }
+}
+
+const MAX_PITCH: f32 = 89.0 * std::f32::consts::PI / 180.0;
+
+/// A camera with zero yaw and pitch looks down -Z
+fn forward(yaw: f32, pitch: f32) -> nalgebra_glm::Vec3 {
+    nalgebra_glm::vec3(
+        -yaw.sin() * pitch.cos(),
+        pitch.sin(),
+        -yaw.cos() * pitch.cos(),
+    )
+}
+
+fn right(yaw: f32) -> nalgebra_glm::Vec3 {
+    nalgebra_glm::vec3(yaw.cos(), 0.0, -yaw.sin())
+}
+
+/// The rotation that points a camera's -Z axis along `direction` with +Y kept upwards,
+/// matching how `world::create_camera_matrices` builds the view
+fn look_rotation(direction: &nalgebra_glm::Vec3) -> nalgebra_glm::Quat {
+    let forward = direction.normalize();
+    let mut right = forward.cross(&nalgebra_glm::Vec3::y());
+    if right.norm() <= f32::EPSILON {
+        // Looking straight up or down
+        right = nalgebra_glm::Vec3::x();
+    }
+    let right = right.normalize();
+    let up = right.cross(&forward);
+    nalgebra_glm::mat3_to_quat(&nalgebra_glm::Mat3::from_columns(&[right, up, -forward]))
+}
+
+/// The distance at which a sphere around the box fits the vertical field of view
+fn framing_distance(aabb: &AxisAlignedBoundingBox, y_fov_rad: f32) -> f32 {
+    let radius = (aabb.extents().norm() / 2.0).max(0.01);
+    radius / (y_fov_rad / 2.0).sin()
+}
+
+/// Exponential smoothing that converges at the same rate regardless of the frame rate
+fn smoothing_factor(sharpness: f32, delta_time: f32) -> f32 {
+    if sharpness <= 0.0 {
+        1.0
+    } else {
+        1.0 - (-sharpness * delta_time).exp()
+    }
+}
+
+/// Drives the default camera of a scene from input events on the bus
+/// and frames nodes when it receives `EngineCommand::FocusNode`
+pub struct CameraControllerService<T, C, E>
+where
+    T: CameraController,
+    C: Clone + fmt::Debug + 'static,
+    E: Clone + fmt::Debug + 'static,
+{
+    pub controller: T,
+    pub scene_index: usize,
+    world: Rc<RefCell<World>>,
+    input: InputState,
+    client: Client<C, E>,
+    subscribed: bool,
+    seeded: bool,
+    last_update: Option<crate::Instant>,
+}
+
+impl<T, C, E> CameraControllerService<T, C, E>
+where
+    T: CameraController,
+    C: Clone + fmt::Debug + 'static,
+    E: Clone + fmt::Debug + 'static,
+{
+    pub fn new(world: Rc<RefCell<World>>, scene_index: usize, controller: T) -> Self {
+        Self {
+            controller,
+            scene_index,
+            world,
+            input: InputState::default(),
+            client: Client::default(),
+            subscribed: false,
+            seeded: false,
+            last_update: None,
+        }
+    }
+
+    /// Applies the input received so far and moves the camera.
+    /// The first update starts the controller from where the scene places the camera.
+    pub fn update_camera(&mut self, delta_time: f32) {
+        if !self.seeded {
+            let world = self.world.borrow();
+            let scene = &world.scenes[self.scene_index];
+            let camera_graph_node_index = scene
+                .default_camera_graph_node_index
+                .filter(|graph_node_index| scene.graph.contains_node(*graph_node_index));
+            if let Some(camera_graph_node_index) = camera_graph_node_index {
+                let transform =
+                    Transform::from(world.global_transform(&scene.graph, camera_graph_node_index));
+                self.controller.set_transform(&transform);
+                self.seeded = true;
+            }
+        }
+        let transform = self.controller.update(&self.input, delta_time);
+        self.input.end_frame();
+        self.apply(transform);
+    }
+
+    /// Frames a graph node together with its descendants that have bounding boxes
+    pub fn focus(&mut self, graph_node_index: petgraph::graph::NodeIndex) {
+        let world = self.world.borrow();
+        let scene = &world.scenes[self.scene_index];
+        let Some(aabb) = petgraph::visit::Walker::iter(
+            petgraph::visit::Dfs::new(&scene.graph, graph_node_index),
+            &scene.graph,
+        )
+        .filter_map(|graph_node_index| world::world_space_aabb(&world, scene, graph_node_index))
+        .reduce(|mut aabb, other| {
+            aabb.expand_to_include(&other);
+            aabb
+        }) else {
+            return;
+        };
+        let y_fov_rad = scene
+            .default_camera_graph_node_index
+            .and_then(|camera_graph_node_index| scene.graph.node_weight(camera_graph_node_index))
+            .and_then(|node_index| world.nodes[*node_index].camera_index)
+            .and_then(
+                |camera_index| match &world.cameras[camera_index].projection {
+                    world::Projection::Perspective(camera) => Some(camera.y_fov_rad),
+                    world::Projection::Orthographic(_) => None,
+                },
+            )
+            .unwrap_or(90_f32.to_radians());
+        self.controller.focus(&aabb, y_fov_rad);
+    }
+
+    fn apply(&self, transform: Transform) {
+        let mut world = self.world.borrow_mut();
+        let scene = &world.scenes[self.scene_index];
+        let Some(camera_graph_node_index) = scene.default_camera_graph_node_index else {
+            return;
+        };
+        let Some(&camera_node_index) = scene.graph.node_weight(camera_graph_node_index) else {
+            return;
+        };
+        // Controllers work in world space, so undo any transform inherited from the parent
+        let local_transform = match world.parent(self.scene_index, camera_graph_node_index) {
+            Some(parent) => {
+                let parent_transform = world.global_transform(&scene.graph, parent);
+                Transform::from(nalgebra_glm::inverse(&parent_transform) * transform.matrix())
+            }
+            None => transform,
+        };
+        let transform_index = world.nodes[camera_node_index].transform_index;
+        world.transforms[transform_index] = local_transform;
+    }
+}
+
+impl<T, C, E> Service<C, E> for CameraControllerService<T, C, E>
+where
+    T: CameraController,
+    C: Clone + fmt::Debug + 'static,
+    E: Clone + fmt::Debug + 'static,
+{
+    fn update(&mut self, broker: &mut Broker<C, E>) {
+        if !self.subscribed {
+            self.client
+                .subscribe_to_topic(contract::ENGINE_EVENT_TOPIC, broker);
+            self.client
+                .subscribe_to_topic(contract::ENGINE_COMMAND_TOPIC, broker);
+            self.subscribed = true;
+        }
+        while let Some(message) = self.client.next_message() {
+            match message {
+                EngineMessage::EngineEvent {
+                    event: EngineEvent::Input { event },
+                } => self.input.handle_event(&event),
+                EngineMessage::EngineCommand {
+                    command:
+                        EngineCommand::FocusNode {
+                            scene_index,
+                            graph_node_index,
+                        },
+                } if scene_index == self.scene_index => {
+                    self.focus(petgraph::graph::NodeIndex::new(graph_node_index))
+                }
+                _ => {}
+            }
+        }
+
+        let now = crate::Instant::now();
+        let delta_time = self
+            .last_update
+            .map_or(0.0, |last_update| (now - last_update).as_secs_f32());
+        self.last_update = Some(now);
+        self.update_camera(delta_time);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        forward, CameraController, CameraControllerService, FirstPersonController, FlyController,
+        InputState, OrbitController,
+    };
+    use contract::{InputEvent, Key, MouseButton};
+    use std::{cell::RefCell, rc::Rc};
+    use world::{AxisAlignedBoundingBox, Scene, World};
+
+    fn camera_forward(transform: &world::Transform) -> nalgebra_glm::Vec3 {
+        nalgebra_glm::quat_rotate_vec3(&transform.rotation, &-nalgebra_glm::Vec3::z())
+    }
+
+    fn unit_box_at(center: nalgebra_glm::Vec3) -> AxisAlignedBoundingBox {
+        AxisAlignedBoundingBox::new(
+            center - nalgebra_glm::vec3(0.5, 0.5, 0.5),
+            center + nalgebra_glm::vec3(0.5, 0.5, 0.5),
+        )
+    }
+
+    #[test]
+    fn orbit_controller_looks_at_its_target() {
+        let mut controller = OrbitController {
+            smoothing: 0.0,
+            ..Default::default()
+        };
+        let mut input = InputState::default();
+        input.handle_event(&InputEvent::MouseButtonPressed {
+            button: MouseButton::Left,
+        });
+        input.handle_event(&InputEvent::MouseMoved {
+            delta_x: 100.0,
+            delta_y: -40.0,
+        });
+        let transform = controller.update(&input, 1.0 / 60.0);
+        let to_target = (controller.target.offset - transform.translation).normalize();
+        assert!(nalgebra_glm::distance(&camera_forward(&transform), &to_target) < 1e-4);
+        assert!(
+            (nalgebra_glm::length(&transform.translation) - controller.target.radius).abs() < 1e-4
+        );
+    }
+
+    #[test]
+    fn smoothing_converges_on_the_target() {
+        let mut controller = FlyController::default();
+        let mut input = InputState::default();
+        input.handle_event(&InputEvent::KeyPressed { key: Key::W });
+        let first = controller.update(&input, 0.1);
+        assert!(first.translation.z > controller.position.z);
+        input.handle_event(&InputEvent::KeyReleased { key: Key::W });
+        let mut transform = first;
+        for _ in 0..100 {
+            transform = controller.update(&input, 0.1);
+        }
+        assert!(nalgebra_glm::distance(&transform.translation, &controller.position) < 1e-4);
+        assert!((controller.position.z - 4.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn first_person_controller_stays_on_the_ground() {
+        let mut controller = FirstPersonController::new(nalgebra_glm::vec3(0.0, 1.8, 0.0), 0.0);
+        controller.fly.smoothing = 0.0;
+        let mut input = InputState::default();
+        input.handle_event(&InputEvent::MouseMoved {
+            delta_x: 0.0,
+            delta_y: -300.0,
+        });
+        input.handle_event(&InputEvent::KeyPressed { key: Key::W });
+        let transform = controller.update(&input, 1.0);
+        assert!(controller.fly.pitch > 0.5);
+        assert_eq!(transform.translation.y, 1.8);
+        assert!(transform.translation.z < 0.0);
+    }
+
+    #[test]
+    fn focusing_frames_the_box() {
+        let target = unit_box_at(nalgebra_glm::vec3(10.0, 0.0, 0.0));
+        let y_fov_rad = 90_f32.to_radians();
+
+        let mut orbit = OrbitController {
+            smoothing: 0.0,
+            ..Default::default()
+        };
+        orbit.focus(&target, y_fov_rad);
+        let transform = orbit.update(&InputState::default(), 0.0);
+        let distance = nalgebra_glm::distance(&transform.translation, &target.center());
+        assert!((distance - super::framing_distance(&target, y_fov_rad)).abs() < 1e-4);
+
+        let mut fly = FlyController::default();
+        fly.focus(&target, y_fov_rad);
+        let to_target = (target.center() - fly.position).normalize();
+        assert!(nalgebra_glm::distance(&forward(fly.yaw, fly.pitch), &to_target) < 1e-4);
+
+        let mut first_person = FirstPersonController::default();
+        first_person.focus(&target, y_fov_rad);
+        let to_target = (target.center() - first_person.fly.position).normalize();
+        let first_person_forward = forward(first_person.fly.yaw, first_person.fly.pitch);
+        assert!(nalgebra_glm::distance(&first_person_forward, &to_target) < 1e-4);
+    }
+
+    #[test]
+    fn first_person_focus_turns_the_short_way() {
+        let mut controller = FirstPersonController::new(nalgebra_glm::vec3(0.0, 1.8, 0.0), 0.0);
+        let wound_up = 2.0 * std::f32::consts::TAU + 0.1;
+        controller.fly.yaw = wound_up;
+        controller.fly.current_yaw = wound_up;
+
+        let target = unit_box_at(nalgebra_glm::vec3(-1.0, 1.8, -5.0));
+        controller.focus(&target, 90_f32.to_radians());
+        let turn = controller.fly.yaw - wound_up;
+        assert!(turn.abs() < 0.2, "turned by {turn}");
+        let to_target = (target.center() - controller.fly.position).normalize();
+        let facing = forward(controller.fly.yaw, controller.fly.pitch);
+        assert!(nalgebra_glm::distance(&facing, &to_target) < 1e-4);
+    }
+
+    #[test]
+    fn service_moves_the_scene_camera() {
+        let mut world = World::default();
+        let mut scene = Scene::default();
+        let root_node_index = world.add_node();
+        world.transforms[root_node_index].translation = nalgebra_glm::vec3(0.0, 10.0, 0.0);
+        let root = scene.graph.add_node(root_node_index);
+        let camera_node_index = world.add_node();
+        world.add_camera_to_node(camera_node_index);
+        let camera = scene.graph.add_node(camera_node_index);
+        scene.graph.add_edge(root, camera, ());
+        scene.default_camera_graph_node_index = Some(camera);
+        world.scenes.push(scene);
+        let world = Rc::new(RefCell::new(world));
+
+        let controller = FlyController {
+            smoothing: 0.0,
+            ..Default::default()
+        };
+        let mut service = CameraControllerService::<_, (), ()>::new(world.clone(), 0, controller);
+        let camera_translation = |world: &World| {
+            let scene = &world.scenes[0];
+            world::Transform::from(
+                world
+                    .global_transform(&scene.graph, scene.default_camera_graph_node_index.unwrap()),
+            )
+            .translation
+        };
+
+        // The controller starts where the scene places the camera
+        let start = camera_translation(&world.borrow());
+        service.update_camera(0.0);
+        assert!(nalgebra_glm::distance(&camera_translation(&world.borrow()), &start) < 1e-4);
+
+        service
+            .input
+            .handle_event(&InputEvent::KeyPressed { key: Key::W });
+        service.update_camera(1.0);
+        let moved = camera_translation(&world.borrow());
+        assert!((nalgebra_glm::distance(&moved, &start) - 5.0).abs() < 1e-4);
+
+        // Keys held when the window loses focus are released
+        service.input.handle_event(&InputEvent::FocusLost);
+        service.update_camera(1.0);
+        assert!(nalgebra_glm::distance(&camera_translation(&world.borrow()), &moved) < 1e-4);
+    }
+
+    #[test]
+    fn controllers_start_from_the_camera_transform() {
+        let transform = world::Transform {
+            translation: nalgebra_glm::vec3(1.0, 2.0, 3.0),
+            rotation: super::look_rotation(&nalgebra_glm::vec3(1.0, -0.5, -1.0)),
+            ..Default::default()
+        };
+        let expected_forward = camera_forward(&transform);
+
+        let mut fly = FlyController::default();
+        fly.set_transform(&transform);
+        let seeded = fly.update(&InputState::default(), 1.0 / 60.0);
+        assert!(nalgebra_glm::distance(&seeded.translation, &transform.translation) < 1e-4);
+        assert!(nalgebra_glm::distance(&camera_forward(&seeded), &expected_forward) < 1e-4);
+
+        let mut orbit = OrbitController::default();
+        orbit.set_transform(&transform);
+        let seeded = orbit.update(&InputState::default(), 1.0 / 60.0);
+        assert!(nalgebra_glm::distance(&seeded.translation, &transform.translation) < 1e-4);
+        assert!(nalgebra_glm::distance(&camera_forward(&seeded), &expected_forward) < 1e-4);
+    }
+}
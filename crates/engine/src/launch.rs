@@ -7,8 +7,9 @@ pub use web_time::{Duration, Instant};
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::*;
 
+use contract::{EngineEvent, InputEvent, Key, MouseButton};
 use service::ServiceBus;
-use std::fmt;
+use std::{cell::RefCell, collections::HashSet, fmt, rc::Rc};
 
 pub trait State<C, E>
 where
@@ -16,6 +17,17 @@ where
     E: Clone + fmt::Debug + 'static,
 {
     fn update(&mut self, _bus: &mut ServiceBus<C, E>, _ui_context: &egui::Context);
+
+    /// The world to render, viewed through the default camera of its default scene
+    fn world(&self) -> Option<Rc<RefCell<world::World>>> {
+        None
+    }
+
+    /// Whether the cursor should be hidden and locked to the window,
+    /// so that mouse motion is reported without holding a button, such as for first person controls
+    fn cursor_grabbed(&self) -> bool {
+        false
+    }
 }
 
 pub fn launch<C, E>(state: impl State<C, E> + 'static)
@@ -102,6 +114,12 @@ async fn run_app<C, E>(
 
     let mut last_render_time = Instant::now();
 
+    // Mouse motion only reaches the engine while the window is focused
+    // and a button pressed outside of the gui is held, or the cursor is grabbed
+    let mut window_focused = window.has_focus();
+    let mut held_buttons = HashSet::new();
+    let mut cursor_grabbed = false;
+
     event_loop
         .run(move |event, elwt| {
             match event {
@@ -109,7 +127,22 @@ async fn run_app<C, E>(
 
                 winit::event::Event::WindowEvent { ref event, .. } => {
                     // Receive gui window event
-                    if gui_state.on_window_event(&window, event).consumed {
+                    let consumed = gui_state.on_window_event(&window, event).consumed;
+
+                    if let winit::event::WindowEvent::Focused(focused) = event {
+                        window_focused = *focused;
+                        if !focused {
+                            held_buttons.clear();
+                            // The grab is applied again once the window is focused
+                            cursor_grabbed = false;
+                            service_bus.publish_engine_event(EngineEvent::Input {
+                                event: InputEvent::FocusLost,
+                            });
+                        }
+                    }
+
+                    // Releases still reach the engine, so that nothing pressed before the gui took over stays held
+                    if consumed && !is_release(event) {
                         return;
                     }
 
@@ -119,6 +152,7 @@ async fn run_app<C, E>(
                             event:
                                 winit::event::KeyEvent {
                                     physical_key: winit::keyboard::PhysicalKey::Code(key_code),
+                                    state,
                                     ..
                                 },
                             ..
@@ -127,6 +161,43 @@ async fn run_app<C, E>(
                             if matches!(key_code, winit::keyboard::KeyCode::Escape) {
                                 elwt.exit();
                             }
+                            let key = map_key(*key_code);
+                            let event = match state {
+                                winit::event::ElementState::Pressed => {
+                                    InputEvent::KeyPressed { key }
+                                }
+                                winit::event::ElementState::Released => {
+                                    InputEvent::KeyReleased { key }
+                                }
+                            };
+                            service_bus.publish_engine_event(EngineEvent::Input { event });
+                        }
+
+                        winit::event::WindowEvent::MouseInput { state, button, .. } => {
+                            let button = map_mouse_button(*button);
+                            let event = match state {
+                                winit::event::ElementState::Pressed => {
+                                    held_buttons.insert(button);
+                                    InputEvent::MouseButtonPressed { button }
+                                }
+                                winit::event::ElementState::Released => {
+                                    held_buttons.remove(&button);
+                                    InputEvent::MouseButtonReleased { button }
+                                }
+                            };
+                            service_bus.publish_engine_event(EngineEvent::Input { event });
+                        }
+
+                        winit::event::WindowEvent::MouseWheel { delta, .. } => {
+                            let delta = match delta {
+                                winit::event::MouseScrollDelta::LineDelta(_, y) => *y,
+                                winit::event::MouseScrollDelta::PixelDelta(position) => {
+                                    position.y as f32 / PIXELS_PER_SCROLL_LINE
+                                }
+                            };
+                            service_bus.publish_engine_event(EngineEvent::Input {
+                                event: InputEvent::MouseScrolled { delta },
+                            });
                         }
 
                         // Close button handler
@@ -156,6 +227,11 @@ async fn run_app<C, E>(
                             service_bus.update();
                             app.update(&mut service_bus, gui_state.egui_ctx());
 
+                            if window_focused && app.cursor_grabbed() != cursor_grabbed {
+                                cursor_grabbed = app.cursor_grabbed();
+                                set_cursor_grab(&window, cursor_grabbed);
+                            }
+
                             let egui::FullOutput {
                                 textures_delta,
                                 shapes,
@@ -183,7 +259,10 @@ async fn run_app<C, E>(
                                 }
                             };
 
+                            let world = app.world();
+                            let world = world.as_ref().map(|world| world.borrow());
                             renderer.render_frame(
+                                world.as_deref(),
                                 screen_descriptor,
                                 paint_jobs,
                                 textures_delta,
@@ -195,8 +274,88 @@ async fn run_app<C, E>(
                     }
                 }
 
+                winit::event::Event::DeviceEvent {
+                    event: winit::event::DeviceEvent::MouseMotion { delta: (x, y) },
+                    ..
+                } if window_focused
+                    && (cursor_grabbed
+                        || (!held_buttons.is_empty()
+                            && !gui_state.egui_ctx().is_using_pointer())) =>
+                {
+                    service_bus.publish_engine_event(EngineEvent::Input {
+                        event: InputEvent::MouseMoved {
+                            delta_x: x as f32,
+                            delta_y: y as f32,
+                        },
+                    });
+                }
+
                 _ => {}
             }
         })
         .unwrap();
 }
+
+// Trackpads report scrolling in pixels rather than lines
+const PIXELS_PER_SCROLL_LINE: f32 = 20.0;
+
+fn is_release(event: &winit::event::WindowEvent) -> bool {
+    matches!(
+        event,
+        winit::event::WindowEvent::KeyboardInput {
+            event: winit::event::KeyEvent {
+                state: winit::event::ElementState::Released,
+                ..
+            },
+            ..
+        } | winit::event::WindowEvent::MouseInput {
+            state: winit::event::ElementState::Released,
+            ..
+        }
+    )
+}
+
+fn set_cursor_grab(window: &winit::window::Window, grabbed: bool) {
+    let result = if grabbed {
+        // Not every platform can lock the cursor in place, but confining it still keeps it in the window
+        window
+            .set_cursor_grab(winit::window::CursorGrabMode::Locked)
+            .or_else(|_| window.set_cursor_grab(winit::window::CursorGrabMode::Confined))
+    } else {
+        window.set_cursor_grab(winit::window::CursorGrabMode::None)
+    };
+    if let Err(error) = result {
+        log::warn!("Failed to change the cursor grab: {error}");
+    }
+    window.set_cursor_visible(!grabbed);
+}
+
+fn map_key(key_code: winit::keyboard::KeyCode) -> Key {
+    use winit::keyboard::KeyCode;
+    match key_code {
+        KeyCode::KeyW => Key::W,
+        KeyCode::KeyA => Key::A,
+        KeyCode::KeyS => Key::S,
+        KeyCode::KeyD => Key::D,
+        KeyCode::KeyQ => Key::Q,
+        KeyCode::KeyE => Key::E,
+        KeyCode::KeyF => Key::F,
+        KeyCode::Space => Key::Space,
+        KeyCode::ShiftLeft | KeyCode::ShiftRight => Key::Shift,
+        KeyCode::ControlLeft | KeyCode::ControlRight => Key::Control,
+        KeyCode::ArrowUp => Key::Up,
+        KeyCode::ArrowDown => Key::Down,
+        KeyCode::ArrowLeft => Key::Left,
+        KeyCode::ArrowRight => Key::Right,
+        _ => Key::Other,
+    }
+}
+
+fn map_mouse_button(button: winit::event::MouseButton) -> MouseButton {
+    match button {
+        winit::event::MouseButton::Left => MouseButton::Left,
+        winit::event::MouseButton::Right => MouseButton::Right,
+        winit::event::MouseButton::Middle => MouseButton::Middle,
+        _ => MouseButton::Other,
+    }
+}
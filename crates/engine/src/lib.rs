@@ -1,5 +1,9 @@
+pub mod audio;
+pub mod camera;
 pub mod launch;
 
+pub use audio::*;
+pub use camera::*;
 pub use launch::*;
 
 pub use contract;
@@ -8,3 +12,4 @@ pub use log;
 
 pub use service;
 pub use service::ServiceBus;
+pub use world;
@@ -0,0 +1,1450 @@
+use gltf::json::{self, validation::Checked::Valid};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    path::Path,
+};
+
+#[derive(thiserror::Error, Debug)]
+pub enum ExportError {
+    #[error("Failed to write the glTF file: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Failed to serialize the glTF document: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Scene {scene_index} does not exist")]
+    MissingScene { scene_index: usize },
+
+    #[error("Image {image_index} could not be encoded as PNG: {reason}")]
+    ImageEncoding { image_index: usize, reason: String },
+}
+
+#[derive(Default, Debug, Copy, Clone)]
+pub struct ExportOptions {
+    /// The scene of the world to write
+    pub scene_index: usize,
+}
+
+/// Writes a scene of the world as .glb, or as .gltf with an embedded buffer
+/// when the path has any other extension
+pub fn export_gltf(
+    world: &world::World,
+    path: impl AsRef<Path>,
+    options: &ExportOptions,
+) -> Result<(), ExportError> {
+    let path = path.as_ref();
+    let is_binary = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("glb"));
+    let bytes = if is_binary {
+        export_glb(world, options)?
+    } else {
+        export_gltf_json(world, options)?.into_bytes()
+    };
+    std::fs::write(path, bytes)?;
+    Ok(())
+}
+
+/// Writes a scene of the world as a binary .glb
+pub fn export_glb(world: &world::World, options: &ExportOptions) -> Result<Vec<u8>, ExportError> {
+    let (mut root, data) = Exporter::new(world, options).export()?;
+    if !data.is_empty() {
+        root.buffers.push(buffer(data.len(), None));
+    }
+    let json = pad(root.to_vec()?, b' ');
+    let bin = pad(data, 0);
+
+    let mut length = 12 + 8 + json.len();
+    if !bin.is_empty() {
+        length += 8 + bin.len();
+    }
+    let mut bytes = Vec::with_capacity(length);
+    bytes.extend_from_slice(b"glTF");
+    bytes.extend_from_slice(&2_u32.to_le_bytes());
+    bytes.extend_from_slice(&(length as u32).to_le_bytes());
+    bytes.extend_from_slice(&(json.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(b"JSON");
+    bytes.extend_from_slice(&json);
+    if !bin.is_empty() {
+        bytes.extend_from_slice(&(bin.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(b"BIN\0");
+        bytes.extend_from_slice(&bin);
+    }
+    Ok(bytes)
+}
+
+/// Writes a scene of the world as .gltf JSON, embedding the buffer as a data URI
+pub fn export_gltf_json(
+    world: &world::World,
+    options: &ExportOptions,
+) -> Result<String, ExportError> {
+    let (mut root, data) = Exporter::new(world, options).export()?;
+    if !data.is_empty() {
+        let uri = format!(
+            "data:application/octet-stream;base64,{}",
+            base64::encode(&data)
+        );
+        root.buffers.push(buffer(data.len(), Some(uri)));
+    }
+    Ok(root.to_string_pretty()?)
+}
+
+fn buffer(byte_length: usize, uri: Option<String>) -> json::Buffer {
+    json::Buffer {
+        byte_length: byte_length.into(),
+        name: None,
+        uri,
+        extensions: None,
+        extras: Default::default(),
+    }
+}
+
+fn pad(mut chunk: Vec<u8>, padding: u8) -> Vec<u8> {
+    while chunk.len() % 4 != 0 {
+        chunk.push(padding);
+    }
+    chunk
+}
+
+fn index<T>(index: usize) -> json::Index<T> {
+    json::Index::new(index as u32)
+}
+
+/// Returns the glTF index of a world item among those written so far, adding it when it is new
+fn gltf_index<T>(written: &mut Vec<usize>, world_index: usize) -> json::Index<T> {
+    let position = written
+        .iter()
+        .position(|index| *index == world_index)
+        .unwrap_or_else(|| {
+            written.push(world_index);
+            written.len() - 1
+        });
+    index(position)
+}
+
+/// The glTF image each exported world image became
+type ImageIndices = HashMap<usize, json::Index<json::Image>>;
+
+/// Builds the document and its single buffer, remembering which glTF node
+/// each exported world node became so skins and animations can refer to them.
+/// Only the meshes, materials, textures and lights that the scene's nodes use are written.
+struct Exporter<'a> {
+    world: &'a world::World,
+    options: &'a ExportOptions,
+    root: json::Root,
+    data: Vec<u8>,
+    nodes: HashMap<usize, json::Index<json::Node>>,
+    /// The world camera written as each glTF camera
+    cameras: Vec<usize>,
+    /// The world mesh written as each glTF mesh
+    meshes: Vec<usize>,
+    /// The world material written as each glTF material
+    materials: Vec<usize>,
+    /// The world light written as each glTF light
+    lights: Vec<usize>,
+    /// The glTF texture each world texture became, leaving out textures whose image was skipped
+    textures: HashMap<usize, json::Index<json::Texture>>,
+}
+
+impl<'a> Exporter<'a> {
+    fn new(world: &'a world::World, options: &'a ExportOptions) -> Self {
+        Self {
+            world,
+            options,
+            root: json::Root::default(),
+            data: Vec::new(),
+            nodes: HashMap::new(),
+            cameras: Vec::new(),
+            meshes: Vec::new(),
+            materials: Vec::new(),
+            lights: Vec::new(),
+            textures: HashMap::new(),
+        }
+    }
+
+    fn export(mut self) -> Result<(json::Root, Vec<u8>), ExportError> {
+        let world = self.world;
+        let scene_index = self.options.scene_index;
+        let scene = world
+            .scenes
+            .get(scene_index)
+            .ok_or(ExportError::MissingScene { scene_index })?;
+
+        let (mut gltf_nodes, scene_roots) = self.nodes(scene);
+        let skins = self.skins(&mut gltf_nodes);
+        let gltf_nodes = gltf_nodes
+            .into_iter()
+            .map(|(_, node)| node)
+            .collect::<Vec<_>>();
+
+        let meshes = self.meshes();
+        let mut texture_indices = Vec::new();
+        self.materials
+            .iter()
+            .flat_map(|material_index| world.materials[*material_index].textures())
+            .filter(|info| info.texture_index < world.textures.len())
+            .for_each(|info| {
+                gltf_index::<json::Texture>(&mut texture_indices, info.texture_index);
+            });
+        let (images, image_indices) = self.images(&texture_indices)?;
+        let (textures, samplers) = self.textures(&texture_indices, &image_indices);
+        let animations = self.animations();
+        let mut extensions_used = BTreeSet::new();
+        let materials = self
+            .materials
+            .iter()
+            .map(|material_index| {
+                map_material(
+                    &world.materials[*material_index],
+                    &self.textures,
+                    &mut extensions_used,
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if !self.lights.is_empty() {
+            extensions_used.insert("KHR_lights_punctual".to_string());
+            self.root.extensions = Some(json::extensions::root::Root {
+                khr_lights_punctual: Some(json::extensions::root::KhrLightsPunctual {
+                    lights: self
+                        .lights
+                        .iter()
+                        .map(|light_index| map_light(&world.lights[*light_index]))
+                        .collect(),
+                }),
+                ..Default::default()
+            });
+        }
+
+        let cameras = self
+            .cameras
+            .iter()
+            .map(|camera_index| map_camera(&world.cameras[*camera_index]))
+            .collect();
+        let root = json::Root {
+            asset: json::Asset {
+                copyright: None,
+                extensions: None,
+                extras: Default::default(),
+                generator: Some("gltf_importer".to_string()),
+                min_version: None,
+                version: "2.0".to_string(),
+            },
+            // Scenes without nodes are left out, since gltf-json reads a scene's nodes as required
+            scene: (!scene_roots.is_empty()).then(|| index(0)),
+            scenes: (!scene_roots.is_empty())
+                .then(|| json::Scene {
+                    extensions: None,
+                    extras: Default::default(),
+                    name: None,
+                    nodes: scene_roots,
+                })
+                .into_iter()
+                .collect(),
+            nodes: gltf_nodes,
+            meshes,
+            materials,
+            textures,
+            samplers,
+            images,
+            cameras,
+            skins,
+            animations,
+            extensions_used: extensions_used.into_iter().collect(),
+            ..self.root
+        };
+        Ok((root, self.data))
+    }
+
+    /// Returns the glTF nodes in depth first order alongside the world node they came from,
+    /// and the scene's root glTF nodes
+    fn nodes(
+        &mut self,
+        scene: &world::Scene,
+    ) -> (Vec<(usize, json::Node)>, Vec<json::Index<json::Node>>) {
+        let world = self.world;
+        let graph = &scene.graph;
+
+        // Synthetic cameras would be added again on every import, so their nodes are left out,
+        // unless they have children, which keep the node without its camera
+        let excluded = graph
+            .node_indices()
+            .filter(|graph_node_index| {
+                self.is_synthetic_camera(graph[*graph_node_index])
+                    && children(graph, *graph_node_index).is_empty()
+            })
+            .collect::<HashSet<_>>();
+
+        // The importer wraps each scene in an empty root node, which is left out
+        // so that the world doesn't gain another level with each round trip
+        let mut roots = graph
+            .externals(petgraph::Direction::Incoming)
+            .collect::<Vec<_>>();
+        if let [root] = roots[..] {
+            let node = &world.nodes[graph[root]];
+            let is_empty = node.mesh_index.is_none()
+                && node.camera_index.is_none()
+                && node.light_index.is_none()
+                && node.skin_index.is_none()
+                && world.transforms[node.transform_index] == world::Transform::default();
+            if is_empty {
+                roots = children(graph, root);
+            }
+        }
+
+        let mut gltf_nodes = Vec::new();
+        let scene_roots = roots
+            .into_iter()
+            .filter(|root| !excluded.contains(root))
+            .map(|root| self.visit_node(graph, root, &excluded, &mut gltf_nodes))
+            .collect();
+        (gltf_nodes, scene_roots)
+    }
+
+    fn visit_node(
+        &mut self,
+        graph: &world::SceneGraph,
+        graph_node_index: petgraph::graph::NodeIndex,
+        excluded: &HashSet<petgraph::graph::NodeIndex>,
+        gltf_nodes: &mut Vec<(usize, json::Node)>,
+    ) -> json::Index<json::Node> {
+        let world = self.world;
+        let node_index = graph[graph_node_index];
+        let node = &world.nodes[node_index];
+        let transform = &world.transforms[node.transform_index];
+
+        let gltf_node_index = index(gltf_nodes.len());
+        self.nodes.entry(node_index).or_insert(gltf_node_index);
+
+        let camera = node
+            .camera_index
+            .filter(|_| !self.is_synthetic_camera(node_index))
+            .map(|camera_index| gltf_index(&mut self.cameras, camera_index));
+        let extensions = node
+            .light_index
+            .map(|light_index| json::extensions::scene::Node {
+                khr_lights_punctual: Some(
+                    json::extensions::scene::khr_lights_punctual::KhrLightsPunctual {
+                        light: gltf_index(&mut self.lights, light_index),
+                    },
+                ),
+                ..Default::default()
+            });
+        gltf_nodes.push((
+            node_index,
+            json::Node {
+                name: Some(world.metadata[node.metadata_index].name.clone()),
+                translation: Some(transform.translation.into()),
+                rotation: Some(json::scene::UnitQuaternion(
+                    transform.rotation.coords.into(),
+                )),
+                scale: Some(transform.scale.into()),
+                mesh: node
+                    .mesh_index
+                    .map(|mesh_index| gltf_index(&mut self.meshes, mesh_index)),
+                camera,
+                extensions,
+                weights: node
+                    .morph_weights_index
+                    .map(|morph_weights_index| world.morph_weights[morph_weights_index].clone()),
+                ..Default::default()
+            },
+        ));
+
+        let children = children(graph, graph_node_index)
+            .into_iter()
+            .filter(|child| !excluded.contains(child))
+            .map(|child| self.visit_node(graph, child, excluded, gltf_nodes))
+            .collect::<Vec<_>>();
+        if !children.is_empty() {
+            gltf_nodes[gltf_node_index.value()].1.children = Some(children);
+        }
+        gltf_node_index
+    }
+
+    fn is_synthetic_camera(&self, node_index: usize) -> bool {
+        self.world.nodes[node_index]
+            .camera_index
+            .is_some_and(|camera_index| self.world.cameras[camera_index].synthetic)
+    }
+
+    /// Writes the skins of exported nodes whose joints were all exported
+    fn skins(&mut self, gltf_nodes: &mut [(usize, json::Node)]) -> Vec<json::Skin> {
+        let world = self.world;
+        let mut skin_map = HashMap::new();
+        let mut skins = Vec::new();
+        for (node_index, gltf_node) in gltf_nodes.iter_mut() {
+            let Some(skin_index) = world.nodes[*node_index].skin_index else {
+                continue;
+            };
+            let skin = &world.skins[skin_index];
+            let joints = skin
+                .joints
+                .iter()
+                .map(|joint| self.nodes.get(&joint.target_node_index).copied())
+                .collect::<Option<Vec<_>>>();
+            let Some(joints) = joints else {
+                continue;
+            };
+            let gltf_skin_index = match skin_map.get(&skin_index) {
+                Some(gltf_skin_index) => *gltf_skin_index,
+                None => {
+                    let matrices = skin
+                        .joints
+                        .iter()
+                        .flat_map(|joint| joint.inverse_bind_matrix.as_slice().to_vec())
+                        .collect::<Vec<_>>();
+                    let inverse_bind_matrices =
+                        self.float_accessor(&matrices, json::accessor::Type::Mat4, None, false);
+                    let gltf_skin_index = json::Index::push(
+                        &mut skins,
+                        json::Skin {
+                            extensions: None,
+                            extras: Default::default(),
+                            inverse_bind_matrices: Some(inverse_bind_matrices),
+                            joints,
+                            name: None,
+                            skeleton: None,
+                        },
+                    );
+                    skin_map.insert(skin_index, gltf_skin_index);
+                    gltf_skin_index
+                }
+            };
+            gltf_node.skin = Some(gltf_skin_index);
+        }
+        skins
+    }
+
+    fn meshes(&mut self) -> Vec<json::Mesh> {
+        let world = self.world;
+        let skinned_meshes = self
+            .nodes
+            .keys()
+            .filter(|node_index| world.nodes[**node_index].skin_index.is_some())
+            .filter_map(|node_index| world.nodes[*node_index].mesh_index)
+            .collect::<HashSet<_>>();
+        self.meshes
+            .clone()
+            .into_iter()
+            .map(|mesh_index| {
+                let mesh = &world.meshes[mesh_index];
+                let primitives = mesh
+                    .primitives
+                    .iter()
+                    .map(|primitive| {
+                        self.primitive(primitive, skinned_meshes.contains(&mesh_index))
+                    })
+                    .collect::<Vec<_>>();
+                json::Mesh {
+                    extensions: None,
+                    extras: Default::default(),
+                    name: None,
+                    primitives,
+                    weights: (!mesh.weights.is_empty()).then(|| mesh.weights.clone()),
+                }
+            })
+            .collect()
+    }
+
+    fn primitive(
+        &mut self,
+        primitive: &world::Primitive,
+        is_skinned: bool,
+    ) -> json::mesh::Primitive {
+        use json::{accessor::Type, buffer::Target, mesh::Semantic};
+
+        let world = self.world;
+        let vertices = &world.vertices
+            [primitive.vertex_offset..primitive.vertex_offset + primitive.number_of_vertices];
+
+        let mut attributes = BTreeMap::new();
+        let mut attribute = |semantic, values: Vec<f32>, kind, with_bounds| {
+            let accessor =
+                self.float_accessor(&values, kind, Some(Target::ArrayBuffer), with_bounds);
+            attributes.insert(Valid(semantic), accessor);
+        };
+
+        attribute(
+            Semantic::Positions,
+            flatten(vertices.iter().map(|vertex| vertex.position.as_slice())),
+            Type::Vec3,
+            true,
+        );
+        attribute(
+            Semantic::Normals,
+            flatten(vertices.iter().map(|vertex| vertex.normal.as_slice())),
+            Type::Vec3,
+            false,
+        );
+        // Tangents are only written when they are complete, since glTF requires unit length
+        if vertices.iter().all(|vertex| vertex.tangent.w != 0.0) {
+            attribute(
+                Semantic::Tangents,
+                flatten(vertices.iter().map(|vertex| vertex.tangent.as_slice())),
+                Type::Vec4,
+                false,
+            );
+        }
+        // Attributes holding the importer's defaults are left out
+        if vertices
+            .iter()
+            .any(|vertex| vertex.uv_0 != nalgebra_glm::Vec2::zeros())
+        {
+            attribute(
+                Semantic::TexCoords(0),
+                flatten(vertices.iter().map(|vertex| vertex.uv_0.as_slice())),
+                Type::Vec2,
+                false,
+            );
+        }
+        if vertices
+            .iter()
+            .any(|vertex| vertex.uv_1 != nalgebra_glm::Vec2::zeros())
+        {
+            attribute(
+                Semantic::TexCoords(1),
+                flatten(vertices.iter().map(|vertex| vertex.uv_1.as_slice())),
+                Type::Vec2,
+                false,
+            );
+        }
+        let white = nalgebra_glm::vec3(1.0, 1.0, 1.0);
+        if vertices.iter().any(|vertex| vertex.color_0 != white) {
+            attribute(
+                Semantic::Colors(0),
+                flatten(vertices.iter().map(|vertex| vertex.color_0.as_slice())),
+                Type::Vec3,
+                false,
+            );
+        }
+        if is_skinned {
+            attribute(
+                Semantic::Weights(0),
+                flatten(vertices.iter().map(|vertex| vertex.weight_0.as_slice())),
+                Type::Vec4,
+                false,
+            );
+            let bytes = vertices
+                .iter()
+                .flat_map(|vertex| vertex.joint_0.iter().map(|joint| *joint as u16))
+                .flat_map(u16::to_le_bytes)
+                .collect::<Vec<_>>();
+            let view = self.buffer_view(&bytes, Some(Target::ArrayBuffer));
+            let accessor = self.accessor(
+                view,
+                json::accessor::ComponentType::U16,
+                vertices.len(),
+                Type::Vec4,
+                None,
+            );
+            attributes.insert(Valid(Semantic::Joints(0)), accessor);
+        }
+
+        let targets = (0..primitive.number_of_morph_targets)
+            .map(|target_index| {
+                let offset = primitive.morph_target_offset + target_index * vertices.len();
+                let displacements = &world.morph_target_vertices[offset..offset + vertices.len()];
+                let mut displacement = |values: Vec<f32>, with_bounds| {
+                    self.float_accessor(&values, Type::Vec3, Some(Target::ArrayBuffer), with_bounds)
+                };
+                json::mesh::MorphTarget {
+                    positions: Some(displacement(
+                        flatten(
+                            displacements
+                                .iter()
+                                .map(|vertex| vertex.position.as_slice()),
+                        ),
+                        true,
+                    )),
+                    normals: Some(displacement(
+                        flatten(displacements.iter().map(|vertex| vertex.normal.as_slice())),
+                        false,
+                    )),
+                    tangents: Some(displacement(
+                        flatten(displacements.iter().map(|vertex| vertex.tangent.as_slice())),
+                        false,
+                    )),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let indices = (primitive.number_of_indices > 0).then(|| {
+            let indices = &world.indices
+                [primitive.index_offset..primitive.index_offset + primitive.number_of_indices];
+            let bytes = indices
+                .iter()
+                .flat_map(|index| index.to_le_bytes())
+                .collect::<Vec<_>>();
+            let view = self.buffer_view(&bytes, Some(Target::ElementArrayBuffer));
+            self.accessor(
+                view,
+                json::accessor::ComponentType::U32,
+                indices.len(),
+                Type::Scalar,
+                None,
+            )
+        });
+        json::mesh::Primitive {
+            attributes,
+            extensions: None,
+            extras: Default::default(),
+            indices,
+            material: primitive
+                .material_index
+                .map(|material_index| gltf_index(&mut self.materials, material_index)),
+            mode: Valid(map_topology(&primitive.topology)),
+            targets: (!targets.is_empty()).then_some(targets),
+        }
+    }
+
+    /// Embeds the images of the exported textures that PNG can hold. Others, such as float images,
+    /// are left out with a warning, along with the textures that sample them.
+    fn images(
+        &mut self,
+        texture_indices: &[usize],
+    ) -> Result<(Vec<json::Image>, ImageIndices), ExportError> {
+        let world = self.world;
+        let mut referenced = Vec::new();
+        texture_indices.iter().for_each(|texture_index| {
+            gltf_index::<json::Image>(&mut referenced, world.textures[*texture_index].image_index);
+        });
+        let mut images = Vec::new();
+        let mut image_indices = HashMap::new();
+        for image_index in referenced {
+            let Some(image) = world.images.get(image_index) else {
+                continue;
+            };
+            let Some(png) = encode_png(image_index, image)? else {
+                log::warn!(
+                    "Image {image_index} is left out of the export, since PNG can't hold its {:?} format",
+                    image.format
+                );
+                continue;
+            };
+            image_indices.insert(
+                image_index,
+                json::Index::push(
+                    &mut images,
+                    json::Image {
+                        buffer_view: Some(self.buffer_view(&png, None)),
+                        mime_type: Some(json::image::MimeType("image/png".to_string())),
+                        name: None,
+                        uri: None,
+                        extensions: None,
+                        extras: Default::default(),
+                    },
+                ),
+            );
+        }
+        Ok((images, image_indices))
+    }
+
+    /// Writes the textures whose image was exported, along with the samplers they use
+    fn textures(
+        &mut self,
+        texture_indices: &[usize],
+        image_indices: &ImageIndices,
+    ) -> (Vec<json::Texture>, Vec<json::texture::Sampler>) {
+        let world = self.world;
+        let mut textures = Vec::new();
+        let mut samplers = Vec::new();
+        for texture_index in texture_indices.iter().copied() {
+            let texture = &world.textures[texture_index];
+            let Some(source) = image_indices.get(&texture.image_index) else {
+                continue;
+            };
+            let gltf_texture = json::Texture {
+                name: None,
+                sampler: texture
+                    .sampler_index
+                    .map(|sampler_index| gltf_index(&mut samplers, sampler_index)),
+                source: *source,
+                extensions: None,
+                extras: Default::default(),
+            };
+            self.textures.insert(
+                texture_index,
+                json::Index::push(&mut textures, gltf_texture),
+            );
+        }
+        let samplers = samplers
+            .into_iter()
+            .map(|sampler_index| map_sampler(&world.samplers[sampler_index]))
+            .collect();
+        (textures, samplers)
+    }
+
+    fn animations(&mut self) -> Vec<json::Animation> {
+        let world = self.world;
+        world
+            .animations
+            .iter()
+            .filter_map(|animation| {
+                let targeted = animation
+                    .channels
+                    .iter()
+                    .filter_map(|channel| {
+                        let target_node = *self.nodes.get(&channel.target_node_index)?;
+                        Some((channel, target_node))
+                    })
+                    .collect::<Vec<_>>();
+                let (channels, samplers): (Vec<_>, Vec<_>) = targeted
+                    .into_iter()
+                    .enumerate()
+                    .map(|(sampler_index, (channel, target_node))| {
+                        let (path, kind, values) = transformation_values(&channel.transformations);
+                        let values = match (&channel.interpolation, &channel.tangents) {
+                            (world::Interpolation::CubicSpline, Some(tangents)) => {
+                                let (_, _, in_tangents) =
+                                    transformation_values(&tangents.in_tangents);
+                                let (_, _, out_tangents) =
+                                    transformation_values(&tangents.out_tangents);
+                                let width = values.len() / channel.inputs.len().max(1);
+                                (0..channel.inputs.len())
+                                    .flat_map(|keyframe| {
+                                        let range = keyframe * width..(keyframe + 1) * width;
+                                        [&in_tangents, &values, &out_tangents]
+                                            .into_iter()
+                                            .flat_map(move |set| set[range.clone()].to_vec())
+                                    })
+                                    .collect()
+                            }
+                            _ => values,
+                        };
+                        let input = self.float_accessor(
+                            &channel.inputs,
+                            json::accessor::Type::Scalar,
+                            None,
+                            true,
+                        );
+                        let output = self.float_accessor(&values, kind, None, false);
+                        let interpolation = match channel.interpolation {
+                            world::Interpolation::Linear => json::animation::Interpolation::Linear,
+                            world::Interpolation::Step => json::animation::Interpolation::Step,
+                            world::Interpolation::CubicSpline => {
+                                json::animation::Interpolation::CubicSpline
+                            }
+                        };
+                        (
+                            json::animation::Channel {
+                                sampler: index(sampler_index),
+                                target: json::animation::Target {
+                                    extensions: None,
+                                    extras: Default::default(),
+                                    node: target_node,
+                                    path: Valid(path),
+                                },
+                                extensions: None,
+                                extras: Default::default(),
+                            },
+                            json::animation::Sampler {
+                                extensions: None,
+                                extras: Default::default(),
+                                input,
+                                interpolation: Valid(interpolation),
+                                output,
+                            },
+                        )
+                    })
+                    .unzip();
+                if channels.is_empty() {
+                    return None;
+                }
+                Some(json::Animation {
+                    extensions: None,
+                    extras: Default::default(),
+                    channels,
+                    name: (!animation.name.is_empty()).then(|| animation.name.clone()),
+                    samplers,
+                })
+            })
+            .collect()
+    }
+
+    /// Appends bytes to the buffer as a new view, aligned for any component type
+    fn buffer_view(
+        &mut self,
+        bytes: &[u8],
+        target: Option<json::buffer::Target>,
+    ) -> json::Index<json::buffer::View> {
+        while self.data.len() % 4 != 0 {
+            self.data.push(0);
+        }
+        let view = json::buffer::View {
+            buffer: index(0),
+            byte_length: bytes.len().into(),
+            byte_offset: Some(self.data.len().into()),
+            byte_stride: None,
+            name: None,
+            target: target.map(Valid),
+            extensions: None,
+            extras: Default::default(),
+        };
+        self.data.extend_from_slice(bytes);
+        self.root.push(view)
+    }
+
+    fn accessor(
+        &mut self,
+        buffer_view: json::Index<json::buffer::View>,
+        component_type: json::accessor::ComponentType,
+        count: usize,
+        kind: json::accessor::Type,
+        bounds: Option<(Vec<f32>, Vec<f32>)>,
+    ) -> json::Index<json::Accessor> {
+        let (min, max) = bounds.map_or((None, None), |(min, max)| {
+            (Some(json::Value::from(min)), Some(json::Value::from(max)))
+        });
+        self.root.push(json::Accessor {
+            buffer_view: Some(buffer_view),
+            byte_offset: None,
+            count: count.into(),
+            component_type: Valid(json::accessor::GenericComponentType(component_type)),
+            extensions: None,
+            extras: Default::default(),
+            type_: Valid(kind),
+            min,
+            max,
+            name: None,
+            normalized: false,
+            sparse: None,
+        })
+    }
+
+    fn float_accessor(
+        &mut self,
+        values: &[f32],
+        kind: json::accessor::Type,
+        target: Option<json::buffer::Target>,
+        with_bounds: bool,
+    ) -> json::Index<json::Accessor> {
+        let width = kind.multiplicity();
+        let bounds = with_bounds.then(|| {
+            let mut min = vec![f32::MAX; width];
+            let mut max = vec![f32::MIN; width];
+            values.chunks_exact(width).for_each(|element| {
+                element.iter().enumerate().for_each(|(component, value)| {
+                    min[component] = min[component].min(*value);
+                    max[component] = max[component].max(*value);
+                })
+            });
+            (min, max)
+        });
+        let bytes = values
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect::<Vec<_>>();
+        let view = self.buffer_view(&bytes, target);
+        self.accessor(
+            view,
+            json::accessor::ComponentType::F32,
+            values.len() / width,
+            kind,
+            bounds,
+        )
+    }
+}
+
+fn flatten<'v>(values: impl Iterator<Item = &'v [f32]>) -> Vec<f32> {
+    values.flat_map(|value| value.iter().copied()).collect()
+}
+
+fn children(
+    graph: &world::SceneGraph,
+    graph_node_index: petgraph::graph::NodeIndex,
+) -> Vec<petgraph::graph::NodeIndex> {
+    // Petgraph lists neighbors from the most recently added edge
+    let mut children = graph
+        .neighbors_directed(graph_node_index, petgraph::Direction::Outgoing)
+        .collect::<Vec<_>>();
+    children.reverse();
+    children
+}
+
+fn transformation_values(
+    transformations: &world::TransformationSet,
+) -> (json::animation::Property, json::accessor::Type, Vec<f32>) {
+    use json::{accessor::Type, animation::Property};
+    match transformations {
+        world::TransformationSet::Translations(translations) => (
+            Property::Translation,
+            Type::Vec3,
+            translations
+                .iter()
+                .flat_map(|value| value.iter().copied())
+                .collect(),
+        ),
+        world::TransformationSet::Rotations(rotations) => (
+            Property::Rotation,
+            Type::Vec4,
+            rotations
+                .iter()
+                .flat_map(|value| value.iter().copied())
+                .collect(),
+        ),
+        world::TransformationSet::Scales(scales) => (
+            Property::Scale,
+            Type::Vec3,
+            scales
+                .iter()
+                .flat_map(|value| value.iter().copied())
+                .collect(),
+        ),
+        world::TransformationSet::MorphTargetWeights(weights) => {
+            (Property::MorphTargetWeights, Type::Scalar, weights.clone())
+        }
+    }
+}
+
+/// Encodes an image as PNG, or returns `None` when PNG has no matching color type
+fn encode_png(image_index: usize, image: &world::Image) -> Result<Option<Vec<u8>>, ExportError> {
+    let swizzled;
+    let (pixels, color_type) = match image.format {
+        world::ImageFormat::R8 => (&image.pixels, image::ColorType::L8),
+        world::ImageFormat::R8G8 => (&image.pixels, image::ColorType::La8),
+        world::ImageFormat::R8G8B8 => (&image.pixels, image::ColorType::Rgb8),
+        world::ImageFormat::R8G8B8A8 => (&image.pixels, image::ColorType::Rgba8),
+        world::ImageFormat::R16 => (&image.pixels, image::ColorType::L16),
+        world::ImageFormat::R16G16 => (&image.pixels, image::ColorType::La16),
+        world::ImageFormat::R16G16B16 => (&image.pixels, image::ColorType::Rgb16),
+        world::ImageFormat::R16G16B16A16 => (&image.pixels, image::ColorType::Rgba16),
+        world::ImageFormat::B8G8R8 | world::ImageFormat::B8G8R8A8 => {
+            let (stride, color_type) = match image.format {
+                world::ImageFormat::B8G8R8 => (3, image::ColorType::Rgb8),
+                _ => (4, image::ColorType::Rgba8),
+            };
+            let mut pixels = image.pixels.clone();
+            pixels
+                .chunks_exact_mut(stride)
+                .for_each(|pixel| pixel.swap(0, 2));
+            swizzled = pixels;
+            (&swizzled, color_type)
+        }
+        _ => return Ok(None),
+    };
+    let mut png = Vec::new();
+    image::ImageEncoder::write_image(
+        image::codecs::png::PngEncoder::new(&mut png),
+        pixels,
+        image.width,
+        image.height,
+        color_type,
+    )
+    .map_err(|error| ExportError::ImageEncoding {
+        image_index,
+        reason: error.to_string(),
+    })?;
+    Ok(Some(png))
+}
+
+fn map_material(
+    material: &world::Material,
+    textures: &HashMap<usize, json::Index<json::Texture>>,
+    extensions_used: &mut BTreeSet<String>,
+) -> Result<json::Material, ExportError> {
+    use json::extensions::material as extension;
+
+    let mut texture_info = |info: &Option<world::TextureInfo>| {
+        info.as_ref()
+            .and_then(|info| map_texture_info(info, textures, extensions_used))
+    };
+    let mut extensions = extension::Material {
+        emissive_strength: material.emissive_strength.map(|emissive_strength| {
+            extension::EmissiveStrength {
+                emissive_strength: extension::EmissiveStrengthFactor(emissive_strength),
+            }
+        }),
+        unlit: material.unlit.then_some(extension::Unlit {}),
+        ior: material.ior.map(|ior| extension::Ior {
+            ior: extension::IndexOfRefraction(ior),
+            extras: Default::default(),
+        }),
+        specular: material
+            .specular
+            .as_ref()
+            .map(|specular| extension::Specular {
+                specular_factor: extension::SpecularFactor(specular.factor),
+                specular_texture: texture_info(&specular.texture),
+                specular_color_factor: extension::SpecularColorFactor(specular.color_factor.into()),
+                specular_color_texture: texture_info(&specular.color_texture),
+                extras: Default::default(),
+            }),
+        transmission: material
+            .transmission
+            .as_ref()
+            .map(|transmission| extension::Transmission {
+                transmission_factor: extension::TransmissionFactor(transmission.factor),
+                transmission_texture: texture_info(&transmission.texture),
+                extras: Default::default(),
+            }),
+        ..Default::default()
+    };
+    // gltf-json has no type for KHR_materials_clearcoat, so it is written as plain JSON
+    if let Some(clearcoat) = &material.clearcoat {
+        let mut extension = serde_json::Map::new();
+        extension.insert("clearcoatFactor".to_string(), clearcoat.factor.into());
+        extension.insert(
+            "clearcoatRoughnessFactor".to_string(),
+            clearcoat.roughness_factor.into(),
+        );
+        [
+            ("clearcoatTexture", &clearcoat.texture),
+            ("clearcoatRoughnessTexture", &clearcoat.roughness_texture),
+            ("clearcoatNormalTexture", &clearcoat.normal_texture),
+        ]
+        .into_iter()
+        .filter_map(|(name, info)| Some((name, texture_info(info)?)))
+        .try_for_each(|(name, info)| {
+            let mut info = serde_json::to_value(info)?;
+            if name == "clearcoatNormalTexture" {
+                info["scale"] = clearcoat.normal_scale.into();
+            }
+            extension.insert(name.to_string(), info);
+            Ok::<_, ExportError>(())
+        })?;
+        extensions.others.insert(
+            "KHR_materials_clearcoat".to_string(),
+            serde_json::Value::Object(extension),
+        );
+    }
+
+    let used = [
+        (
+            "KHR_materials_emissive_strength",
+            extensions.emissive_strength.is_some(),
+        ),
+        ("KHR_materials_unlit", extensions.unlit.is_some()),
+        ("KHR_materials_ior", extensions.ior.is_some()),
+        ("KHR_materials_specular", extensions.specular.is_some()),
+        (
+            "KHR_materials_transmission",
+            extensions.transmission.is_some(),
+        ),
+        ("KHR_materials_clearcoat", material.clearcoat.is_some()),
+    ]
+    .into_iter()
+    .filter_map(|(name, used)| used.then_some(name))
+    .collect::<Vec<_>>();
+    extensions_used.extend(used.iter().map(|name| name.to_string()));
+
+    Ok(json::Material {
+        alpha_cutoff: material.alpha_cutoff.map(json::material::AlphaCutoff),
+        alpha_mode: Valid(match material.alpha_mode {
+            world::AlphaMode::Opaque => json::material::AlphaMode::Opaque,
+            world::AlphaMode::Mask => json::material::AlphaMode::Mask,
+            world::AlphaMode::Blend => json::material::AlphaMode::Blend,
+        }),
+        pbr_metallic_roughness: json::material::PbrMetallicRoughness {
+            base_color_factor: json::material::PbrBaseColorFactor(
+                material.base_color_factor.into(),
+            ),
+            base_color_texture: material
+                .base_color_texture
+                .as_ref()
+                .and_then(|info| map_texture_info(info, textures, extensions_used)),
+            ..Default::default()
+        },
+        emissive_texture: material
+            .emissive_texture
+            .as_ref()
+            .and_then(|info| map_texture_info(info, textures, extensions_used)),
+        emissive_factor: json::material::EmissiveFactor(material.emissive_factor.into()),
+        extensions: (!used.is_empty()).then_some(extensions),
+        ..Default::default()
+    })
+}
+
+/// Maps a reference to a texture, or returns `None` when the texture was left out
+fn map_texture_info(
+    info: &world::TextureInfo,
+    textures: &HashMap<usize, json::Index<json::Texture>>,
+    extensions_used: &mut BTreeSet<String>,
+) -> Option<json::texture::Info> {
+    use json::extensions::texture as extension;
+
+    let texture = *textures.get(&info.texture_index)?;
+    let extensions = info.transform.as_ref().map(|transform| {
+        extensions_used.insert("KHR_texture_transform".to_string());
+        extension::Info {
+            texture_transform: Some(extension::TextureTransform {
+                offset: extension::TextureTransformOffset(transform.offset.into()),
+                rotation: extension::TextureTransformRotation(transform.rotation),
+                scale: extension::TextureTransformScale(transform.scale.into()),
+                tex_coord: transform.tex_coord,
+                extras: Default::default(),
+            }),
+            ..Default::default()
+        }
+    });
+    Some(json::texture::Info {
+        index: texture,
+        tex_coord: info.tex_coord,
+        extensions,
+        extras: Default::default(),
+    })
+}
+
+fn map_sampler(sampler: &world::Sampler) -> json::texture::Sampler {
+    use json::texture::{MagFilter, MinFilter, WrappingMode};
+
+    let wrapping_mode = |mode: &world::WrappingMode| {
+        Valid(match mode {
+            world::WrappingMode::ClampToEdge => WrappingMode::ClampToEdge,
+            world::WrappingMode::MirroredRepeat => WrappingMode::MirroredRepeat,
+            world::WrappingMode::Repeat => WrappingMode::Repeat,
+        })
+    };
+    json::texture::Sampler {
+        mag_filter: Some(Valid(match sampler.mag_filter {
+            world::MagFilter::Nearest => MagFilter::Nearest,
+            world::MagFilter::Linear => MagFilter::Linear,
+        })),
+        min_filter: Some(Valid(match sampler.min_filter {
+            world::MinFilter::Nearest => MinFilter::Nearest,
+            world::MinFilter::Linear => MinFilter::Linear,
+            world::MinFilter::NearestMipmapNearest => MinFilter::NearestMipmapNearest,
+            world::MinFilter::LinearMipmapNearest => MinFilter::LinearMipmapNearest,
+            world::MinFilter::NearestMipmapLinear => MinFilter::NearestMipmapLinear,
+            world::MinFilter::LinearMipmapLinear => MinFilter::LinearMipmapLinear,
+        })),
+        wrap_s: wrapping_mode(&sampler.wrap_s),
+        wrap_t: wrapping_mode(&sampler.wrap_t),
+        ..Default::default()
+    }
+}
+
+fn map_camera(camera: &world::Camera) -> json::Camera {
+    let (type_, perspective, orthographic) = match &camera.projection {
+        world::Projection::Perspective(perspective) => (
+            json::camera::Type::Perspective,
+            Some(json::camera::Perspective {
+                aspect_ratio: perspective.aspect_ratio,
+                yfov: perspective.y_fov_rad,
+                zfar: perspective.z_far,
+                znear: perspective.z_near,
+                extensions: None,
+                extras: Default::default(),
+            }),
+            None,
+        ),
+        world::Projection::Orthographic(orthographic) => (
+            json::camera::Type::Orthographic,
+            None,
+            Some(json::camera::Orthographic {
+                xmag: orthographic.x_mag,
+                ymag: orthographic.y_mag,
+                zfar: orthographic.z_far,
+                znear: orthographic.z_near,
+                extensions: None,
+                extras: Default::default(),
+            }),
+        ),
+    };
+    json::Camera {
+        name: None,
+        orthographic,
+        perspective,
+        type_: Valid(type_),
+        extensions: None,
+        extras: Default::default(),
+    }
+}
+
+fn map_light(light: &world::Light) -> json::extensions::scene::khr_lights_punctual::Light {
+    use json::extensions::scene::khr_lights_punctual::{Light, Spot, Type};
+
+    let (type_, spot) = match light.kind {
+        world::LightKind::Directional => (Type::Directional, None),
+        world::LightKind::Point => (Type::Point, None),
+        world::LightKind::Spot {
+            inner_cone_angle,
+            outer_cone_angle,
+        } => (
+            Type::Spot,
+            Some(Spot {
+                inner_cone_angle,
+                outer_cone_angle,
+            }),
+        ),
+    };
+    Light {
+        color: light.color.into(),
+        extensions: None,
+        extras: Default::default(),
+        intensity: light.intensity,
+        name: None,
+        // The importer reads a missing range as zero
+        range: (light.range > 0.0).then_some(light.range),
+        spot,
+        type_: Valid(type_),
+    }
+}
+
+fn map_topology(topology: &world::PrimitiveTopology) -> json::mesh::Mode {
+    match topology {
+        world::PrimitiveTopology::Points => json::mesh::Mode::Points,
+        world::PrimitiveTopology::Lines => json::mesh::Mode::Lines,
+        world::PrimitiveTopology::LineLoop => json::mesh::Mode::LineLoop,
+        world::PrimitiveTopology::LineStrip => json::mesh::Mode::LineStrip,
+        world::PrimitiveTopology::Triangles => json::mesh::Mode::Triangles,
+        world::PrimitiveTopology::TriangleStrip => json::mesh::Mode::TriangleStrip,
+        world::PrimitiveTopology::TriangleFan => json::mesh::Mode::TriangleFan,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{export_glb, export_gltf, import_gltf, import_gltf_slice, ExportOptions};
+
+    #[test]
+    fn helmet_round_trip() {
+        let world = import_gltf("../../assets/DamagedHelmet.glb").unwrap();
+        let exported = export_glb(&world, &ExportOptions::default()).unwrap();
+        let reimported = import_gltf_slice(&exported).unwrap();
+        let report = reimported.validate();
+        assert!(report.is_valid(), "{report}");
+        assert_eq!(reimported.meshes, world.meshes);
+        assert_eq!(reimported.indices, world.indices);
+        let positions = |world: &world::World| {
+            world
+                .vertices
+                .iter()
+                .map(|vertex| vertex.position)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(positions(&reimported), positions(&world));
+        // Textures in slots the world doesn't model, such as the normal map, are left out
+        let used_images = world.materials[0]
+            .textures()
+            .map(|info| world.textures[info.texture_index].image_index)
+            .collect::<std::collections::HashSet<_>>();
+        assert_eq!(reimported.images.len(), used_images.len());
+
+        let exported_again = export_glb(&reimported, &ExportOptions::default()).unwrap();
+        assert_eq!(
+            import_gltf_slice(&exported_again)
+                .unwrap()
+                .to_bytes(world::Compression::None)
+                .unwrap(),
+            reimported.to_bytes(world::Compression::None).unwrap()
+        );
+    }
+
+    #[test]
+    fn skinned_scene_round_trip() {
+        let world = import_gltf("../../assets/SkinnedScenes.gltf").unwrap();
+        let path = std::env::temp_dir().join(format!("skinned-{}.gltf", std::process::id()));
+        export_gltf(&world, &path, &ExportOptions { scene_index: 1 }).unwrap();
+        let reimported = import_gltf(&path);
+        std::fs::remove_file(&path).unwrap();
+        let reimported = reimported.unwrap();
+
+        let names = |world: &world::World, scene_index: usize| {
+            world.scenes[scene_index]
+                .graph
+                .node_weights()
+                .map(|node_index| {
+                    world.metadata[world.nodes[*node_index].metadata_index]
+                        .name
+                        .clone()
+                })
+                // Importing adds a main camera to each scene without an authored camera
+                .filter(|name| name != "Main Camera")
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(&reimported, 0), names(&world, 1));
+        assert_eq!(reimported.skins.len(), 1);
+        assert_eq!(reimported.animations.len(), 2);
+        world
+            .animations
+            .iter()
+            .zip(reimported.animations.iter())
+            .for_each(|(animation, reimported_animation)| {
+                assert_eq!(reimported_animation.name, animation.name);
+                // Only the instances in the exported scene are kept
+                assert_eq!(
+                    reimported_animation.channels.len(),
+                    animation
+                        .channels
+                        .iter()
+                        .filter(|channel| {
+                            world.scenes[1]
+                                .graph
+                                .node_weights()
+                                .any(|node_index| *node_index == channel.target_node_index)
+                        })
+                        .count()
+                );
+            });
+        let cubic = reimported.animations[1]
+            .channels
+            .iter()
+            .find(|channel| channel.tangents.is_some())
+            .unwrap();
+        assert!(matches!(
+            cubic.interpolation,
+            world::Interpolation::CubicSpline
+        ));
+        assert!(matches!(
+            &cubic.tangents.as_ref().unwrap().out_tangents,
+            world::TransformationSet::Scales(scales) if scales[0] == nalgebra_glm::vec3(3.0, 0.0, 0.0)
+        ));
+    }
+
+    fn triangle() -> [world::Vertex; 3] {
+        [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)].map(|(x, y)| world::Vertex {
+            position: nalgebra_glm::vec3(x, y, 0.0),
+            normal: nalgebra_glm::vec3(0.0, 0.0, 1.0),
+            uv_0: nalgebra_glm::vec2(x, y),
+            ..Default::default()
+        })
+    }
+
+    /// A scene with one node drawing a triangle with the first material
+    fn triangle_world() -> world::World {
+        let mut world = world::World {
+            vertices: triangle().to_vec(),
+            meshes: vec![world::Mesh {
+                primitives: vec![world::Primitive {
+                    number_of_vertices: 3,
+                    material_index: Some(0),
+                    ..Default::default()
+                }],
+                weights: Vec::new(),
+            }],
+            scenes: vec![world::Scene::default()],
+            ..Default::default()
+        };
+        let node_index = world.add_node();
+        world.nodes[node_index].mesh_index = Some(0);
+        world.scenes[0].graph.add_node(node_index);
+        world
+    }
+
+    #[test]
+    fn exports_leave_out_images_that_png_cannot_hold() {
+        let image = |format, pixels: Vec<u8>| world::Image {
+            pixels,
+            format,
+            width: 1,
+            height: 1,
+            mip_levels: Vec::new(),
+            compressed: None,
+        };
+        let texture_info = |texture_index| world::TextureInfo {
+            texture_index,
+            tex_coord: 0,
+            transform: None,
+        };
+        let world = world::World {
+            images: vec![
+                image(world::ImageFormat::R32F, 1.5_f32.to_ne_bytes().to_vec()),
+                image(world::ImageFormat::R8, vec![64]),
+            ],
+            textures: vec![
+                world::Texture {
+                    image_index: 0,
+                    sampler_index: None,
+                },
+                world::Texture {
+                    image_index: 1,
+                    sampler_index: None,
+                },
+            ],
+            materials: vec![world::Material {
+                base_color_texture: Some(texture_info(0)),
+                emissive_texture: Some(texture_info(1)),
+                ..Default::default()
+            }],
+            ..triangle_world()
+        };
+        let exported = export_glb(&world, &ExportOptions::default()).unwrap();
+        let imported = import_gltf_slice(&exported).unwrap();
+        assert_eq!(imported.images.len(), 1);
+        assert_eq!(imported.images[0].pixels, [64]);
+        assert_eq!(imported.textures.len(), 1);
+        let material = &imported.materials[0];
+        assert!(material.base_color_texture.is_none());
+        assert_eq!(
+            material
+                .emissive_texture
+                .as_ref()
+                .map(|info| info.texture_index),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn exports_write_only_what_the_scene_uses() {
+        let mut world = world::World::default();
+        for scene_index in 0..2 {
+            world.images.push(world::Image {
+                pixels: vec![scene_index as u8],
+                format: world::ImageFormat::R8,
+                width: 1,
+                height: 1,
+                mip_levels: Vec::new(),
+                compressed: None,
+            });
+            world.samplers.push(world::Sampler {
+                wrap_s: [
+                    world::WrappingMode::ClampToEdge,
+                    world::WrappingMode::MirroredRepeat,
+                ][scene_index]
+                    .clone(),
+                ..Default::default()
+            });
+            world.textures.push(world::Texture {
+                image_index: scene_index,
+                sampler_index: Some(scene_index),
+            });
+            world.materials.push(world::Material {
+                base_color_texture: Some(world::TextureInfo {
+                    texture_index: scene_index,
+                    tex_coord: 0,
+                    transform: None,
+                }),
+                ..Default::default()
+            });
+            world.vertices.extend(triangle());
+            world.meshes.push(world::Mesh {
+                primitives: vec![world::Primitive {
+                    vertex_offset: 3 * scene_index,
+                    number_of_vertices: 3,
+                    material_index: Some(scene_index),
+                    ..Default::default()
+                }],
+                weights: Vec::new(),
+            });
+            world.lights.push(world::Light {
+                intensity: 1.0 + scene_index as f32,
+                range: 0.0,
+                color: nalgebra_glm::vec3(1.0, 1.0, 1.0),
+                kind: world::LightKind::Point,
+            });
+
+            let node_index = world.add_node();
+            world.nodes[node_index].mesh_index = Some(scene_index);
+            world.nodes[node_index].light_index = Some(scene_index);
+            let mut scene = world::Scene::default();
+            scene.graph.add_node(node_index);
+            world.scenes.push(scene);
+        }
+
+        let exported = export_glb(&world, &ExportOptions { scene_index: 1 }).unwrap();
+        let imported = import_gltf_slice(&exported).unwrap();
+        assert_eq!(imported.meshes.len(), 1);
+        assert_eq!(imported.meshes[0].primitives[0].material_index, Some(0));
+        assert_eq!(imported.materials.len(), 1);
+        assert_eq!(imported.textures.len(), 1);
+        assert_eq!(imported.images.len(), 1);
+        assert_eq!(imported.images[0].pixels, [1]);
+        assert_eq!(imported.samplers.len(), 1);
+        assert_eq!(
+            imported.samplers[0].wrap_s,
+            world::WrappingMode::MirroredRepeat
+        );
+        assert_eq!(imported.lights.len(), 1);
+        assert_eq!(imported.lights[0].intensity, 2.0);
+    }
+}
@@ -1,7 +1,301 @@
-pub fn import_gltf(path: impl AsRef<std::path::Path>) -> world::World {
-    let (gltf, buffers, raw_images) = gltf::import(path.as_ref()).expect("Failed to import gltf");
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
 
-    let images = raw_images.into_iter().map(map_image).collect::<Vec<_>>();
+#[derive(thiserror::Error, Debug)]
+pub enum ImportError {
+    #[error("Failed to read the glTF file: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Failed to parse the glTF document: {0}")]
+    Parse(#[from] gltf::Error),
+
+    #[error("Image {image_index} has an unsupported format: {format}")]
+    UnsupportedImageFormat { image_index: usize, format: String },
+
+    #[error("Image {image_index} could not be loaded: {reason}")]
+    InvalidImage { image_index: usize, reason: String },
+
+    #[error("Buffer {buffer_index} could not be loaded: {reason}")]
+    MissingBuffer { buffer_index: usize, reason: String },
+
+    #[error("Accessor {accessor_index} is invalid: {reason}")]
+    InvalidAccessor {
+        accessor_index: usize,
+        reason: &'static str,
+    },
+
+    #[error("Primitive {primitive_index} of mesh {mesh_index} has no positions")]
+    MissingPositions {
+        mesh_index: usize,
+        primitive_index: usize,
+    },
+}
+
+/// Loads the bytes of buffers and images that a glTF document references by URI.
+/// Data URIs are decoded by the importer and never reach a resolver.
+pub trait UriResolver {
+    fn resolve(&mut self, uri: &str) -> std::io::Result<Vec<u8>>;
+}
+
+/// Resolves relative and `file:` URIs from the filesystem
+#[derive(Default, Debug, Clone)]
+pub struct FileResolver {
+    pub base: PathBuf,
+}
+
+impl FileResolver {
+    pub fn new(base: impl Into<PathBuf>) -> Self {
+        Self { base: base.into() }
+    }
+}
+
+impl UriResolver for FileResolver {
+    fn resolve(&mut self, uri: &str) -> std::io::Result<Vec<u8>> {
+        let path = match uri
+            .strip_prefix("file://")
+            .or_else(|| uri.strip_prefix("file:"))
+        {
+            Some(path) => PathBuf::from(path),
+            None => {
+                let relative = urlencoding::decode(uri).map_err(|error| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidInput, error)
+                })?;
+                self.base.join(&*relative)
+            }
+        };
+        std::fs::read(path)
+    }
+}
+
+/// Rejects every external URI, for documents that must embed all of their data
+#[derive(Default, Debug, Copy, Clone)]
+pub struct EmbeddedResolver;
+
+impl UriResolver for EmbeddedResolver {
+    fn resolve(&mut self, uri: &str) -> std::io::Result<Vec<u8>> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("{uri} is not embedded in the document"),
+        ))
+    }
+}
+
+/// Serves URIs that were fetched ahead of time, see [`external_uris`]
+impl UriResolver for HashMap<String, Vec<u8>> {
+    fn resolve(&mut self, uri: &str) -> std::io::Result<Vec<u8>> {
+        self.get(uri).cloned().ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("{uri} was not fetched"),
+            )
+        })
+    }
+}
+
+impl<F> UriResolver for F
+where
+    F: FnMut(&str) -> std::io::Result<Vec<u8>>,
+{
+    fn resolve(&mut self, uri: &str) -> std::io::Result<Vec<u8>> {
+        self(uri)
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct ImportOptions {
+    /// Adds a "Main Camera" node to every scene without an authored camera,
+    /// so that each scene has a default camera. Without it those scenes
+    /// have no default camera until the caller adds one.
+    pub add_default_camera: bool,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        Self {
+            add_default_camera: true,
+        }
+    }
+}
+
+/// Imports a .gltf or .glb file, loading external buffers and images relative to its directory
+pub fn import_gltf(path: impl AsRef<Path>) -> Result<world::World, ImportError> {
+    let path = path.as_ref();
+    let bytes = std::fs::read(path)?;
+    let base = path.parent().unwrap_or_else(|| Path::new("./"));
+    import_gltf_with_resolver(
+        &bytes,
+        &mut FileResolver::new(base),
+        &ImportOptions::default(),
+    )
+}
+
+/// Imports .gltf or .glb bytes whose buffers and images are all embedded
+pub fn import_gltf_slice(bytes: &[u8]) -> Result<world::World, ImportError> {
+    import_gltf_with_resolver(bytes, &mut EmbeddedResolver, &ImportOptions::default())
+}
+
+/// Imports .gltf or .glb bytes, loading external buffers and images through the resolver
+pub fn import_gltf_with_resolver(
+    bytes: &[u8],
+    resolver: &mut impl UriResolver,
+    options: &ImportOptions,
+) -> Result<world::World, ImportError> {
+    let (document, blob) = parse(bytes)?;
+    let buffers = load_buffers(&document, blob, resolver)?;
+    let images = load_images(&document, &buffers, resolver)?;
+    map_document(&document, &buffers, images, options)
+}
+
+/// Lists the external URIs that a resolver will be asked for,
+/// so that asynchronous sources can fetch them before importing
+pub fn external_uris(bytes: &[u8]) -> Result<Vec<String>, ImportError> {
+    let (document, _) = parse(bytes)?;
+    let buffer_uris = document
+        .buffers()
+        .filter_map(|buffer| match buffer.source() {
+            gltf::buffer::Source::Uri(uri) => Some(uri),
+            gltf::buffer::Source::Bin => None,
+        });
+    let image_uris = document.images().filter_map(|image| match image.source() {
+        gltf::image::Source::Uri { uri, .. } => Some(uri),
+        gltf::image::Source::View { .. } => None,
+    });
+    let mut uris = Vec::new();
+    buffer_uris
+        .chain(image_uris)
+        .filter(|uri| !uri.starts_with("data:"))
+        .for_each(|uri| {
+            if !uris.iter().any(|existing| existing == uri) {
+                uris.push(uri.to_string());
+            }
+        });
+    Ok(uris)
+}
+
+fn parse(bytes: &[u8]) -> Result<(gltf::Document, Option<Vec<u8>>), ImportError> {
+    let gltf::Gltf { document, blob } = gltf::Gltf::from_slice_without_validation(bytes)?;
+    let mut json = document.into_json();
+    // Unsupported required extensions would fail validation, although most of them
+    // (like material extensions) only degrade how the asset looks
+    json.extensions_required.retain(|name| {
+        let supported = gltf::json::extensions::ENABLED_EXTENSIONS.contains(&name.as_str());
+        if !supported {
+            log::warn!("Importing glTF without support for its required extension {name}");
+        }
+        supported
+    });
+    let document = gltf::Document::from_json(json)?;
+    Ok((document, blob))
+}
+
+fn read_uri(uri: &str, resolver: &mut impl UriResolver) -> std::io::Result<Vec<u8>> {
+    let Some(data) = uri.strip_prefix("data:") else {
+        return resolver.resolve(uri);
+    };
+    let invalid = |message: String| std::io::Error::new(std::io::ErrorKind::InvalidData, message);
+    let (_, encoded) = data
+        .split_once(";base64,")
+        .ok_or_else(|| invalid("only base64 data URIs are supported".to_string()))?;
+    base64::decode(encoded).map_err(|error| invalid(error.to_string()))
+}
+
+fn load_buffers(
+    document: &gltf::Document,
+    mut blob: Option<Vec<u8>>,
+    resolver: &mut impl UriResolver,
+) -> Result<Vec<gltf::buffer::Data>, ImportError> {
+    document
+        .buffers()
+        .map(|buffer| {
+            let missing = |reason: String| ImportError::MissingBuffer {
+                buffer_index: buffer.index(),
+                reason,
+            };
+            let mut data = match buffer.source() {
+                gltf::buffer::Source::Uri(uri) => {
+                    read_uri(uri, resolver).map_err(|error| missing(error.to_string()))?
+                }
+                gltf::buffer::Source::Bin => blob
+                    .take()
+                    .ok_or_else(|| missing("the binary chunk is missing".to_string()))?,
+            };
+            if data.len() < buffer.length() {
+                return Err(missing(format!(
+                    "expected {} bytes but found {}",
+                    buffer.length(),
+                    data.len()
+                )));
+            }
+            // Accessors may read up to the next four byte boundary
+            while data.len() % 4 != 0 {
+                data.push(0);
+            }
+            Ok(gltf::buffer::Data(data))
+        })
+        .collect()
+}
+
+fn load_images(
+    document: &gltf::Document,
+    buffers: &[gltf::buffer::Data],
+    resolver: &mut impl UriResolver,
+) -> Result<Vec<world::Image>, ImportError> {
+    document
+        .images()
+        .map(|image| {
+            let image_index = image.index();
+            let invalid = |reason: String| ImportError::InvalidImage {
+                image_index,
+                reason,
+            };
+            let (encoded, mime_type, uri) = match image.source() {
+                gltf::image::Source::View { view, mime_type } => {
+                    let begin = view.offset();
+                    let encoded = buffers[view.buffer().index()]
+                        .get(begin..begin + view.length())
+                        .ok_or_else(|| invalid("its buffer view is out of range".to_string()))?;
+                    (Cow::Borrowed(encoded), Some(mime_type), None)
+                }
+                gltf::image::Source::Uri { uri, mime_type } => {
+                    let encoded =
+                        read_uri(uri, resolver).map_err(|error| invalid(error.to_string()))?;
+                    (Cow::Owned(encoded), mime_type, Some(uri))
+                }
+            };
+            let format = match mime_type {
+                Some("image/png") => Some(image::ImageFormat::Png),
+                Some("image/jpeg") => Some(image::ImageFormat::Jpeg),
+                _ => None,
+            }
+            .or_else(|| uri.and_then(|uri| image::ImageFormat::from_path(uri).ok()))
+            .or_else(|| image::guess_format(&encoded).ok())
+            .ok_or_else(|| ImportError::UnsupportedImageFormat {
+                image_index,
+                format: mime_type.unwrap_or("an unrecognized encoding").to_string(),
+            })?;
+            let decoded = image::load_from_memory_with_format(&encoded, format).map_err(
+                |error| match error {
+                    image::ImageError::Unsupported(error) => ImportError::UnsupportedImageFormat {
+                        image_index,
+                        format: error.to_string(),
+                    },
+                    error => invalid(error.to_string()),
+                },
+            )?;
+            map_dynamic_image(image_index, decoded)
+        })
+        .collect()
+}
+
+fn map_document(
+    gltf: &gltf::Document,
+    buffers: &[gltf::buffer::Data],
+    images: Vec<world::Image>,
+    options: &ImportOptions,
+) -> Result<world::World, ImportError> {
     let samplers = gltf.samplers().map(map_sampler).collect::<Vec<_>>();
     let textures = gltf
         .textures()
@@ -10,166 +304,75 @@ pub fn import_gltf(path: impl AsRef<std::path::Path>) -> world::World {
             sampler_index: texture.sampler().index(),
         })
         .collect::<Vec<_>>();
-    let materials = gltf
-        .materials()
-        .map(|material| world::Material {
-            base_color_factor: nalgebra_glm::Vec4::from(
-                material.pbr_metallic_roughness().base_color_factor(),
-            ),
-            alpha_mode: convert_alpha_mode(material.alpha_mode()),
-            alpha_cutoff: material.alpha_cutoff(),
-            base_color_texture_index: material
-                .pbr_metallic_roughness()
-                .base_color_texture()
-                .map(|texture| texture.texture().index())
-                .unwrap_or_default(),
-            emissive_factor: material.emissive_factor().into(),
-            emissive_texture_index: material
-                .emissive_texture()
-                .map(|texture| texture.texture().index())
-                .unwrap_or_default(),
-        })
-        .collect::<Vec<_>>();
+    let mut materials = gltf.materials().map(map_material).collect::<Vec<_>>();
+    // Primitives without a material use the default material, which is only added when needed
+    let default_material_index = materials.len();
+    let mut uses_default_material = false;
 
-    let (meshes, vertices, indices) = {
-        let (mut vertices, mut indices) = (vec![], vec![]);
+    let (meshes, vertices, indices, morph_target_vertices) = {
+        let (mut vertices, mut indices, mut morph_target_vertices) = (vec![], vec![], vec![]);
         let meshes = gltf
             .meshes()
             .map(|mesh| {
-                world::Mesh {
-                    primitives: mesh
-                        .primitives()
-                        .map(|primitive| {
-                            let primitive_vertices: Vec<world::Vertex> = {
-                                let reader =
-                                    primitive.reader(|buffer| Some(&*buffers[buffer.index()]));
-
-                                let mut positions = Vec::new();
-                                let read_positions = reader
-                                    .read_positions()
-                                    .expect("Failed to read gltf vertex positions");
-                                read_positions.for_each(|position| {
-                                    positions.push(nalgebra_glm::Vec3::from(position));
-                                });
-                                let number_of_vertices = positions.len();
-                                let normals = reader.read_normals().map_or(
-                                    vec![nalgebra_glm::vec3(0.0, 0.0, 0.0); number_of_vertices],
-                                    |normals| {
-                                        normals.map(nalgebra_glm::Vec3::from).collect::<Vec<_>>()
-                                    },
-                                );
-                                let map_to_vec2 =
-                            |coords: gltf::mesh::util::ReadTexCoords| -> Vec<nalgebra_glm::Vec2> {
-                                coords
-                                    .into_f32()
-                                    .map(nalgebra_glm::Vec2::from)
-                                    .collect::<Vec<_>>()
-                            };
-                                let uv_0 = reader.read_tex_coords(0).map_or(
-                                    vec![nalgebra_glm::vec2(0.0, 0.0); number_of_vertices],
-                                    map_to_vec2,
-                                );
-                                let uv_1 = reader.read_tex_coords(1).map_or(
-                                    vec![nalgebra_glm::vec2(0.0, 0.0); number_of_vertices],
-                                    map_to_vec2,
-                                );
-                                let convert_joints =
-                                |joints: gltf::mesh::util::ReadJoints| -> Vec<nalgebra_glm::Vec4> {
-                                    joints
-                                        .into_u16()
-                                        .map(|joint| {
-                                            nalgebra_glm::vec4(
-                                                joint[0] as _,
-                                                joint[1] as _,
-                                                joint[2] as _,
-                                                joint[3] as _,
-                                            )
-                                        })
-                                        .collect::<Vec<_>>()
-                                };
-                                let joints_0 = reader.read_joints(0).map_or(
-                                    vec![
-                                        nalgebra_glm::vec4(0.0, 0.0, 0.0, 0.0);
-                                        number_of_vertices
-                                    ],
-                                    convert_joints,
-                                );
-                                let convert_weights =
-                            |weights: gltf::mesh::util::ReadWeights| -> Vec<nalgebra_glm::Vec4> {
-                                weights.into_f32().map(nalgebra_glm::Vec4::from).collect()
-                            };
-                                let weights_0 = reader.read_weights(0).map_or(
-                                    vec![
-                                        nalgebra_glm::vec4(1.0, 0.0, 0.0, 0.0);
-                                        number_of_vertices
-                                    ],
-                                    convert_weights,
-                                );
-                                let convert_colors =
-                                |colors: gltf::mesh::util::ReadColors| -> Vec<nalgebra_glm::Vec3> {
-                                    colors
-                                        .into_rgb_f32()
-                                        .map(nalgebra_glm::Vec3::from)
-                                        .collect::<Vec<_>>()
-                                };
-                                let colors_0 = reader.read_colors(0).map_or(
-                                    vec![nalgebra_glm::vec3(1.0, 1.0, 1.0); number_of_vertices],
-                                    convert_colors,
-                                );
-
-                                // every vertex is guaranteed to have a position attribute,
-                                // so we can use the position attribute array to index into the other attribute arrays
-
-                                positions
-                                    .into_iter()
-                                    .enumerate()
-                                    .map(|(index, position)| world::Vertex {
-                                        position,
-                                        normal: normals[index],
-                                        uv_0: uv_0[index],
-                                        uv_1: uv_1[index],
-                                        joint_0: joints_0[index],
-                                        weight_0: weights_0[index],
-                                        color_0: colors_0[index],
-                                    })
-                                    .collect()
-                            };
-
-                            let primitive_indices: Vec<u32> = primitive
-                                .reader(|buffer| Some(&*buffers[buffer.index()]))
-                                .read_indices()
-                                .take()
-                                .map(|read_indices| read_indices.into_u32().collect())
-                                .unwrap_or_default();
-
-                            let primitive = world::Primitive {
-                                topology: map_mesh_mode(primitive.mode()),
-                                material_index: primitive.material().index(),
-                                vertex_offset: vertices.len(),
-                                index_offset: indices.len(),
-                                number_of_vertices: primitive_vertices.len(),
-                                number_of_indices: primitive_indices.len(),
-                            };
-
-                            vertices.extend(primitive_vertices);
-                            indices.extend(primitive_indices);
-
-                            primitive
-                        })
-                        .collect::<Vec<_>>(),
-                }
+                let primitives = mesh
+                    .primitives()
+                    .map(|primitive| {
+                        let (
+                            topology,
+                            primitive_vertices,
+                            primitive_indices,
+                            primitive_morph_targets,
+                        ) = map_primitive(mesh.index(), &primitive, buffers)?;
+                        let world_primitive = world::Primitive {
+                            topology,
+                            material_index: Some(primitive.material().index().unwrap_or_else(
+                                || {
+                                    uses_default_material = true;
+                                    default_material_index
+                                },
+                            )),
+                            vertex_offset: vertices.len(),
+                            index_offset: indices.len(),
+                            number_of_vertices: primitive_vertices.len(),
+                            number_of_indices: primitive_indices.len(),
+                            morph_target_offset: morph_target_vertices.len(),
+                            number_of_morph_targets: primitive_morph_targets.len(),
+                            lods: Vec::new(),
+                        };
+
+                        vertices.extend(primitive_vertices);
+                        indices.extend(primitive_indices);
+                        primitive_morph_targets
+                            .into_iter()
+                            .for_each(|target| morph_target_vertices.extend(target));
+
+                        Ok(world_primitive)
+                    })
+                    .collect::<Result<Vec<_>, ImportError>>()?;
+                Ok(world::Mesh {
+                    primitives,
+                    weights: mesh.weights().map(<[f32]>::to_vec).unwrap_or_default(),
+                })
             })
-            .collect::<Vec<_>>();
-        (meshes, vertices, indices)
+            .collect::<Result<Vec<_>, ImportError>>()?;
+        (meshes, vertices, indices, morph_target_vertices)
     };
+    if uses_default_material {
+        materials.push(world::Material::default());
+    }
 
-    let (mut scenes, mut nodes, mut transforms, mut metadata) = {
+    // glTF nodes become one world node per scene that instances them,
+    // so each scene maps glTF node indices to its own world nodes
+    let (mut scenes, mut nodes, mut transforms, mut metadata, morph_weights, scene_node_indices) = {
+        let mut scene_node_indices = Vec::new();
         let mut nodes = Vec::new();
         let mut transforms = Vec::new();
         let mut metadata = Vec::new();
+        let mut morph_weights = Vec::new();
         let scenes = gltf
             .scenes()
             .map(|gltf_scene| {
+                #[allow(clippy::too_many_arguments)]
                 fn visit_node(
                     parent_graph_node_index: Option<petgraph::graph::NodeIndex>,
                     node: &gltf::Node,
@@ -177,6 +380,8 @@ pub fn import_gltf(path: impl AsRef<std::path::Path>) -> world::World {
                     nodes: &mut Vec<world::Node>,
                     transforms: &mut Vec<world::Transform>,
                     metadata: &mut Vec<world::NodeMetadata>,
+                    morph_weights: &mut Vec<Vec<f32>>,
+                    node_indices: &mut Vec<Option<usize>>,
                 ) {
                     let transform_index = transforms.len();
                     transforms.push(world::Transform::from(node.transform().decomposed()));
@@ -186,15 +391,35 @@ pub fn import_gltf(path: impl AsRef<std::path::Path>) -> world::World {
                         name: node.name().unwrap_or("Node").to_string(),
                     });
 
+                    let morph_weights_index = node.mesh().and_then(|mesh| {
+                        let number_of_morph_targets = mesh
+                            .primitives()
+                            .map(|primitive| primitive.morph_targets().len())
+                            .max()
+                            .unwrap_or_default();
+                        if number_of_morph_targets == 0 {
+                            return None;
+                        }
+                        let weights = node
+                            .weights()
+                            .or(mesh.weights())
+                            .map(<[f32]>::to_vec)
+                            .unwrap_or_else(|| vec![0.0; number_of_morph_targets]);
+                        morph_weights.push(weights);
+                        Some(morph_weights.len() - 1)
+                    });
+
                     let node_index = nodes.len();
                     nodes.push(world::Node {
                         metadata_index,
                         transform_index,
-                        camera_index: node.camera().map(|camera| camera.index() + 1), // + 1 because the first camera slot is reserved for the main camera
+                        camera_index: node.camera().map(|camera| camera.index()),
                         mesh_index: node.mesh().map(|mesh| mesh.index()),
                         light_index: node.light().map(|light| light.index()),
+                        morph_weights_index,
                         ..Default::default()
                     });
+                    node_indices[node.index()] = Some(node_index);
                     let graph_node_index = scene.graph.add_node(node_index);
                     if let Some(parent_graph_node_index) = parent_graph_node_index {
                         if parent_graph_node_index != graph_node_index {
@@ -211,6 +436,8 @@ pub fn import_gltf(path: impl AsRef<std::path::Path>) -> world::World {
                             nodes,
                             transforms,
                             metadata,
+                            morph_weights,
+                            node_indices,
                         );
                     });
                 }
@@ -235,8 +462,11 @@ pub fn import_gltf(path: impl AsRef<std::path::Path>) -> world::World {
                     rigid_body_index: None,
                     primitive_mesh_index: None,
                     aabb_index: None,
+                    morph_weights_index: None,
+                    skin_index: None,
                 });
 
+                let mut node_indices = vec![None; gltf.nodes().len()];
                 let root_node_index = scene.graph.add_node(node_index);
                 gltf_scene.nodes().for_each(|root_node| {
                     visit_node(
@@ -246,39 +476,75 @@ pub fn import_gltf(path: impl AsRef<std::path::Path>) -> world::World {
                         &mut nodes,
                         &mut transforms,
                         &mut metadata,
+                        &mut morph_weights,
+                        &mut node_indices,
                     );
                 });
+                scene_node_indices.push(node_indices);
                 scene
             })
             .collect::<Vec<_>>();
-        (scenes, nodes, transforms, metadata)
+        (
+            scenes,
+            nodes,
+            transforms,
+            metadata,
+            morph_weights,
+            scene_node_indices,
+        )
     };
 
-    let skins = gltf
+    let inverse_bind_matrices = gltf
         .skins()
         .map(|gltf_skin| {
             let reader = gltf_skin.reader(|buffer| Some(&buffers[buffer.index()]));
-            let inverse_bind_matrices = reader
+            reader
                 .read_inverse_bind_matrices()
                 .map_or(Vec::new(), |matrices| {
                     matrices.map(nalgebra_glm::Mat4::from).collect::<Vec<_>>()
-                });
+                })
+        })
+        .collect::<Vec<_>>();
+
+    // A skin is imported once for every scene with a node it deforms,
+    // targeting the joints instanced in that scene
+    let mut skins = Vec::new();
+    for node_indices in scene_node_indices.iter() {
+        for gltf_skin in gltf.skins() {
+            let skinned_node_indices = gltf
+                .nodes()
+                .filter(|node| {
+                    node.skin()
+                        .is_some_and(|skin| skin.index() == gltf_skin.index())
+                })
+                .filter_map(|node| node_indices[node.index()])
+                .collect::<Vec<_>>();
+            if skinned_node_indices.is_empty() {
+                continue;
+            }
             let joints = gltf_skin
                 .joints()
                 .enumerate()
                 .map(|(index, joint_node)| {
-                    let inverse_bind_matrix = *inverse_bind_matrices
+                    let inverse_bind_matrix = *inverse_bind_matrices[gltf_skin.index()]
                         .get(index)
                         .unwrap_or(&nalgebra_glm::Mat4::identity());
-                    world::Joint {
+                    node_indices[joint_node.index()].map(|target_node_index| world::Joint {
                         inverse_bind_matrix,
-                        target_node_index: joint_node.index(),
-                    }
+                        target_node_index,
+                    })
                 })
-                .collect();
-            world::Skin { joints }
-        })
-        .collect::<Vec<_>>();
+                .collect::<Option<Vec<_>>>();
+            // Joints outside of the scene can't be posed there
+            let Some(joints) = joints else {
+                continue;
+            };
+            skinned_node_indices
+                .into_iter()
+                .for_each(|node_index| nodes[node_index].skin_index = Some(skins.len()));
+            skins.push(world::Skin { joints });
+        }
+    }
 
     let animations = gltf
         .animations()
@@ -286,15 +552,19 @@ pub fn import_gltf(path: impl AsRef<std::path::Path>) -> world::World {
             let channels = animation
                 .channels()
                 .map(|channel| {
-                    let target_node_index = channel.target().node().index();
+                    let gltf_node_index = channel.target().node().index();
                     let reader = channel.reader(|buffer| Some(&buffers[buffer.index()]));
                     let inputs = reader
                         .read_inputs()
-                        .expect("Failed to read animation channel inputs!")
+                        .ok_or(ImportError::InvalidAccessor {
+                            accessor_index: channel.sampler().input().index(),
+                            reason: "the animation inputs could not be read",
+                        })?
                         .collect::<Vec<_>>();
-                    let outputs = reader
-                        .read_outputs()
-                        .expect("Failed to read animation channel outputs!");
+                    let outputs = reader.read_outputs().ok_or(ImportError::InvalidAccessor {
+                        accessor_index: channel.sampler().output().index(),
+                        reason: "the animation outputs could not be read",
+                    })?;
                     let transformations = match outputs {
                         gltf::animation::util::ReadOutputs::Translations(translations) => {
                             let translations = translations
@@ -318,25 +588,54 @@ pub fn import_gltf(path: impl AsRef<std::path::Path>) -> world::World {
                             world::TransformationSet::MorphTargetWeights(morph_target_weights)
                         }
                     };
-                    world::Channel {
-                        target_node_index,
-                        inputs,
-                        transformations,
-                        interpolation: world::Interpolation::default(),
+                    let interpolation = map_interpolation(channel.sampler().interpolation());
+                    let (transformations, tangents) = match interpolation {
+                        world::Interpolation::CubicSpline => {
+                            let (transformations, tangents) =
+                                split_cubic_spline(transformations, inputs.len()).ok_or(
+                                    ImportError::InvalidAccessor {
+                                        accessor_index: channel.sampler().output().index(),
+                                        reason: "cubic spline outputs need an in tangent, \
+                                                 value and out tangent for every input",
+                                    },
+                                )?;
+                            (transformations, Some(tangents))
+                        }
+                        _ => (transformations, None),
+                    };
+                    if !transformations.matches_inputs(inputs.len()) {
+                        return Err(ImportError::InvalidAccessor {
+                            accessor_index: channel.sampler().output().index(),
+                            reason: "animation outputs need a value for every input",
+                        });
                     }
+                    // Every instance of the target node is animated
+                    Ok(scene_node_indices
+                        .iter()
+                        .filter_map(|node_indices| node_indices[gltf_node_index])
+                        .map(|target_node_index| world::Channel {
+                            target_node_index,
+                            inputs: inputs.clone(),
+                            transformations: transformations.clone(),
+                            interpolation: interpolation.clone(),
+                            tangents: tangents.clone(),
+                        })
+                        .collect::<Vec<_>>())
                 })
-                .collect::<Vec<_>>();
+                .collect::<Result<Vec<_>, ImportError>>()?
+                .concat();
             let max_animation_time = channels
                 .iter()
                 .flat_map(|channel| channel.inputs.iter().copied())
                 .fold(0.0, f32::max);
-            world::Animation {
+            Ok(world::Animation {
                 channels,
                 time: 0.0,
                 max_animation_time,
-            }
+                name: animation.name().unwrap_or("Animation").to_string(),
+            })
         })
-        .collect::<Vec<_>>();
+        .collect::<Result<Vec<_>, ImportError>>()?;
 
     let lights = match gltf.lights() {
         Some(lights) => lights.into_iter().map(map_light).collect(),
@@ -347,45 +646,70 @@ pub fn import_gltf(path: impl AsRef<std::path::Path>) -> world::World {
         scenes.push(world::Scene::default());
     };
 
-    let camera = world::Camera::default();
+    // Scenes look through their first authored camera in depth first order,
+    // and otherwise through a synthetic camera shared by all of them
+    let mut cameras = gltf.cameras().map(map_camera).collect::<Vec<_>>();
+    let mut main_camera_index = None;
+    for scene in scenes.iter_mut() {
+        let authored_camera = scene
+            .graph
+            .node_indices()
+            .find(|graph_node_index| nodes[scene.graph[*graph_node_index]].camera_index.is_some());
+        if let Some(graph_node_index) = authored_camera {
+            scene.default_camera_graph_node_index = Some(graph_node_index);
+            continue;
+        }
+        if !options.add_default_camera {
+            continue;
+        }
 
-    let transform_index = transforms.len();
-    let transform = world::Transform {
-        translation: camera.orientation.position(),
-        rotation: camera.orientation.look_at_offset(),
-        ..Default::default()
-    };
-    transforms.push(transform);
+        let camera_index = *main_camera_index.get_or_insert_with(|| {
+            cameras.push(world::Camera {
+                synthetic: true,
+                ..Default::default()
+            });
+            cameras.len() - 1
+        });
+        let camera = &cameras[camera_index];
 
-    let metadata_index = metadata.len();
-    metadata.push(world::NodeMetadata {
-        name: "Main Camera".to_string(),
-    });
+        let transform_index = transforms.len();
+        transforms.push(world::Transform {
+            translation: camera.orientation.position(),
+            rotation: camera.orientation.look_at_offset(),
+            ..Default::default()
+        });
 
-    let mut cameras = vec![camera];
-
-    let node_index = nodes.len();
-    nodes.push(world::Node {
-        transform_index,
-        metadata_index,
-        camera_index: Some(0),
-        mesh_index: None,
-        light_index: None,
-        rigid_body_index: None,
-        primitive_mesh_index: None,
-        aabb_index: None,
-    });
+        let metadata_index = metadata.len();
+        metadata.push(world::NodeMetadata {
+            name: "Main Camera".to_string(),
+        });
 
-    let camera_graph_node_index = scenes[0].graph.add_node(node_index);
-    scenes[0].graph.add_edge(
-        petgraph::graph::NodeIndex::new(0),
-        camera_graph_node_index,
-        (),
-    );
-    scenes[0].default_camera_graph_node_index = camera_graph_node_index;
+        let node_index = nodes.len();
+        nodes.push(world::Node {
+            transform_index,
+            metadata_index,
+            camera_index: Some(camera_index),
+            mesh_index: None,
+            light_index: None,
+            rigid_body_index: None,
+            primitive_mesh_index: None,
+            aabb_index: None,
+            morph_weights_index: None,
+            skin_index: None,
+        });
 
-    let gltf_cameras = gltf.cameras().map(map_camera).collect::<Vec<_>>();
-    cameras.extend_from_slice(&gltf_cameras);
+        // The placeholder scene of a document without scenes has no root to attach to
+        let has_root = scene.graph.node_count() > 0;
+        let camera_graph_node_index = scene.graph.add_node(node_index);
+        if has_root {
+            scene.graph.add_edge(
+                petgraph::graph::NodeIndex::new(0),
+                camera_graph_node_index,
+                (),
+            );
+        }
+        scene.default_camera_graph_node_index = Some(camera_graph_node_index);
+    }
 
     let mut aabbs = Vec::new();
     scenes.iter().for_each(|scene| {
@@ -409,7 +733,7 @@ pub fn import_gltf(path: impl AsRef<std::path::Path>) -> world::World {
         });
     });
 
-    world::World {
+    Ok(world::World {
         animations,
         cameras,
         images,
@@ -427,7 +751,219 @@ pub fn import_gltf(path: impl AsRef<std::path::Path>) -> world::World {
         vertices,
         primitive_meshes: vec![],
         aabbs,
+        morph_target_vertices,
+        morph_weights,
+        default_scene_index: gltf
+            .default_scene()
+            .map(|scene| scene.index())
+            .unwrap_or_default(),
+    })
+}
+
+type PrimitiveData = (
+    world::PrimitiveTopology,
+    Vec<world::Vertex>,
+    Vec<u32>,
+    Vec<Vec<world::MorphTargetVertex>>,
+);
+
+fn map_primitive(
+    mesh_index: usize,
+    primitive: &gltf::Primitive<'_>,
+    buffers: &[gltf::buffer::Data],
+) -> Result<PrimitiveData, ImportError> {
+    let reader = primitive.reader(|buffer| Some(&*buffers[buffer.index()]));
+
+    let positions = reader
+        .read_positions()
+        .ok_or(ImportError::MissingPositions {
+            mesh_index,
+            primitive_index: primitive.index(),
+        })?
+        .map(nalgebra_glm::Vec3::from)
+        .collect::<Vec<_>>();
+    let number_of_vertices = positions.len();
+
+    // Every other attribute is indexed by the position attribute's vertices,
+    // so a shorter or longer accessor would index out of bounds
+    let check_count = |accessor: Option<gltf::Accessor<'_>>| match accessor {
+        Some(accessor) if accessor.count() != number_of_vertices => {
+            Err(ImportError::InvalidAccessor {
+                accessor_index: accessor.index(),
+                reason: "its count does not match the primitive's positions",
+            })
+        }
+        _ => Ok(()),
+    };
+    for (semantic, _) in primitive.attributes() {
+        check_count(primitive.get(&semantic))?;
     }
+    for target in primitive.morph_targets() {
+        check_count(target.positions())?;
+        check_count(target.normals())?;
+        check_count(target.tangents())?;
+    }
+
+    let normals = reader.read_normals().map_or(
+        vec![nalgebra_glm::vec3(0.0, 0.0, 0.0); number_of_vertices],
+        |normals| normals.map(nalgebra_glm::Vec3::from).collect::<Vec<_>>(),
+    );
+    let tangents = reader.read_tangents().map_or(
+        vec![nalgebra_glm::vec4(0.0, 0.0, 0.0, 0.0); number_of_vertices],
+        |tangents| tangents.map(nalgebra_glm::Vec4::from).collect::<Vec<_>>(),
+    );
+    let map_to_vec2 = |coords: gltf::mesh::util::ReadTexCoords| -> Vec<nalgebra_glm::Vec2> {
+        coords
+            .into_f32()
+            .map(nalgebra_glm::Vec2::from)
+            .collect::<Vec<_>>()
+    };
+    let uv_0 = reader.read_tex_coords(0).map_or(
+        vec![nalgebra_glm::vec2(0.0, 0.0); number_of_vertices],
+        map_to_vec2,
+    );
+    let uv_1 = reader.read_tex_coords(1).map_or(
+        vec![nalgebra_glm::vec2(0.0, 0.0); number_of_vertices],
+        map_to_vec2,
+    );
+    let convert_joints = |joints: gltf::mesh::util::ReadJoints| -> Vec<nalgebra_glm::Vec4> {
+        joints
+            .into_u16()
+            .map(|joint| {
+                nalgebra_glm::vec4(joint[0] as _, joint[1] as _, joint[2] as _, joint[3] as _)
+            })
+            .collect::<Vec<_>>()
+    };
+    let joints_0 = reader.read_joints(0).map_or(
+        vec![nalgebra_glm::vec4(0.0, 0.0, 0.0, 0.0); number_of_vertices],
+        convert_joints,
+    );
+    let convert_weights = |weights: gltf::mesh::util::ReadWeights| -> Vec<nalgebra_glm::Vec4> {
+        weights.into_f32().map(nalgebra_glm::Vec4::from).collect()
+    };
+    let weights_0 = reader.read_weights(0).map_or(
+        vec![nalgebra_glm::vec4(1.0, 0.0, 0.0, 0.0); number_of_vertices],
+        convert_weights,
+    );
+    let convert_colors = |colors: gltf::mesh::util::ReadColors| -> Vec<nalgebra_glm::Vec3> {
+        colors
+            .into_rgb_f32()
+            .map(nalgebra_glm::Vec3::from)
+            .collect::<Vec<_>>()
+    };
+    let colors_0 = reader.read_colors(0).map_or(
+        vec![nalgebra_glm::vec3(1.0, 1.0, 1.0); number_of_vertices],
+        convert_colors,
+    );
+
+    let mut primitive_vertices = positions
+        .into_iter()
+        .enumerate()
+        .map(|(index, position)| world::Vertex {
+            position,
+            normal: normals[index],
+            uv_0: uv_0[index],
+            uv_1: uv_1[index],
+            joint_0: joints_0[index],
+            weight_0: weights_0[index],
+            color_0: colors_0[index],
+            tangent: tangents[index],
+        })
+        .collect::<Vec<_>>();
+
+    let mut primitive_indices: Vec<u32> = reader
+        .read_indices()
+        .map(|read_indices| read_indices.into_u32().collect())
+        .unwrap_or_default();
+    if let Some(accessor) = primitive.indices() {
+        if primitive_indices.len() != accessor.count() {
+            return Err(ImportError::InvalidAccessor {
+                accessor_index: accessor.index(),
+                reason: "the indices could not be read",
+            });
+        }
+        if primitive_indices
+            .iter()
+            .any(|index| *index as usize >= number_of_vertices)
+        {
+            return Err(ImportError::InvalidAccessor {
+                accessor_index: accessor.index(),
+                reason: "an index is past the primitive's last vertex",
+            });
+        }
+    }
+
+    let mut primitive_morph_targets: Vec<Vec<world::MorphTargetVertex>> = reader
+        .read_morph_targets()
+        .map(|(positions, normals, tangents)| {
+            let read_displacements = |displacements: Option<gltf::accessor::Iter<'_, [f32; 3]>>| {
+                displacements.map_or(
+                    vec![nalgebra_glm::vec3(0.0, 0.0, 0.0); number_of_vertices],
+                    |displacements| {
+                        displacements
+                            .map(nalgebra_glm::Vec3::from)
+                            .collect::<Vec<_>>()
+                    },
+                )
+            };
+            let positions = read_displacements(positions);
+            let normals = read_displacements(normals);
+            let tangents = read_displacements(tangents);
+            (0..number_of_vertices)
+                .map(|index| world::MorphTargetVertex {
+                    position: positions[index],
+                    normal: normals[index],
+                    tangent: tangents[index],
+                })
+                .collect()
+        })
+        .collect();
+
+    let mut topology = map_mesh_mode(primitive.mode());
+    let missing_normals = primitive.get(&gltf::Semantic::Normals).is_none();
+    let missing_tangents = primitive.get(&gltf::Semantic::Tangents).is_none();
+    let has_triangles = matches!(
+        topology,
+        world::PrimitiveTopology::Triangles
+            | world::PrimitiveTopology::TriangleStrip
+            | world::PrimitiveTopology::TriangleFan
+    );
+    if has_triangles && (missing_normals || missing_tangents) {
+        // Attributes are generated per triangle, so strips and fans become lists first
+        primitive_indices =
+            world::triangulate(topology, &primitive_indices, primitive_vertices.len());
+        topology = world::PrimitiveTopology::Triangles;
+
+        let mut remap_morph_targets = |sources: Vec<u32>| {
+            primitive_morph_targets.iter_mut().for_each(|target| {
+                *target = sources
+                    .iter()
+                    .map(|source| target[*source as usize])
+                    .collect();
+            });
+        };
+        // The spec asks for flat normals when they are missing,
+        // which needs a vertex per triangle corner
+        if missing_normals {
+            remap_morph_targets(world::compute_flat_normals(
+                &mut primitive_vertices,
+                &mut primitive_indices,
+            ));
+        }
+        if missing_tangents {
+            remap_morph_targets(world::generate_tangents(
+                &mut primitive_vertices,
+                &mut primitive_indices,
+            ));
+        }
+    }
+
+    Ok((
+        topology,
+        primitive_vertices,
+        primitive_indices,
+        primitive_morph_targets,
+    ))
 }
 
 pub fn convert_alpha_mode(mode: gltf::material::AlphaMode) -> world::AlphaMode {
@@ -438,6 +974,96 @@ pub fn convert_alpha_mode(mode: gltf::material::AlphaMode) -> world::AlphaMode {
     }
 }
 
+pub fn map_material(material: gltf::Material<'_>) -> world::Material {
+    let pbr = material.pbr_metallic_roughness();
+    world::Material {
+        base_color_factor: nalgebra_glm::Vec4::from(pbr.base_color_factor()),
+        alpha_mode: convert_alpha_mode(material.alpha_mode()),
+        alpha_cutoff: material.alpha_cutoff(),
+        base_color_texture: pbr.base_color_texture().map(map_texture_info),
+        emissive_factor: material.emissive_factor().into(),
+        emissive_texture: material.emissive_texture().map(map_texture_info),
+        emissive_strength: material.emissive_strength(),
+        unlit: material.unlit(),
+        ior: material.ior(),
+        specular: material.specular().map(|specular| world::Specular {
+            factor: specular.specular_factor(),
+            texture: specular.specular_texture().map(map_texture_info),
+            color_factor: specular.specular_color_factor().into(),
+            color_texture: specular.specular_color_texture().map(map_texture_info),
+        }),
+        transmission: material
+            .transmission()
+            .map(|transmission| world::Transmission {
+                factor: transmission.transmission_factor(),
+                texture: transmission.transmission_texture().map(map_texture_info),
+            }),
+        clearcoat: material
+            .extension_value("KHR_materials_clearcoat")
+            .map(map_clearcoat),
+    }
+}
+
+pub fn map_texture_info(info: gltf::texture::Info<'_>) -> world::TextureInfo {
+    world::TextureInfo {
+        texture_index: info.texture().index(),
+        tex_coord: info.tex_coord(),
+        transform: info.texture_transform().map(map_texture_transform),
+    }
+}
+
+pub fn map_texture_transform(
+    transform: gltf::texture::TextureTransform<'_>,
+) -> world::TextureTransform {
+    world::TextureTransform {
+        offset: transform.offset().into(),
+        rotation: transform.rotation(),
+        scale: transform.scale().into(),
+        tex_coord: transform.tex_coord(),
+    }
+}
+
+/// The gltf crate has no typed support for KHR_materials_clearcoat,
+/// so it is read from the raw extension object
+fn map_clearcoat(value: &serde_json::Value) -> world::Clearcoat {
+    let factor = |name: &str| value[name].as_f64().unwrap_or_default() as f32;
+    let texture_info = |name: &str| {
+        let info = &value[name];
+        let texture_index = info["index"].as_u64()? as usize;
+        let transform = &info["extensions"]["KHR_texture_transform"];
+        let pair = |name: &str, default: f32| {
+            let component = |index: usize| {
+                transform[name][index]
+                    .as_f64()
+                    .map_or(default, |value| value as f32)
+            };
+            nalgebra_glm::vec2(component(0), component(1))
+        };
+        Some(world::TextureInfo {
+            texture_index,
+            tex_coord: info["texCoord"].as_u64().unwrap_or_default() as u32,
+            transform: transform.is_object().then(|| world::TextureTransform {
+                offset: pair("offset", 0.0),
+                rotation: transform["rotation"].as_f64().unwrap_or_default() as f32,
+                scale: pair("scale", 1.0),
+                tex_coord: transform["texCoord"]
+                    .as_u64()
+                    .map(|tex_coord| tex_coord as u32),
+            }),
+        })
+    };
+    world::Clearcoat {
+        factor: factor("clearcoatFactor"),
+        texture: texture_info("clearcoatTexture"),
+        roughness_factor: factor("clearcoatRoughnessFactor"),
+        roughness_texture: texture_info("clearcoatRoughnessTexture"),
+        normal_texture: texture_info("clearcoatNormalTexture"),
+        normal_scale: value["clearcoatNormalTexture"]["scale"]
+            .as_f64()
+            .map_or(1.0, |scale| scale as f32),
+    }
+}
+
 pub fn map_sampler(sampler: gltf::texture::Sampler<'_>) -> world::Sampler {
     let min_filter = sampler
         .min_filter()
@@ -481,45 +1107,43 @@ pub fn map_sampler(sampler: gltf::texture::Sampler<'_>) -> world::Sampler {
     }
 }
 
-pub fn map_image(data: gltf::image::Data) -> world::Image {
-    let img = match data.format {
-        gltf::image::Format::R8 => image::DynamicImage::ImageLuma8(
-            image::ImageBuffer::from_raw(data.width, data.height, data.pixels.to_vec()).unwrap(),
+fn map_dynamic_image(
+    image_index: usize,
+    img: image::DynamicImage,
+) -> Result<world::Image, ImportError> {
+    let (width, height) = (img.width(), img.height());
+    let (format, img) = match img.color() {
+        image::ColorType::L8 => (world::ImageFormat::R8, img),
+        image::ColorType::La8 => (world::ImageFormat::R8G8, img),
+        image::ColorType::Rgb8 | image::ColorType::Rgba8 => (
+            world::ImageFormat::R8G8B8A8,
+            image::DynamicImage::ImageRgba8(img.into_rgba8()),
         ),
-        gltf::image::Format::R8G8 => image::DynamicImage::ImageLumaA8(
-            image::ImageBuffer::from_raw(data.width, data.height, data.pixels.to_vec()).unwrap(),
+        image::ColorType::L16 => (world::ImageFormat::R16, img),
+        image::ColorType::La16 => (world::ImageFormat::R16G16, img),
+        image::ColorType::Rgb16 | image::ColorType::Rgba16 => (
+            world::ImageFormat::R16G16B16A16,
+            image::DynamicImage::ImageRgba16(img.into_rgba16()),
         ),
-        gltf::image::Format::R8G8B8 => image::DynamicImage::ImageRgb8(
-            image::ImageBuffer::from_raw(data.width, data.height, data.pixels.to_vec()).unwrap(),
+        image::ColorType::Rgb32F | image::ColorType::Rgba32F => (
+            world::ImageFormat::R32G32B32A32F,
+            image::DynamicImage::ImageRgba32F(img.into_rgba32f()),
         ),
-        gltf::image::Format::R8G8B8A8 => image::DynamicImage::ImageRgba8(
-            image::ImageBuffer::from_raw(data.width, data.height, data.pixels.to_vec()).unwrap(),
-        ),
-        _ => panic!("Unsupported image format!"),
+        color => {
+            return Err(ImportError::UnsupportedImageFormat {
+                image_index,
+                format: format!("{color:?}"),
+            })
+        }
     };
-    let rgba_img = img.to_rgba8();
-    let pixels = rgba_img.into_raw();
-    world::Image {
-        pixels,
-        format: world::ImageFormat::R8G8B8A8,
-        width: data.width,
-        height: data.height,
-    }
-}
-
-pub fn map_image_format(format: gltf::image::Format) -> world::ImageFormat {
-    match format {
-        gltf::image::Format::R8 => world::ImageFormat::R8,
-        gltf::image::Format::R8G8 => world::ImageFormat::R8G8,
-        gltf::image::Format::R8G8B8 => world::ImageFormat::R8G8B8,
-        gltf::image::Format::R8G8B8A8 => world::ImageFormat::R8G8B8A8,
-        gltf::image::Format::R16 => world::ImageFormat::R16,
-        gltf::image::Format::R16G16 => world::ImageFormat::R16G16,
-        gltf::image::Format::R16G16B16 => world::ImageFormat::R16G16B16,
-        gltf::image::Format::R16G16B16A16 => world::ImageFormat::R16G16B16A16,
-        gltf::image::Format::R32G32B32FLOAT => world::ImageFormat::R32G32B32,
-        gltf::image::Format::R32G32B32A32FLOAT => world::ImageFormat::R32G32B32A32,
-    }
+    Ok(world::Image {
+        pixels: img.into_bytes(),
+        format,
+        width,
+        height,
+        mip_levels: Vec::new(),
+        compressed: None,
+    })
 }
 
 pub fn map_camera(camera: gltf::Camera<'_>) -> world::Camera {
@@ -543,6 +1167,7 @@ pub fn map_camera(camera: gltf::Camera<'_>) -> world::Camera {
             }
         },
         orientation: world::Orientation::default(),
+        synthetic: false,
     }
 }
 
@@ -569,6 +1194,58 @@ pub fn map_light_kind(kind: gltf::khr_lights_punctual::Kind) -> world::LightKind
     }
 }
 
+pub fn map_interpolation(interpolation: gltf::animation::Interpolation) -> world::Interpolation {
+    match interpolation {
+        gltf::animation::Interpolation::Linear => world::Interpolation::Linear,
+        gltf::animation::Interpolation::Step => world::Interpolation::Step,
+        gltf::animation::Interpolation::CubicSpline => world::Interpolation::CubicSpline,
+    }
+}
+
+/// Separates cubic spline outputs, which hold an in tangent, a value and an out tangent
+/// for every keyframe, into the values and their tangents
+fn split_cubic_spline(
+    transformations: world::TransformationSet,
+    number_of_keyframes: usize,
+) -> Option<(world::TransformationSet, world::ChannelTangents)> {
+    fn split<T: Copy>(outputs: &[T], number_of_keyframes: usize) -> Option<[Vec<T>; 3]> {
+        let stride = 3 * number_of_keyframes;
+        if stride == 0 || outputs.is_empty() || outputs.len() % stride != 0 {
+            return None;
+        }
+        let width = outputs.len() / stride;
+        let mut parts = [Vec::new(), Vec::new(), Vec::new()];
+        outputs.chunks_exact(3 * width).for_each(|keyframe| {
+            keyframe
+                .chunks_exact(width)
+                .zip(parts.iter_mut())
+                .for_each(|(part, values)| values.extend_from_slice(part));
+        });
+        Some(parts)
+    }
+    let [in_tangents, values, out_tangents] = match transformations {
+        world::TransformationSet::Translations(outputs) => {
+            split(&outputs, number_of_keyframes)?.map(world::TransformationSet::Translations)
+        }
+        world::TransformationSet::Rotations(outputs) => {
+            split(&outputs, number_of_keyframes)?.map(world::TransformationSet::Rotations)
+        }
+        world::TransformationSet::Scales(outputs) => {
+            split(&outputs, number_of_keyframes)?.map(world::TransformationSet::Scales)
+        }
+        world::TransformationSet::MorphTargetWeights(outputs) => {
+            split(&outputs, number_of_keyframes)?.map(world::TransformationSet::MorphTargetWeights)
+        }
+    };
+    Some((
+        values,
+        world::ChannelTangents {
+            in_tangents,
+            out_tangents,
+        },
+    ))
+}
+
 pub fn map_mesh_mode(mode: gltf::mesh::Mode) -> world::PrimitiveTopology {
     match mode {
         gltf::mesh::Mode::Points => world::PrimitiveTopology::Points,
@@ -583,9 +1260,42 @@ pub fn map_mesh_mode(mode: gltf::mesh::Mode) -> world::PrimitiveTopology {
 
 #[cfg(test)]
 mod tests {
+    use crate::gltf::{ImportError, ImportOptions};
+
+    /// Packs a JSON chunk and a binary chunk into a .glb container
+    fn glb(json: &str, bin: &[u8]) -> Vec<u8> {
+        let pad = |mut chunk: Vec<u8>, padding: u8| {
+            while chunk.len() % 4 != 0 {
+                chunk.push(padding);
+            }
+            chunk
+        };
+        let json = pad(json.as_bytes().to_vec(), b' ');
+        let bin = pad(bin.to_vec(), 0);
+        let length = 12 + 8 + json.len() + 8 + bin.len();
+        let mut bytes = Vec::with_capacity(length);
+        bytes.extend_from_slice(b"glTF");
+        bytes.extend_from_slice(&2u32.to_le_bytes());
+        bytes.extend_from_slice(&(length as u32).to_le_bytes());
+        bytes.extend_from_slice(&(json.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(b"JSON");
+        bytes.extend_from_slice(&json);
+        bytes.extend_from_slice(&(bin.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(b"BIN\0");
+        bytes.extend_from_slice(&bin);
+        bytes
+    }
+
+    fn floats(values: &[f32]) -> Vec<u8> {
+        values
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect()
+    }
+
     #[test]
     fn import() {
-        let world = crate::gltf::import_gltf("../../assets/DamagedHelmet.glb");
+        let world = crate::gltf::import_gltf("../../assets/DamagedHelmet.glb").unwrap();
         println!("{} textures", world.textures.len());
         println!("{} images", world.images.len());
         println!("{} samplers", world.samplers.len());
@@ -601,5 +1311,964 @@ mod tests {
         println!("{} animations", world.animations.len());
         println!("{} cameras", world.cameras.len());
         println!("{} lights", world.lights.len());
+        let report = world.validate();
+        assert!(report.is_valid(), "{report}");
+    }
+
+    #[test]
+    fn imported_vertices_have_tangent_frames() {
+        let world = crate::gltf::import_gltf("../../assets/DamagedHelmet.glb").unwrap();
+        world.vertices.iter().for_each(|vertex| {
+            assert!((vertex.normal.norm() - 1.0).abs() < 1e-3);
+            assert!((vertex.tangent.xyz().norm() - 1.0).abs() < 1e-3);
+            assert!(vertex.tangent.xyz().dot(&vertex.normal).abs() < 1e-3);
+            assert_eq!(vertex.tangent.w.abs(), 1.0);
+        });
+    }
+
+    #[test]
+    fn binary_world_round_trip() {
+        let world = crate::gltf::import_gltf("../../assets/DamagedHelmet.glb").unwrap();
+        for compression in [world::Compression::None, world::Compression::Deflate] {
+            let bytes = world.to_bytes(compression).unwrap();
+            let loaded = world::World::from_bytes(&bytes).unwrap();
+            assert_eq!(loaded.to_bytes(compression).unwrap(), bytes);
+        }
+    }
+
+    #[test]
+    fn json_world_round_trip() {
+        let mut world = crate::gltf::import_gltf("../../assets/DamagedHelmet.glb").unwrap();
+        // Pixels are kept out of this test, because tens of megabytes of JSON make it take far too long
+        world
+            .images
+            .iter_mut()
+            .for_each(|image| image.pixels.clear());
+        let json = world.to_json().unwrap();
+        let loaded = world::World::from_json(&json).unwrap();
+        assert_eq!(loaded.to_json().unwrap(), json);
+        assert_eq!(
+            loaded.to_bytes(world::Compression::None).unwrap(),
+            world.to_bytes(world::Compression::None).unwrap()
+        );
+    }
+
+    #[test]
+    fn newer_world_files_are_rejected() {
+        let mut bytes = world::World::default()
+            .to_bytes(world::Compression::None)
+            .unwrap();
+        bytes[4..8].copy_from_slice(&(world::WORLD_FILE_VERSION + 1).to_le_bytes());
+        assert!(matches!(
+            world::World::from_bytes(&bytes),
+            Err(world::WorldFileError::UnsupportedVersion(_))
+        ));
+    }
+
+    #[test]
+    fn instantiate_helmet_as_prefab() {
+        let helmet = crate::gltf::import_gltf("../../assets/DamagedHelmet.glb").unwrap();
+        let mut world = helmet.clone();
+        let parent = petgraph::graph::NodeIndex::new(0);
+
+        let roots = world.instantiate_with_options(
+            &helmet,
+            0,
+            parent,
+            &world::InstantiateOptions {
+                deduplicate: true,
+                ..Default::default()
+            },
+        );
+        assert_eq!(roots.len(), 1);
+        assert_eq!(world.images.len(), helmet.images.len());
+        assert_eq!(world.materials.len(), helmet.materials.len());
+        assert_eq!(world.cameras.len(), helmet.cameras.len());
+        assert_eq!(world.vertices.len(), helmet.vertices.len() * 2);
+        assert_eq!(
+            world.scenes[0]
+                .graph
+                .neighbors_directed(roots[0], petgraph::Direction::Incoming)
+                .next(),
+            Some(parent)
+        );
+
+        let roots = world.instantiate(&helmet, 0, parent);
+        assert_eq!(roots.len(), 1);
+        // Only the base color and emissive images are referenced by the material
+        assert_eq!(world.images.len(), helmet.images.len() + 2);
+        assert_eq!(world.indices.len(), helmet.indices.len() * 3);
+
+        // Images that differ only in their compressed copy are not shared
+        let mut compressed = helmet.clone();
+        compressed.images.iter_mut().for_each(|image| {
+            image.compressed = Some(Box::new(world::Image {
+                pixels: vec![0; 16],
+                format: world::ImageFormat::BC7,
+                width: 4,
+                height: 4,
+                mip_levels: Vec::new(),
+                compressed: None,
+            }))
+        });
+        let number_of_images = world.images.len();
+        world.instantiate_with_options(
+            &compressed,
+            0,
+            parent,
+            &world::InstantiateOptions {
+                deduplicate: true,
+                ..Default::default()
+            },
+        );
+        assert_eq!(world.images.len(), number_of_images + 2);
+    }
+
+    #[test]
+    fn instances_keep_authored_default_cameras() {
+        let mut helmet = crate::gltf::import_gltf("../../assets/DamagedHelmet.glb").unwrap();
+        let mut world = helmet.clone();
+        let parent = petgraph::graph::NodeIndex::new(0);
+
+        world.instantiate(&helmet, 0, parent);
+        assert_eq!(world.cameras.len(), helmet.cameras.len());
+
+        helmet
+            .cameras
+            .iter_mut()
+            .for_each(|camera| camera.synthetic = false);
+        world.instantiate(&helmet, 0, parent);
+        assert_eq!(world.cameras.len(), helmet.cameras.len() + 1);
+    }
+
+    #[test]
+    fn removed_nodes_keep_the_scene_camera_valid() {
+        let mut world = crate::gltf::import_gltf("../../assets/DamagedHelmet.glb").unwrap();
+        let mut history = world::CommandHistory::default();
+        let camera_matrices = |world: &world::World| {
+            world::create_camera_matrices(world, &world.scenes[0], 1.0).map(|matrices| matrices.2)
+        };
+        let view = camera_matrices(&world);
+        let graph = &world.scenes[0].graph;
+        let node_count = graph.node_count();
+        let camera_node_index = graph[world.scenes[0].default_camera_graph_node_index.unwrap()];
+        let mesh_node_index = graph
+            .node_indices()
+            .map(|graph_node_index| graph[graph_node_index])
+            .find(|node_index| world.nodes[*node_index].mesh_index.is_some())
+            .unwrap();
+
+        // Removing another node leaves the camera's graph node in place
+        history.execute(
+            &mut world,
+            world::WorldCommand::RemoveNode {
+                scene_index: 0,
+                node_index: mesh_node_index,
+            },
+        );
+        assert_eq!(camera_matrices(&world), view);
+
+        // Restoring the camera node makes it the scene camera again
+        history.execute(
+            &mut world,
+            world::WorldCommand::RemoveNode {
+                scene_index: 0,
+                node_index: camera_node_index,
+            },
+        );
+        assert_eq!(world.scenes[0].default_camera_graph_node_index, None);
+        assert_eq!(camera_matrices(&world), None);
+        history.undo(&mut world);
+        assert_eq!(camera_matrices(&world), view);
+        assert_eq!(
+            world.scenes[0].graph[world.scenes[0].default_camera_graph_node_index.unwrap()],
+            camera_node_index
+        );
+
+        history.undo(&mut world);
+        assert_eq!(camera_matrices(&world), view);
+        assert_eq!(world.scenes[0].graph.node_count(), node_count);
+    }
+
+    #[test]
+    fn missing_files_are_io_errors() {
+        assert!(matches!(
+            crate::gltf::import_gltf("../../assets/Missing.glb"),
+            Err(ImportError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn malformed_documents_are_parse_errors() {
+        assert!(matches!(
+            crate::gltf::import_gltf_slice(b"{ not json"),
+            Err(ImportError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn unsupported_required_extensions_are_skipped() {
+        let json = r#"{
+            "asset": { "version": "2.0" },
+            "extensionsUsed": ["KHR_draco_mesh_compression"],
+            "extensionsRequired": ["KHR_draco_mesh_compression"],
+            "scenes": [{ "nodes": [] }]
+        }"#;
+        let world = crate::gltf::import_gltf_slice(json.as_bytes()).unwrap();
+        assert_eq!(world.scenes.len(), 1);
+    }
+
+    #[test]
+    fn unresolvable_buffers_are_missing() {
+        let json = r#"{
+            "asset": { "version": "2.0" },
+            "buffers": [{ "byteLength": 4 }, { "byteLength": 4, "uri": "missing.bin" }]
+        }"#;
+        match crate::gltf::import_gltf_slice(&glb(json, &[0; 4])) {
+            Err(ImportError::MissingBuffer { buffer_index, .. }) => assert_eq!(buffer_index, 1),
+            result => panic!("unexpected result: {result:?}"),
+        }
+    }
+
+    #[test]
+    fn mismatched_attribute_counts_are_invalid_accessors() {
+        let json = r#"{
+            "asset": { "version": "2.0" },
+            "buffers": [{ "byteLength": 60 }],
+            "bufferViews": [
+                { "buffer": 0, "byteOffset": 0, "byteLength": 36 },
+                { "buffer": 0, "byteOffset": 36, "byteLength": 24 }
+            ],
+            "accessors": [
+                { "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+                  "min": [0, 0, 0], "max": [1, 1, 0] },
+                { "bufferView": 1, "componentType": 5126, "count": 2, "type": "VEC3" }
+            ],
+            "meshes": [{ "primitives": [{ "attributes": { "POSITION": 0, "NORMAL": 1 } }] }],
+            "nodes": [{ "mesh": 0 }],
+            "scenes": [{ "nodes": [0] }]
+        }"#;
+        let bin = floats(&[
+            0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, //
+            0.0, 0.0, 1.0, 0.0, 0.0, 1.0,
+        ]);
+        match crate::gltf::import_gltf_slice(&glb(json, &bin)) {
+            Err(ImportError::InvalidAccessor { accessor_index, .. }) => {
+                assert_eq!(accessor_index, 1)
+            }
+            result => panic!("unexpected result: {result:?}"),
+        }
+    }
+
+    #[test]
+    fn animation_outputs_without_a_value_per_input_are_invalid_accessors() {
+        let json = r#"{
+            "asset": { "version": "2.0" },
+            "buffers": [{ "byteLength": 36 }],
+            "bufferViews": [
+                { "buffer": 0, "byteOffset": 0, "byteLength": 12 },
+                { "buffer": 0, "byteOffset": 12, "byteLength": 24 }
+            ],
+            "accessors": [
+                { "bufferView": 0, "componentType": 5126, "count": 3, "type": "SCALAR",
+                  "min": [0], "max": [2] },
+                { "bufferView": 1, "componentType": 5126, "count": 2, "type": "VEC3" }
+            ],
+            "animations": [{
+                "channels": [{ "sampler": 0, "target": { "node": 0, "path": "translation" } }],
+                "samplers": [{ "input": 0, "output": 1, "interpolation": "LINEAR" }]
+            }],
+            "nodes": [{}],
+            "scenes": [{ "nodes": [0] }]
+        }"#;
+        let bin = floats(&[0.0, 1.0, 2.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0]);
+        match crate::gltf::import_gltf_slice(&glb(json, &bin)) {
+            Err(ImportError::InvalidAccessor { accessor_index, .. }) => {
+                assert_eq!(accessor_index, 1)
+            }
+            result => panic!("unexpected result: {result:?}"),
+        }
+    }
+
+    #[test]
+    fn triangle_strips_get_generated_normals_and_tangents() {
+        let json = r#"{
+            "asset": { "version": "2.0" },
+            "buffers": [{ "byteLength": 80 }],
+            "bufferViews": [
+                { "buffer": 0, "byteOffset": 0, "byteLength": 48 },
+                { "buffer": 0, "byteOffset": 48, "byteLength": 32 }
+            ],
+            "accessors": [
+                { "bufferView": 0, "componentType": 5126, "count": 4, "type": "VEC3",
+                  "min": [0, 0, 0], "max": [1, 1, 0] },
+                { "bufferView": 1, "componentType": 5126, "count": 4, "type": "VEC2" }
+            ],
+            "meshes": [{
+                "primitives": [{ "attributes": { "POSITION": 0, "TEXCOORD_0": 1 }, "mode": 5 }]
+            }],
+            "nodes": [{ "mesh": 0 }],
+            "scenes": [{ "nodes": [0] }]
+        }"#;
+        let bin = [
+            floats(&[0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 1.0, 1.0, 0.0]),
+            floats(&[0.0, 1.0, 1.0, 1.0, 0.0, 0.0, 1.0, 0.0]),
+        ]
+        .concat();
+        let world = crate::gltf::import_gltf_slice(&glb(json, &bin)).unwrap();
+        let primitive = &world.meshes[0].primitives[0];
+        assert_eq!(primitive.topology, world::PrimitiveTopology::Triangles);
+        assert_eq!(primitive.number_of_indices, 6);
+        world.vertices[primitive.vertex_offset..][..primitive.number_of_vertices]
+            .iter()
+            .for_each(|vertex| {
+                assert!((vertex.normal - nalgebra_glm::Vec3::z()).norm() < 1e-5);
+                assert!((vertex.tangent.xyz() - nalgebra_glm::Vec3::x()).norm() < 1e-5);
+            });
+    }
+
+    #[test]
+    fn unsupported_image_encodings_are_reported() {
+        let json = r#"{
+            "asset": { "version": "2.0" },
+            "buffers": [{ "byteLength": 4 }],
+            "bufferViews": [{ "buffer": 0, "byteLength": 4 }],
+            "images": [{ "bufferView": 0, "mimeType": "image/ktx2" }]
+        }"#;
+        match crate::gltf::import_gltf_slice(&glb(json, &[0; 4])) {
+            Err(ImportError::UnsupportedImageFormat { image_index, .. }) => {
+                assert_eq!(image_index, 0)
+            }
+            result => panic!("unexpected result: {result:?}"),
+        }
+    }
+
+    const EXTERNAL_TRIANGLE: &str = r#"{
+        "asset": { "version": "2.0" },
+        "buffers": [{ "byteLength": 36, "uri": "triangle%20positions.bin" }],
+        "bufferViews": [{ "buffer": 0, "byteLength": 36 }],
+        "accessors": [
+            { "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+              "min": [0, 0, 0], "max": [1, 1, 0] }
+        ],
+        "meshes": [{ "primitives": [{ "attributes": { "POSITION": 0 } }] }],
+        "nodes": [{ "mesh": 0 }],
+        "scenes": [{ "nodes": [0] }]
+    }"#;
+
+    fn triangle_positions() -> Vec<u8> {
+        floats(&[0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0])
+    }
+
+    #[test]
+    fn external_buffers_are_loaded_through_the_resolver() {
+        let uris = crate::gltf::external_uris(EXTERNAL_TRIANGLE.as_bytes()).unwrap();
+        assert_eq!(uris, ["triangle%20positions.bin"]);
+
+        let mut fetched = uris
+            .into_iter()
+            .map(|uri| (uri, triangle_positions()))
+            .collect::<std::collections::HashMap<_, _>>();
+        let world = crate::gltf::import_gltf_with_resolver(
+            EXTERNAL_TRIANGLE.as_bytes(),
+            &mut fetched,
+            &ImportOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(world.vertices.len(), 3);
+        assert_eq!(
+            world.vertices[1].position,
+            nalgebra_glm::vec3(1.0, 0.0, 0.0)
+        );
+
+        assert!(matches!(
+            crate::gltf::import_gltf_slice(EXTERNAL_TRIANGLE.as_bytes()),
+            Err(ImportError::MissingBuffer {
+                buffer_index: 0,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn primitives_without_a_material_use_the_default_material() {
+        let mut fetched = std::collections::HashMap::from([(
+            "triangle%20positions.bin".to_string(),
+            triangle_positions(),
+        )]);
+        let world = crate::gltf::import_gltf_with_resolver(
+            EXTERNAL_TRIANGLE.as_bytes(),
+            &mut fetched,
+            &ImportOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(world.meshes[0].primitives[0].material_index, Some(0));
+        assert_eq!(world.materials, [world::Material::default()]);
+        assert_eq!(world.materials[0].textures().count(), 0);
+    }
+
+    #[test]
+    fn file_resolver_decodes_relative_uris() {
+        let directory = std::env::temp_dir().join(format!("gltf-resolver-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(
+            directory.join("triangle positions.bin"),
+            triangle_positions(),
+        )
+        .unwrap();
+        std::fs::write(directory.join("triangle.gltf"), EXTERNAL_TRIANGLE).unwrap();
+        let world = crate::gltf::import_gltf(directory.join("triangle.gltf"));
+        std::fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(world.unwrap().vertices.len(), 3);
+    }
+
+    #[test]
+    fn data_uris_do_not_reach_the_resolver() {
+        let json = EXTERNAL_TRIANGLE.replace(
+            "triangle%20positions.bin",
+            &format!(
+                "data:application/octet-stream;base64,{}",
+                base64::encode(triangle_positions())
+            ),
+        );
+        assert!(crate::gltf::external_uris(json.as_bytes())
+            .unwrap()
+            .is_empty());
+        let mut resolver =
+            |uri: &str| -> std::io::Result<Vec<u8>> { panic!("{uri} should not be resolved") };
+        let world = crate::gltf::import_gltf_with_resolver(
+            json.as_bytes(),
+            &mut resolver,
+            &ImportOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(world.vertices.len(), 3);
+    }
+
+    fn node_name(world: &world::World, node_index: usize) -> &str {
+        &world.metadata[world.nodes[node_index].metadata_index].name
+    }
+
+    fn scene_of(world: &world::World, node_index: usize) -> usize {
+        world
+            .scenes
+            .iter()
+            .position(|scene| scene.graph.node_weights().any(|index| *index == node_index))
+            .unwrap()
+    }
+
+    #[test]
+    fn skins_target_the_joints_of_their_scene() {
+        let world = crate::gltf::import_gltf("../../assets/SkinnedScenes.gltf").unwrap();
+        let report = world.validate();
+        assert!(report.is_valid(), "{report}");
+
+        let skinned_node_indices = (0..world.nodes.len())
+            .filter(|node_index| node_name(&world, *node_index) == "Skinned Mesh")
+            .collect::<Vec<_>>();
+        assert_eq!(skinned_node_indices.len(), 2);
+        assert_eq!(world.skins.len(), 2);
+        skinned_node_indices.into_iter().for_each(|node_index| {
+            let skin = &world.skins[world.nodes[node_index].skin_index.unwrap()];
+            let joint_names = skin
+                .joints
+                .iter()
+                .map(|joint| node_name(&world, joint.target_node_index))
+                .collect::<Vec<_>>();
+            assert_eq!(joint_names, ["Root Joint", "Tip Joint"]);
+            skin.joints.iter().for_each(|joint| {
+                assert_eq!(
+                    scene_of(&world, joint.target_node_index),
+                    scene_of(&world, node_index)
+                )
+            });
+            assert_eq!(skin.joints[1].inverse_bind_matrix[(1, 3)], -1.0);
+        });
+    }
+
+    const CAMERA_SCENES: &str = r#"{
+        "asset": { "version": "2.0" },
+        "scene": 1,
+        "cameras": [{ "type": "perspective", "perspective": { "yfov": 0.5, "znear": 0.1 } }],
+        "nodes": [{ "name": "Prop" }, { "name": "Authored Camera", "camera": 0 }],
+        "scenes": [{ "nodes": [0] }, { "nodes": [0, 1] }]
+    }"#;
+
+    fn default_camera(world: &world::World, scene_index: usize) -> &world::Node {
+        let scene = &world.scenes[scene_index];
+        &world.nodes[scene.graph[scene.default_camera_graph_node_index.unwrap()]]
+    }
+
+    #[test]
+    fn every_scene_has_a_default_camera() {
+        let world = crate::gltf::import_gltf_slice(CAMERA_SCENES.as_bytes()).unwrap();
+        let report = world.validate();
+        assert!(report.is_valid(), "{report}");
+        assert_eq!(world.default_scene_index, 1);
+        assert_eq!(world.cameras.len(), 2);
+
+        let authored = default_camera(&world, 1);
+        assert_eq!(authored.camera_index, Some(0));
+        assert_eq!(
+            world.metadata[authored.metadata_index].name,
+            "Authored Camera"
+        );
+        let synthetic = default_camera(&world, 0);
+        assert_eq!(synthetic.camera_index, Some(1));
+        assert_eq!(world.metadata[synthetic.metadata_index].name, "Main Camera");
+        assert!(!world.cameras[0].synthetic);
+        assert!(world.cameras[1].synthetic);
+    }
+
+    #[test]
+    fn exports_keep_authored_cameras_and_leave_out_synthetic_ones() {
+        let world = crate::gltf::import_gltf_slice(CAMERA_SCENES.as_bytes()).unwrap();
+        let round_trip = |scene_index| {
+            let options = crate::ExportOptions { scene_index };
+            let exported = crate::export_glb(&world, &options).unwrap();
+            crate::gltf::import_gltf_slice(&exported).unwrap()
+        };
+
+        let authored = round_trip(1);
+        assert_eq!(authored.cameras.len(), 1);
+        assert!(!authored.cameras[0].synthetic);
+        let camera = default_camera(&authored, 0);
+        assert_eq!(
+            authored.metadata[camera.metadata_index].name,
+            "Authored Camera"
+        );
+
+        // Only the camera added by this import remains, rather than one per round trip
+        let synthetic = round_trip(0);
+        assert_eq!(synthetic.cameras.len(), 1);
+        assert!(synthetic.cameras[0].synthetic);
+    }
+
+    #[test]
+    fn the_synthetic_camera_can_be_left_out() {
+        let world = crate::gltf::import_gltf_with_resolver(
+            CAMERA_SCENES.as_bytes(),
+            &mut crate::gltf::EmbeddedResolver,
+            &ImportOptions {
+                add_default_camera: false,
+            },
+        )
+        .unwrap();
+        assert_eq!(world.cameras.len(), 1);
+        assert_eq!(default_camera(&world, 1).camera_index, Some(0));
+        assert_eq!(world.scenes[0].default_camera_graph_node_index, None);
+        assert!(world::create_camera_matrices(&world, &world.scenes[0], 1.0).is_none());
+        let report = world.validate();
+        assert!(report.is_valid(), "{report}");
+    }
+
+    #[test]
+    fn animations_target_every_instance_of_their_nodes() {
+        let world = crate::gltf::import_gltf("../../assets/SkinnedScenes.gltf").unwrap();
+        let channels = &world.animations[0].channels;
+        assert_eq!(channels.len(), 4);
+        let mut targets = channels
+            .iter()
+            .map(|channel| {
+                (
+                    scene_of(&world, channel.target_node_index),
+                    node_name(&world, channel.target_node_index),
+                )
+            })
+            .collect::<Vec<_>>();
+        targets.sort();
+        assert_eq!(
+            targets,
+            [
+                (0, "Root Joint"),
+                (0, "Tip Joint"),
+                (1, "Root Joint"),
+                (1, "Tip Joint")
+            ]
+        );
+    }
+
+    #[test]
+    fn morph_targets_are_imported_and_animated() {
+        let json = r#"{
+            "asset": { "version": "2.0" },
+            "buffers": [{ "byteLength": 208 }],
+            "bufferViews": [
+                { "buffer": 0, "byteOffset": 0, "byteLength": 36 },
+                { "buffer": 0, "byteOffset": 36, "byteLength": 36 },
+                { "buffer": 0, "byteOffset": 72, "byteLength": 48 },
+                { "buffer": 0, "byteOffset": 120, "byteLength": 36 },
+                { "buffer": 0, "byteOffset": 156, "byteLength": 36 },
+                { "buffer": 0, "byteOffset": 192, "byteLength": 8 },
+                { "buffer": 0, "byteOffset": 200, "byteLength": 8 }
+            ],
+            "accessors": [
+                { "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+                  "min": [0, 0, 0], "max": [1, 1, 0] },
+                { "bufferView": 1, "componentType": 5126, "count": 3, "type": "VEC3" },
+                { "bufferView": 2, "componentType": 5126, "count": 3, "type": "VEC4" },
+                { "bufferView": 3, "componentType": 5126, "count": 3, "type": "VEC3",
+                  "min": [0, 0, 1], "max": [0, 0, 1] },
+                { "bufferView": 4, "componentType": 5126, "count": 3, "type": "VEC3" },
+                { "bufferView": 5, "componentType": 5126, "count": 2, "type": "SCALAR",
+                  "min": [0], "max": [1] },
+                { "bufferView": 6, "componentType": 5126, "count": 2, "type": "SCALAR" }
+            ],
+            "meshes": [{
+                "primitives": [{
+                    "attributes": { "POSITION": 0, "NORMAL": 1, "TANGENT": 2 },
+                    "targets": [{ "POSITION": 3, "TANGENT": 4 }]
+                }],
+                "weights": [0.0]
+            }],
+            "nodes": [{ "mesh": 0 }],
+            "scenes": [{ "nodes": [0] }],
+            "animations": [{
+                "channels": [{ "sampler": 0, "target": { "node": 0, "path": "weights" } }],
+                "samplers": [{ "input": 5, "output": 6 }]
+            }]
+        }"#;
+        let bin = [
+            floats(&[0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0]),
+            floats(&[0.0, 0.0, 1.0].repeat(3)),
+            floats(&[1.0, 0.0, 0.0, 1.0].repeat(3)),
+            floats(&[0.0, 0.0, 1.0].repeat(3)),
+            floats(&[-1.0, 1.0, 0.0].repeat(3)),
+            floats(&[0.0, 1.0]),
+            floats(&[0.0, 1.0]),
+        ]
+        .concat();
+        let mut world = crate::gltf::import_gltf_slice(&glb(json, &bin)).unwrap();
+        let report = world.validate();
+        assert!(report.is_valid(), "{report}");
+
+        let primitive = world.meshes[0].primitives[0].clone();
+        assert_eq!(primitive.number_of_morph_targets, 1);
+        assert_eq!(
+            world.morph_target(&primitive, 0)[2].position,
+            nalgebra_glm::vec3(0.0, 0.0, 1.0)
+        );
+
+        let node_index = world.animations[0].channels[0].target_node_index;
+        assert_eq!(world.nodes[node_index].mesh_index, Some(0));
+        assert_eq!(world.node_morph_weights(node_index), [0.0]);
+        world.update_animation(0, 0.5);
+        assert_eq!(world.node_morph_weights(node_index), [0.5]);
+
+        let vertices = world.blend_morph_targets(&primitive, world.node_morph_weights(node_index));
+        assert_eq!(vertices[1].position, nalgebra_glm::vec3(1.0, 0.0, 0.5));
+        let half = std::f32::consts::FRAC_1_SQRT_2;
+        assert!(
+            nalgebra_glm::distance(
+                &vertices[1].tangent,
+                &nalgebra_glm::vec4(half, half, 0.0, 1.0)
+            ) < 1e-6
+        );
+    }
+
+    #[test]
+    fn instantiated_skins_are_remapped() {
+        let skinned = crate::gltf::import_gltf("../../assets/SkinnedScenes.gltf").unwrap();
+        let mut world = crate::gltf::import_gltf("../../assets/DamagedHelmet.glb").unwrap();
+        let roots = world.instantiate(&skinned, 1, petgraph::graph::NodeIndex::new(0));
+        assert_eq!(roots.len(), 1);
+        let report = world.validate();
+        assert!(report.is_valid(), "{report}");
+        let skinned_node_index = (0..world.nodes.len())
+            .find(|node_index| node_name(&world, *node_index) == "Skinned Mesh")
+            .unwrap();
+        let skin = &world.skins[world.nodes[skinned_node_index].skin_index.unwrap()];
+        assert!(skin
+            .joints
+            .iter()
+            .all(|joint| scene_of(&world, joint.target_node_index) == 0));
+    }
+
+    #[test]
+    fn instances_share_their_copied_animations() {
+        let skinned = crate::gltf::import_gltf("../../assets/SkinnedScenes.gltf").unwrap();
+        let mut world = crate::gltf::import_gltf("../../assets/DamagedHelmet.glb").unwrap();
+        let options = world::InstantiateOptions {
+            deduplicate: true,
+            ..Default::default()
+        };
+        let parent = petgraph::graph::NodeIndex::new(0);
+        world.instantiate_with_options(&skinned, 1, parent, &options);
+        let copied = world
+            .animations
+            .iter()
+            .map(|animation| animation.channels.len())
+            .collect::<Vec<_>>();
+        assert!(!copied.is_empty());
+
+        world.instantiate_with_options(&skinned, 1, parent, &options);
+        assert_eq!(
+            world
+                .animations
+                .iter()
+                .map(|animation| animation.channels.len() / 2)
+                .collect::<Vec<_>>(),
+            copied
+        );
+        let report = world.validate();
+        assert!(report.is_valid(), "{report}");
+    }
+
+    #[test]
+    fn animations_keep_their_interpolation() {
+        let mut world = crate::gltf::import_gltf("../../assets/SkinnedScenes.gltf").unwrap();
+        let wave = &world.animations[1];
+        assert_eq!(wave.name, "Wave");
+        assert!(matches!(
+            wave.channels[0].interpolation,
+            world::Interpolation::Step
+        ));
+        assert!(wave.channels[0].tangents.is_none());
+        let cubic = wave
+            .channels
+            .iter()
+            .find(|channel| matches!(channel.interpolation, world::Interpolation::CubicSpline))
+            .unwrap();
+        assert!(
+            matches!(&cubic.transformations, world::TransformationSet::Scales(scales) if scales.len() == 2)
+        );
+        let root_joint = cubic.target_node_index;
+
+        world.update_animation(1, 0.75);
+        let prop = (0..world.nodes.len())
+            .find(|node_index| node_name(&world, *node_index) == "Prop")
+            .unwrap();
+        let prop_translation = world.transforms[world.nodes[prop].transform_index].translation;
+        assert_eq!(prop_translation, nalgebra_glm::vec3(0.0, 0.0, 0.0));
+
+        world.animations[1].time = 0.0;
+        world.update_animation(1, 0.5);
+        let scale = world.transforms[world.nodes[root_joint].transform_index].scale;
+        assert!(nalgebra_glm::distance(&scale, &nalgebra_glm::vec3(1.875, 1.5, 1.5)) < 1e-5);
+    }
+
+    /// Adds a node drawing a triangle with the material to the first scene,
+    /// since exports only write what the scene's nodes use
+    fn add_triangle_node(world: &mut world::World, material_index: usize) {
+        let vertex_offset = world.vertices.len();
+        world.vertices.extend(
+            [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)].map(|(x, y)| world::Vertex {
+                position: nalgebra_glm::vec3(x, y, 0.0),
+                normal: nalgebra_glm::vec3(0.0, 0.0, 1.0),
+                uv_0: nalgebra_glm::vec2(x, y),
+                ..Default::default()
+            }),
+        );
+        world.meshes.push(world::Mesh {
+            primitives: vec![world::Primitive {
+                vertex_offset,
+                number_of_vertices: 3,
+                material_index: Some(material_index),
+                ..Default::default()
+            }],
+            weights: Vec::new(),
+        });
+        let node_index = world.add_node();
+        world.nodes[node_index].mesh_index = Some(world.meshes.len() - 1);
+        world.scenes[0].graph.add_node(node_index);
+    }
+
+    fn image_glb(encoded: &[u8], mime_type: &str) -> Vec<u8> {
+        let json = format!(
+            r#"{{
+                "asset": {{ "version": "2.0" }},
+                "buffers": [{{ "byteLength": {length} }}],
+                "bufferViews": [{{ "buffer": 0, "byteLength": {length} }}],
+                "images": [{{ "bufferView": 0, "mimeType": "{mime_type}" }}]
+            }}"#,
+            length = encoded.len()
+        );
+        glb(&json, encoded)
+    }
+
+    fn encode_png<P: image::Pixel<Subpixel = u16> + image::PixelWithColorType>(
+        pixels: Vec<u16>,
+    ) -> Vec<u8>
+    where
+        [P::Subpixel]: image::EncodableLayout,
+    {
+        let buffer = image::ImageBuffer::<P, _>::from_raw(2, 1, pixels).unwrap();
+        let mut png = std::io::Cursor::new(Vec::new());
+        buffer
+            .write_to(&mut png, image::ImageOutputFormat::Png)
+            .unwrap();
+        png.into_inner()
+    }
+
+    fn channels(image: &world::Image) -> Vec<u16> {
+        image
+            .pixels
+            .chunks_exact(2)
+            .map(|channel| u16::from_ne_bytes([channel[0], channel[1]]))
+            .collect()
+    }
+
+    #[test]
+    fn sixteen_bit_images_keep_their_precision() {
+        let png = encode_png::<image::Rgb<u16>>(vec![0x1234, 0x5678, 0x9abc, 1, 2, 3]);
+        let world = crate::gltf::import_gltf_slice(&image_glb(&png, "image/png")).unwrap();
+        let image = &world.images[0];
+        assert_eq!(image.format, world::ImageFormat::R16G16B16A16);
+        assert_eq!(
+            channels(image),
+            [0x1234, 0x5678, 0x9abc, u16::MAX, 1, 2, 3, u16::MAX]
+        );
+
+        let png = encode_png::<image::Luma<u16>>(vec![0x0102, 0xfffe]);
+        let mut world = crate::gltf::import_gltf_slice(&image_glb(&png, "image/png")).unwrap();
+        assert_eq!(world.images[0].format, world::ImageFormat::R16);
+        assert_eq!(channels(&world.images[0]), [0x0102, 0xfffe]);
+
+        world.textures.push(world::Texture {
+            image_index: 0,
+            sampler_index: None,
+        });
+        world.materials.push(world::Material {
+            base_color_texture: Some(world::TextureInfo {
+                texture_index: 0,
+                tex_coord: 0,
+                transform: None,
+            }),
+            ..Default::default()
+        });
+        add_triangle_node(&mut world, 0);
+        let exported = crate::export_glb(&world, &crate::ExportOptions::default()).unwrap();
+        let reimported = crate::gltf::import_gltf_slice(&exported).unwrap();
+        assert_eq!(reimported.images[0].format, world::ImageFormat::R16);
+        assert_eq!(reimported.images[0].pixels, world.images[0].pixels);
+    }
+
+    #[test]
+    fn float_images_gain_an_opaque_alpha_channel() {
+        let buffer =
+            image::ImageBuffer::<image::Rgb<f32>, _>::from_raw(1, 1, vec![0.5, 2.0, 16.0]).unwrap();
+        let mut exr = std::io::Cursor::new(Vec::new());
+        buffer
+            .write_to(&mut exr, image::ImageOutputFormat::OpenExr)
+            .unwrap();
+        let world =
+            crate::gltf::import_gltf_slice(&image_glb(exr.get_ref(), "image/x-exr")).unwrap();
+        let image = &world.images[0];
+        assert_eq!(image.format, world::ImageFormat::R32G32B32A32F);
+        let values = image
+            .pixels
+            .chunks_exact(4)
+            .map(|channel| f32::from_ne_bytes(channel.try_into().unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(values, [0.5, 2.0, 16.0, 1.0]);
+    }
+
+    #[test]
+    fn material_extensions_are_imported() {
+        let png = encode_png::<image::Rgb<u16>>(vec![0; 6]);
+        let json = format!(
+            r#"{{
+                "asset": {{ "version": "2.0" }},
+                "extensionsUsed": [
+                    "KHR_texture_transform", "KHR_materials_emissive_strength",
+                    "KHR_materials_unlit", "KHR_materials_ior", "KHR_materials_specular",
+                    "KHR_materials_transmission", "KHR_materials_clearcoat"
+                ],
+                "extensionsRequired": ["KHR_texture_transform"],
+                "buffers": [{{ "byteLength": {length} }}],
+                "bufferViews": [{{ "buffer": 0, "byteLength": {length} }}],
+                "images": [{{ "bufferView": 0, "mimeType": "image/png" }}],
+                "textures": [{{ "source": 0 }}],
+                "materials": [{{
+                    "pbrMetallicRoughness": {{
+                        "baseColorTexture": {{
+                            "index": 0,
+                            "extensions": {{ "KHR_texture_transform": {{
+                                "offset": [0.5, 0.25], "rotation": 1.5, "scale": [2, 4], "texCoord": 1
+                            }} }}
+                        }}
+                    }},
+                    "extensions": {{
+                        "KHR_materials_emissive_strength": {{ "emissiveStrength": 5 }},
+                        "KHR_materials_unlit": {{}},
+                        "KHR_materials_ior": {{ "ior": 1.4 }},
+                        "KHR_materials_specular": {{
+                            "specularFactor": 0.5,
+                            "specularColorTexture": {{ "index": 0, "texCoord": 1 }}
+                        }},
+                        "KHR_materials_transmission": {{ "transmissionFactor": 0.75 }},
+                        "KHR_materials_clearcoat": {{
+                            "clearcoatFactor": 1,
+                            "clearcoatRoughnessFactor": 0.25,
+                            "clearcoatNormalTexture": {{
+                                "index": 0,
+                                "scale": 0.5,
+                                "extensions": {{ "KHR_texture_transform": {{ "scale": [3, 3] }} }}
+                            }}
+                        }}
+                    }}
+                }}]
+            }}"#,
+            length = png.len()
+        );
+        let mut world = crate::gltf::import_gltf_slice(&glb(&json, &png)).unwrap();
+        let material = &world.materials[0];
+        assert_eq!(
+            material.base_color_texture,
+            Some(world::TextureInfo {
+                texture_index: 0,
+                tex_coord: 0,
+                transform: Some(world::TextureTransform {
+                    offset: nalgebra_glm::vec2(0.5, 0.25),
+                    rotation: 1.5,
+                    scale: nalgebra_glm::vec2(2.0, 4.0),
+                    tex_coord: Some(1),
+                }),
+            })
+        );
+        assert_eq!(material.emissive_strength, Some(5.0));
+        assert!(material.unlit);
+        assert_eq!(material.ior, Some(1.4));
+        assert_eq!(
+            material.specular,
+            Some(world::Specular {
+                factor: 0.5,
+                color_texture: Some(world::TextureInfo {
+                    texture_index: 0,
+                    tex_coord: 1,
+                    transform: None,
+                }),
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            material.transmission,
+            Some(world::Transmission {
+                factor: 0.75,
+                texture: None,
+            })
+        );
+        assert_eq!(
+            material.clearcoat,
+            Some(world::Clearcoat {
+                factor: 1.0,
+                roughness_factor: 0.25,
+                normal_texture: Some(world::TextureInfo {
+                    texture_index: 0,
+                    tex_coord: 0,
+                    transform: Some(world::TextureTransform {
+                        scale: nalgebra_glm::vec2(3.0, 3.0),
+                        ..Default::default()
+                    }),
+                }),
+                normal_scale: 0.5,
+                ..Default::default()
+            })
+        );
+
+        add_triangle_node(&mut world, 0);
+        let exported = crate::export_glb(&world, &Default::default()).unwrap();
+        let reimported = crate::gltf::import_gltf_slice(&exported).unwrap();
+        assert_eq!(reimported.materials, world.materials);
     }
 }
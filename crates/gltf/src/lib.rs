@@ -1,3 +1,4 @@
+mod export;
 mod gltf;
 
-pub use self::gltf::*;
+pub use self::{export::*, gltf::*};
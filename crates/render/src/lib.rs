@@ -1,3 +1,3 @@
 mod renderer;
 
-pub use renderer::Renderer;
+pub use renderer::{image_texture_format, Renderer};
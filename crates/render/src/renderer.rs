@@ -29,7 +29,7 @@ impl<'window> Renderer<'window> {
             1,
         );
 
-        let scene = Scene::new(&gpu.device, gpu.surface_format);
+        let scene = Scene::new(&gpu.device, &gpu.queue, gpu.surface_format);
 
         Self {
             gpu,
@@ -45,8 +45,16 @@ impl<'window> Renderer<'window> {
         self.depth_texture_view = self.gpu.create_depth_texture(width, height);
     }
 
+    pub fn create_image_texture(&self, image: &world::Image, srgb: bool) -> Option<wgpu::Texture> {
+        self.gpu.create_image_texture(image, srgb)
+    }
+
+    /// Draws the meshes of the world's default scene through its default camera,
+    /// or through a fixed camera when that scene has no camera, then the gui on top.
+    /// A spinning triangle is drawn instead when there is no world.
     pub fn render_frame(
         &mut self,
+        world: Option<&world::World>,
         screen_descriptor: egui_wgpu::ScreenDescriptor,
         paint_jobs: Vec<egui::ClippedPrimitive>,
         textures_delta: egui::TexturesDelta,
@@ -54,8 +62,7 @@ impl<'window> Renderer<'window> {
     ) {
         let delta_time = delta_time.as_secs_f32();
 
-        self.scene
-            .update(&self.gpu.queue, self.gpu.aspect_ratio(), delta_time);
+        self.scene.update(&self.gpu, world, delta_time);
 
         for (id, image_delta) in &textures_delta.set {
             self.egui_renderer
@@ -195,6 +202,65 @@ impl<'window> Gpu<'window> {
         })
     }
 
+    /// Uploads an image and its mip levels, preferring the image's block compressed copy
+    /// when the device can sample that format, such as BC7 on adapters with BC compression.
+    /// Returns `None` when neither can be sampled.
+    pub fn create_image_texture(&self, image: &world::Image, srgb: bool) -> Option<wgpu::Texture> {
+        image
+            .compressed
+            .as_deref()
+            .and_then(|compressed| self.create_texture_from_levels(compressed, srgb))
+            .or_else(|| self.create_texture_from_levels(image, srgb))
+    }
+
+    fn create_texture_from_levels(
+        &self,
+        image: &world::Image,
+        srgb: bool,
+    ) -> Option<wgpu::Texture> {
+        use wgpu::util::DeviceExt;
+
+        // No texture format has three 8-bit channels, so those gain an opaque alpha channel
+        let with_alpha;
+        let image = match image.format {
+            world::ImageFormat::R8G8B8 | world::ImageFormat::B8G8R8 => {
+                with_alpha = add_alpha_channel(image);
+                &with_alpha
+            }
+            _ => image,
+        };
+        let format = image_texture_format(image.format, srgb, self.device.features())?;
+        // Block compressed textures must be whole blocks at their full size
+        let (block_width, block_height) = format.block_dimensions();
+        if image.width % block_width != 0 || image.height % block_height != 0 {
+            return None;
+        }
+        let data = std::iter::once(&image.pixels)
+            .chain(image.mip_levels.iter().map(|level| &level.pixels))
+            .flatten()
+            .copied()
+            .collect::<Vec<_>>();
+        Some(self.device.create_texture_with_data(
+            &self.queue,
+            &wgpu::TextureDescriptor {
+                label: Some("Image Texture"),
+                size: wgpu::Extent3d {
+                    width: image.width,
+                    height: image.height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1 + image.mip_levels.len() as u32,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            &data,
+        ))
+    }
+
     pub async fn new_async(
         window: impl Into<wgpu::SurfaceTarget<'window>>,
         width: u32,
@@ -222,8 +288,11 @@ impl<'window> Gpu<'window> {
                     &wgpu::DeviceDescriptor {
                         label: Some("WGPU Device"),
 
+                        // Compressed and 16-bit images are uploaded when the adapter supports them
                         #[cfg(not(target_arch = "wasm32"))]
-                        required_features: wgpu::Features::default(),
+                        required_features: adapter.features()
+                            & (wgpu::Features::TEXTURE_COMPRESSION_BC
+                                | wgpu::Features::TEXTURE_FORMAT_16BIT_NORM),
 
                         #[cfg(all(target_arch = "wasm32", feature = "webgpu"))]
                         required_features: wgpu::Features::all_webgpu_mask(),
@@ -282,16 +351,168 @@ impl<'window> Gpu<'window> {
     }
 }
 
+/// Copies a three channel 8-bit image and its mip levels into four channels with opaque alpha
+fn add_alpha_channel(image: &world::Image) -> world::Image {
+    let pad = |pixels: &[u8]| {
+        pixels
+            .chunks_exact(3)
+            .flat_map(|texel| [texel[0], texel[1], texel[2], 255])
+            .collect::<Vec<_>>()
+    };
+    world::Image {
+        pixels: pad(&image.pixels),
+        format: match image.format {
+            world::ImageFormat::B8G8R8 => world::ImageFormat::B8G8R8A8,
+            _ => world::ImageFormat::R8G8B8A8,
+        },
+        width: image.width,
+        height: image.height,
+        mip_levels: image
+            .mip_levels
+            .iter()
+            .map(|level| world::MipLevel {
+                pixels: pad(&level.pixels),
+                width: level.width,
+                height: level.height,
+            })
+            .collect(),
+        compressed: None,
+    }
+}
+
+/// The texture format that an image is sampled as, if the device supports one.
+/// Three channel images have no matching texture format.
+pub fn image_texture_format(
+    format: world::ImageFormat,
+    srgb: bool,
+    features: wgpu::Features,
+) -> Option<wgpu::TextureFormat> {
+    let sixteen_bit_norm = features.contains(wgpu::Features::TEXTURE_FORMAT_16BIT_NORM);
+    let texture_format = match format {
+        world::ImageFormat::R8 => wgpu::TextureFormat::R8Unorm,
+        world::ImageFormat::R8G8 => wgpu::TextureFormat::Rg8Unorm,
+        world::ImageFormat::R8G8B8A8 if srgb => wgpu::TextureFormat::Rgba8UnormSrgb,
+        world::ImageFormat::R8G8B8A8 => wgpu::TextureFormat::Rgba8Unorm,
+        world::ImageFormat::B8G8R8A8 if srgb => wgpu::TextureFormat::Bgra8UnormSrgb,
+        world::ImageFormat::B8G8R8A8 => wgpu::TextureFormat::Bgra8Unorm,
+        world::ImageFormat::R16 if sixteen_bit_norm => wgpu::TextureFormat::R16Unorm,
+        world::ImageFormat::R16G16 if sixteen_bit_norm => wgpu::TextureFormat::Rg16Unorm,
+        world::ImageFormat::R16G16B16A16 if sixteen_bit_norm => wgpu::TextureFormat::Rgba16Unorm,
+        world::ImageFormat::R16F => wgpu::TextureFormat::R16Float,
+        world::ImageFormat::R16G16F => wgpu::TextureFormat::Rg16Float,
+        world::ImageFormat::R16G16B16A16F => wgpu::TextureFormat::Rgba16Float,
+        world::ImageFormat::R32F => wgpu::TextureFormat::R32Float,
+        world::ImageFormat::R32G32F => wgpu::TextureFormat::Rg32Float,
+        world::ImageFormat::R32G32B32A32F => wgpu::TextureFormat::Rgba32Float,
+        world::ImageFormat::BC7 if features.contains(wgpu::Features::TEXTURE_COMPRESSION_BC) => {
+            if srgb {
+                wgpu::TextureFormat::Bc7RgbaUnormSrgb
+            } else {
+                wgpu::TextureFormat::Bc7RgbaUnorm
+            }
+        }
+        _ => return None,
+    };
+    Some(texture_format)
+}
+
 struct Scene {
     pub model: nalgebra_glm::Mat4,
     pub vertex_buffer: wgpu::Buffer,
     pub index_buffer: wgpu::Buffer,
     pub uniform: UniformBinding,
     pub pipeline: wgpu::RenderPipeline,
+    pub mesh_uniform: UniformBinding,
+    pub mesh_pipeline: wgpu::RenderPipeline,
+    pub mesh_geometry: Option<MeshGeometry>,
+    pub texture_bind_group_layout: wgpu::BindGroupLayout,
+    /// Sampled by meshes without a base color texture, or whose texture couldn't be uploaded
+    pub white_texture: wgpu::BindGroup,
+    /// The world's textures, by texture index
+    pub textures: Vec<SceneTexture>,
+}
+
+/// The world's meshes for the current frame, uploaded in world space
+struct MeshGeometry {
+    pub vertex_buffer: wgpu::Buffer,
+    pub index_buffer: wgpu::Buffer,
+    pub draws: Vec<MeshDraw>,
+}
+
+/// A range of indices drawn with one base color texture
+#[derive(Debug, Clone, PartialEq)]
+struct MeshDraw {
+    pub texture_index: Option<usize>,
+    pub indices: std::ops::Range<u32>,
+}
+
+/// The triangles of a scene's meshes gathered for drawing
+#[derive(Default)]
+struct WorldMeshes {
+    pub vertices: Vec<MeshVertex>,
+    pub indices: Vec<u32>,
+    pub draws: Vec<MeshDraw>,
+}
+
+/// A world texture uploaded for sampling, along with what it was uploaded from
+/// so that it is uploaded again when that changes
+struct SceneTexture {
+    pub source: TextureSource,
+    pub bind_group: Option<wgpu::BindGroup>,
+}
+
+#[derive(PartialEq)]
+struct TextureSource {
+    texture: world::Texture,
+    sampler: Option<world::Sampler>,
+    image: Option<ImageLayout>,
+}
+
+impl TextureSource {
+    fn of(world: &world::World, texture: &world::Texture) -> Self {
+        Self {
+            texture: texture.clone(),
+            sampler: texture
+                .sampler_index
+                .and_then(|sampler_index| world.samplers.get(sampler_index))
+                .cloned(),
+            image: world.images.get(texture.image_index).map(ImageLayout::of),
+        }
+    }
+}
+
+/// The shape of an uploaded image and its compressed copy, which changes when mip levels
+/// are generated or a compressed copy is added, so either one uploads the texture again
+#[derive(PartialEq)]
+struct ImageLayout {
+    width: u32,
+    height: u32,
+    format: world::ImageFormat,
+    number_of_mip_levels: usize,
+    compressed: Option<Box<ImageLayout>>,
+}
+
+impl ImageLayout {
+    fn of(image: &world::Image) -> Self {
+        Self {
+            width: image.width,
+            height: image.height,
+            format: image.format,
+            number_of_mip_levels: image.mip_levels.len(),
+            compressed: image
+                .compressed
+                .as_deref()
+                .map(|compressed| Box::new(Self::of(compressed))),
+        }
+    }
 }
 
 impl Scene {
-    pub fn new(device: &wgpu::Device, surface_format: wgpu::TextureFormat) -> Self {
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        surface_format: wgpu::TextureFormat,
+    ) -> Self {
         let vertex_buffer = wgpu::util::DeviceExt::create_buffer_init(
             device,
             &wgpu::util::BufferInitDescriptor {
@@ -310,16 +531,96 @@ impl Scene {
         );
         let uniform = UniformBinding::new(device);
         let pipeline = Self::create_pipeline(device, surface_format, &uniform);
+        let mesh_uniform = UniformBinding::new(device);
+        let texture_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
+                label: Some("texture_bind_group_layout"),
+            });
+        let mesh_pipeline = Self::create_mesh_pipeline(
+            device,
+            surface_format,
+            &mesh_uniform,
+            &texture_bind_group_layout,
+        );
+        let white_texture = {
+            let texture = wgpu::util::DeviceExt::create_texture_with_data(
+                device,
+                queue,
+                &wgpu::TextureDescriptor {
+                    label: Some("White Texture"),
+                    size: wgpu::Extent3d {
+                        width: 1,
+                        height: 1,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                    usage: wgpu::TextureUsages::TEXTURE_BINDING,
+                    view_formats: &[],
+                },
+                wgpu::util::TextureDataOrder::LayerMajor,
+                &[255; 4],
+            );
+            create_texture_bind_group(device, &texture_bind_group_layout, &texture, None)
+        };
         Self {
             model: nalgebra_glm::Mat4::identity(),
             uniform,
             pipeline,
             vertex_buffer,
             index_buffer,
+            mesh_uniform,
+            mesh_pipeline,
+            mesh_geometry: None,
+            texture_bind_group_layout,
+            white_texture,
+            textures: Vec::new(),
         }
     }
 
     pub fn render<'rpass>(&'rpass self, renderpass: &mut wgpu::RenderPass<'rpass>) {
+        if let Some(mesh_geometry) = self.mesh_geometry.as_ref() {
+            if !mesh_geometry.draws.is_empty() {
+                renderpass.set_pipeline(&self.mesh_pipeline);
+                renderpass.set_bind_group(0, &self.mesh_uniform.bind_group, &[]);
+                renderpass.set_vertex_buffer(0, mesh_geometry.vertex_buffer.slice(..));
+                renderpass.set_index_buffer(
+                    mesh_geometry.index_buffer.slice(..),
+                    wgpu::IndexFormat::Uint32,
+                );
+            }
+            for draw in mesh_geometry.draws.iter() {
+                let texture = draw
+                    .texture_index
+                    .and_then(|texture_index| self.textures.get(texture_index))
+                    .and_then(|texture| texture.bind_group.as_ref())
+                    .unwrap_or(&self.white_texture);
+                renderpass.set_bind_group(1, texture, &[]);
+                renderpass.draw_indexed(draw.indices.clone(), 0, 0..1);
+            }
+            return;
+        }
+
         renderpass.set_pipeline(&self.pipeline);
         renderpass.set_bind_group(0, &self.uniform.bind_group, &[]);
 
@@ -329,26 +630,204 @@ impl Scene {
         renderpass.draw_indexed(0..(INDICES.len() as _), 0, 0..1);
     }
 
-    pub fn update(&mut self, queue: &wgpu::Queue, aspect_ratio: f32, delta_time: f32) {
-        let projection =
-            nalgebra_glm::perspective_lh_zo(aspect_ratio, 80_f32.to_radians(), 0.1, 1000.0);
-        let view = nalgebra_glm::look_at_lh(
-            &nalgebra_glm::vec3(0.0, 0.0, 3.0),
-            &nalgebra_glm::vec3(0.0, 0.0, 0.0),
-            &nalgebra_glm::Vec3::y(),
-        );
+    pub fn update(&mut self, gpu: &Gpu, world: Option<&world::World>, delta_time: f32) {
+        let aspect_ratio = gpu.aspect_ratio();
+        let camera_matrices = world.and_then(|world| {
+            let scene = world.scenes.get(world.default_scene_index)?;
+            world::create_camera_matrices(world, scene, aspect_ratio)
+        });
+        let (projection, view) = match camera_matrices {
+            Some((_, projection, view)) => (projection, view),
+            None => (
+                world::Camera::default().projection_matrix(aspect_ratio),
+                nalgebra_glm::look_at(
+                    &nalgebra_glm::vec3(0.0, 0.0, 3.0),
+                    &nalgebra_glm::vec3(0.0, 0.0, 0.0),
+                    &nalgebra_glm::Vec3::y(),
+                ),
+            ),
+        };
         self.model = nalgebra_glm::rotate(
             &self.model,
             30_f32.to_radians() * delta_time,
             &nalgebra_glm::Vec3::y(),
         );
         self.uniform.update_buffer(
-            queue,
+            &gpu.queue,
             0,
             UniformBuffer {
                 mvp: projection * view * self.model,
             },
         );
+
+        let Some(world) = world else {
+            self.mesh_geometry = None;
+            self.textures.clear();
+            return;
+        };
+        let view_projection = projection * view;
+        self.mesh_uniform.update_buffer(
+            &gpu.queue,
+            0,
+            UniformBuffer {
+                mvp: view_projection,
+            },
+        );
+        self.update_textures(gpu, world);
+        let lod_selection = world::LodSelection {
+            viewport_height: gpu.surface_config.height as f32,
+            ..Default::default()
+        };
+        let meshes = world_mesh_geometry(
+            world,
+            world.default_scene_index,
+            &view_projection,
+            &lod_selection,
+        );
+        self.upload_mesh_geometry(&gpu.device, &gpu.queue, meshes);
+    }
+
+    /// Uploads the world's textures that are new or have changed since the last frame
+    fn update_textures(&mut self, gpu: &Gpu, world: &world::World) {
+        self.textures.truncate(world.textures.len());
+        for (texture_index, texture) in world.textures.iter().enumerate() {
+            let source = TextureSource::of(world, texture);
+            if self
+                .textures
+                .get(texture_index)
+                .is_some_and(|uploaded| uploaded.source == source)
+            {
+                continue;
+            }
+            // Only base color textures are sampled, and those are sRGB encoded
+            let bind_group = world
+                .images
+                .get(texture.image_index)
+                .and_then(|image| gpu.create_image_texture(image, true))
+                .filter(|gpu_texture| {
+                    gpu_texture
+                        .format()
+                        .sample_type(None, Some(gpu.device.features()))
+                        == Some(wgpu::TextureSampleType::Float { filterable: true })
+                })
+                .map(|gpu_texture| {
+                    create_texture_bind_group(
+                        &gpu.device,
+                        &self.texture_bind_group_layout,
+                        &gpu_texture,
+                        source.sampler.as_ref(),
+                    )
+                });
+            let uploaded = SceneTexture { source, bind_group };
+            match self.textures.get_mut(texture_index) {
+                Some(texture) => *texture = uploaded,
+                None => self.textures.push(uploaded),
+            }
+        }
+    }
+
+    /// Writes this frame's meshes into the existing buffers, growing them when they are too small
+    fn upload_mesh_geometry(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        meshes: WorldMeshes,
+    ) {
+        let vertex_bytes: &[u8] = bytemuck::cast_slice(&meshes.vertices);
+        let index_bytes: &[u8] = bytemuck::cast_slice(&meshes.indices);
+        let fits = self.mesh_geometry.as_ref().is_some_and(|mesh_geometry| {
+            mesh_geometry.vertex_buffer.size() >= vertex_bytes.len() as wgpu::BufferAddress
+                && mesh_geometry.index_buffer.size() >= index_bytes.len() as wgpu::BufferAddress
+        });
+        match self.mesh_geometry.as_mut() {
+            Some(mesh_geometry) if fits => {
+                queue.write_buffer(&mesh_geometry.vertex_buffer, 0, vertex_bytes);
+                queue.write_buffer(&mesh_geometry.index_buffer, 0, index_bytes);
+                mesh_geometry.draws = meshes.draws;
+            }
+            _ => {
+                let vertex_buffer = wgpu::util::DeviceExt::create_buffer_init(
+                    device,
+                    &wgpu::util::BufferInitDescriptor {
+                        label: Some("Mesh Vertex Buffer"),
+                        contents: vertex_bytes,
+                        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                    },
+                );
+                let index_buffer = wgpu::util::DeviceExt::create_buffer_init(
+                    device,
+                    &wgpu::util::BufferInitDescriptor {
+                        label: Some("Mesh Index Buffer"),
+                        contents: index_bytes,
+                        usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
+                    },
+                );
+                self.mesh_geometry = Some(MeshGeometry {
+                    vertex_buffer,
+                    index_buffer,
+                    draws: meshes.draws,
+                });
+            }
+        }
+    }
+
+    fn create_mesh_pipeline(
+        device: &wgpu::Device,
+        surface_format: wgpu::TextureFormat,
+        uniform: &UniformBinding,
+        texture_bind_group_layout: &wgpu::BindGroupLayout,
+    ) -> wgpu::RenderPipeline {
+        let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Mesh Shader"),
+            source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(MESH_SHADER_SOURCE)),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Mesh Pipeline Layout"),
+            bind_group_layouts: &[&uniform.bind_group_layout, texture_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Mesh Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader_module,
+                entry_point: "vertex_main",
+                buffers: &[MeshVertex::description(&MeshVertex::vertex_attributes())],
+            },
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                conservative: false,
+                unclipped_depth: false,
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: Renderer::DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader_module,
+                entry_point: "fragment_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: surface_format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            multiview: None,
+        })
     }
 
     fn create_pipeline(
@@ -431,6 +910,218 @@ impl Vertex {
     }
 }
 
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct MeshVertex {
+    position: [f32; 4],
+    normal: [f32; 4],
+    color: [f32; 4],
+    uv: [f32; 2],
+}
+
+impl MeshVertex {
+    pub fn vertex_attributes() -> Vec<wgpu::VertexAttribute> {
+        wgpu::vertex_attr_array![0 => Float32x4, 1 => Float32x4, 2 => Float32x4, 3 => Float32x2]
+            .to_vec()
+    }
+
+    pub fn description(attributes: &[wgpu::VertexAttribute]) -> wgpu::VertexBufferLayout {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<MeshVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes,
+        }
+    }
+}
+
+/// Gathers the triangles of every mesh in a scene into one list of world space vertices,
+/// with a draw for each primitive. Nodes whose bounds lie outside the view are skipped,
+/// and the rest draw the level of detail that suits their size on screen.
+/// Nodes with morph targets are blended on the CPU with their current weights.
+/// Other topologies and skinning are not drawn yet.
+fn world_mesh_geometry(
+    world: &world::World,
+    scene_index: usize,
+    view_projection: &nalgebra_glm::Mat4,
+    lod_selection: &world::LodSelection,
+) -> WorldMeshes {
+    let mut meshes = WorldMeshes::default();
+    if scene_index >= world.scenes.len() {
+        return meshes;
+    }
+    let frustum = world::Frustum::from_view_projection(view_projection);
+    for scene_node in world.depth_first(scene_index) {
+        let node = &world.nodes[scene_node.node_index];
+        let Some(mesh_index) = node.mesh_index else {
+            continue;
+        };
+        let weights = world.node_morph_weights(scene_node.node_index);
+        let transform = scene_node.global_transform;
+        // Morphed vertices can move past the bounds of the unmorphed mesh
+        let morphed = weights.iter().any(|weight| *weight != 0.0);
+        if !morphed
+            && node.aabb_index.is_some_and(|aabb_index| {
+                !frustum.intersects_aabb(&world.aabbs[aabb_index].transform(&transform))
+            })
+        {
+            continue;
+        }
+        let level = world.select_lod(
+            scene_node.node_index,
+            &transform,
+            view_projection,
+            lod_selection,
+        );
+        let normal_matrix = nalgebra_glm::inverse_transpose(nalgebra_glm::mat4_to_mat3(&transform));
+        for primitive in world.meshes[mesh_index].primitives.iter() {
+            if primitive.topology != world::PrimitiveTopology::Triangles {
+                continue;
+            }
+            let primitive_vertices = if primitive.number_of_morph_targets > 0 && morphed {
+                std::borrow::Cow::Owned(world.blend_morph_targets(primitive, weights))
+            } else {
+                std::borrow::Cow::Borrowed(
+                    &world.vertices[primitive.vertex_offset
+                        ..(primitive.vertex_offset + primitive.number_of_vertices)],
+                )
+            };
+            let material = primitive
+                .material_index
+                .and_then(|material_index| world.materials.get(material_index));
+            let base_color = material.map_or(nalgebra_glm::Vec4::repeat(1.0), |material| {
+                material.base_color_factor
+            });
+            let base_color_texture =
+                material.and_then(|material| material.base_color_texture.as_ref());
+            let tex_coord = base_color_texture.map_or(0, |info| {
+                info.transform
+                    .as_ref()
+                    .and_then(|transform| transform.tex_coord)
+                    .unwrap_or(info.tex_coord)
+            });
+            let uv_transform = base_color_texture
+                .and_then(|info| info.transform.as_ref())
+                .map_or(nalgebra_glm::Mat3::identity(), |transform| {
+                    transform.matrix()
+                });
+
+            let first_vertex = meshes.vertices.len() as u32;
+            meshes
+                .vertices
+                .extend(primitive_vertices.iter().map(|vertex| {
+                    let position = transform * vertex.position.push(1.0);
+                    let normal = normal_matrix * vertex.normal;
+                    let color = vertex.color_0.push(1.0).component_mul(&base_color);
+                    let uv = match tex_coord {
+                        1 => vertex.uv_1,
+                        _ => vertex.uv_0,
+                    };
+                    let uv = uv_transform * uv.push(1.0);
+                    MeshVertex {
+                        position: position.into(),
+                        normal: normal.push(0.0).into(),
+                        color: color.into(),
+                        uv: uv.xy().into(),
+                    }
+                }));
+            let first_index = meshes.indices.len() as u32;
+            let (index_offset, number_of_indices) = primitive.lod_indices(level);
+            if number_of_indices > 0 {
+                meshes.indices.extend(
+                    world.indices[index_offset..(index_offset + number_of_indices)]
+                        .iter()
+                        .map(|index| first_vertex + index),
+                );
+            } else {
+                meshes
+                    .indices
+                    .extend(first_vertex..(first_vertex + primitive.number_of_vertices as u32));
+            }
+            meshes.draws.push(MeshDraw {
+                texture_index: base_color_texture.map(|info| info.texture_index),
+                indices: first_index..(meshes.indices.len() as u32),
+            });
+        }
+    }
+    meshes
+}
+
+fn create_texture_bind_group(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    texture: &wgpu::Texture,
+    sampler: Option<&world::Sampler>,
+) -> wgpu::BindGroup {
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    let sampler =
+        device.create_sampler(&sampler_descriptor(sampler, texture.mip_level_count() > 1));
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(&sampler),
+            },
+        ],
+        label: Some("texture_bind_group"),
+    })
+}
+
+/// Textures without a sampler repeat and filter linearly between mip levels, as glTF suggests.
+/// Min filters without a mipmap mode only sample the largest level.
+fn sampler_descriptor(
+    sampler: Option<&world::Sampler>,
+    has_mip_levels: bool,
+) -> wgpu::SamplerDescriptor<'static> {
+    use wgpu::FilterMode::{Linear, Nearest};
+
+    let address_mode = |mode: &world::WrappingMode| match mode {
+        world::WrappingMode::ClampToEdge => wgpu::AddressMode::ClampToEdge,
+        world::WrappingMode::MirroredRepeat => wgpu::AddressMode::MirrorRepeat,
+        world::WrappingMode::Repeat => wgpu::AddressMode::Repeat,
+    };
+    let Some(sampler) = sampler else {
+        return wgpu::SamplerDescriptor {
+            label: Some("Texture Sampler"),
+            address_mode_u: wgpu::AddressMode::Repeat,
+            address_mode_v: wgpu::AddressMode::Repeat,
+            mag_filter: Linear,
+            min_filter: Linear,
+            mipmap_filter: Linear,
+            ..Default::default()
+        };
+    };
+    let (min_filter, mipmap_filter) = match sampler.min_filter {
+        world::MinFilter::Nearest => (Nearest, None),
+        world::MinFilter::Linear => (Linear, None),
+        world::MinFilter::NearestMipmapNearest => (Nearest, Some(Nearest)),
+        world::MinFilter::LinearMipmapNearest => (Linear, Some(Nearest)),
+        world::MinFilter::NearestMipmapLinear => (Nearest, Some(Linear)),
+        world::MinFilter::LinearMipmapLinear => (Linear, Some(Linear)),
+    };
+    wgpu::SamplerDescriptor {
+        label: Some("Texture Sampler"),
+        address_mode_u: address_mode(&sampler.wrap_s),
+        address_mode_v: address_mode(&sampler.wrap_t),
+        mag_filter: match sampler.mag_filter {
+            world::MagFilter::Nearest => Nearest,
+            world::MagFilter::Linear => Linear,
+        },
+        min_filter,
+        mipmap_filter: mipmap_filter.unwrap_or(Nearest),
+        lod_max_clamp: if mipmap_filter.is_some() && has_mip_levels {
+            32.0
+        } else {
+            0.0
+        },
+        ..Default::default()
+    }
+}
+
 #[repr(C)]
 #[derive(Default, Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
 struct UniformBuffer {
@@ -545,3 +1236,235 @@ fn fragment_main(in: VertexOutput) -> @location(0) vec4<f32> {
     return vec4<f32>(in.color);
 }
 ";
+
+const MESH_SHADER_SOURCE: &str = "
+struct Uniform {
+    mvp: mat4x4<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> ubo: Uniform;
+
+@group(1) @binding(0)
+var base_color_texture: texture_2d<f32>;
+@group(1) @binding(1)
+var base_color_sampler: sampler;
+
+struct VertexInput {
+    @location(0) position: vec4<f32>,
+    @location(1) normal: vec4<f32>,
+    @location(2) color: vec4<f32>,
+    @location(3) uv: vec2<f32>,
+};
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) normal: vec3<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) uv: vec2<f32>,
+};
+
+@vertex
+fn vertex_main(vert: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.normal = vert.normal.xyz;
+    out.color = vert.color;
+    out.uv = vert.uv;
+    out.position = ubo.mvp * vert.position;
+    return out;
+};
+
+@fragment
+fn fragment_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    var light = 1.0;
+    if dot(in.normal, in.normal) > 0.0 {
+        light = 0.3 + 0.7 * abs(dot(normalize(in.normal), normalize(vec3<f32>(0.4, 1.0, 0.6))));
+    }
+    let color = in.color * textureSample(base_color_texture, base_color_sampler, in.uv);
+    return vec4<f32>(color.rgb * light, color.a);
+}
+";
+
+#[cfg(test)]
+mod tests {
+    use super::{world_mesh_geometry, MeshDraw, TextureSource};
+
+    #[test]
+    fn morphed_meshes_are_blended_and_moved_into_world_space() {
+        let mut world = world::World {
+            vertices: vec![
+                world::Vertex {
+                    normal: nalgebra_glm::vec3(0.0, 0.0, 1.0),
+                    color_0: nalgebra_glm::vec3(1.0, 1.0, 1.0),
+                    ..Default::default()
+                };
+                3
+            ],
+            indices: vec![0, 1, 2],
+            morph_target_vertices: vec![
+                world::MorphTargetVertex {
+                    position: nalgebra_glm::vec3(0.0, 2.0, 0.0),
+                    ..Default::default()
+                };
+                3
+            ],
+            meshes: vec![world::Mesh {
+                primitives: vec![world::Primitive {
+                    number_of_vertices: 3,
+                    number_of_indices: 3,
+                    number_of_morph_targets: 1,
+                    ..Default::default()
+                }],
+                weights: vec![0.0],
+            }],
+            morph_weights: vec![vec![0.5]],
+            scenes: vec![world::Scene::default()],
+            ..Default::default()
+        };
+        let node_index = world.add_node();
+        world.nodes[node_index].mesh_index = Some(0);
+        world.nodes[node_index].morph_weights_index = Some(0);
+        world.transforms[world.nodes[node_index].transform_index].translation =
+            nalgebra_glm::vec3(1.0, 0.0, 0.0);
+        world.scenes[0].graph.add_node(node_index);
+
+        let meshes = world_mesh_geometry(
+            &world,
+            0,
+            &nalgebra_glm::Mat4::identity(),
+            &world::LodSelection::default(),
+        );
+
+        assert_eq!(meshes.indices, vec![0, 1, 2]);
+        assert_eq!(
+            meshes.draws,
+            vec![MeshDraw {
+                texture_index: None,
+                indices: 0..3
+            }]
+        );
+        assert_eq!(meshes.vertices.len(), 3);
+        assert_eq!(meshes.vertices[0].position, [1.0, 1.0, 0.0, 1.0]);
+        assert_eq!(meshes.vertices[0].normal, [0.0, 0.0, 1.0, 0.0]);
+        assert_eq!(meshes.vertices[0].color, [1.0, 1.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn nodes_outside_the_view_are_culled() {
+        let mut world = world::World {
+            vertices: vec![world::Vertex::default(); 3],
+            indices: vec![0, 1, 2],
+            meshes: vec![world::Mesh {
+                primitives: vec![world::Primitive {
+                    number_of_vertices: 3,
+                    number_of_indices: 3,
+                    ..Default::default()
+                }],
+                weights: Vec::new(),
+            }],
+            aabbs: vec![world::AxisAlignedBoundingBox::new(
+                nalgebra_glm::vec3(-0.5, -0.5, -0.5),
+                nalgebra_glm::vec3(0.5, 0.5, 0.5),
+            )],
+            scenes: vec![world::Scene::default()],
+            ..Default::default()
+        };
+        for x in [0.0, 50.0] {
+            let node_index = world.add_node();
+            world.nodes[node_index].mesh_index = Some(0);
+            world.nodes[node_index].aabb_index = Some(0);
+            world.transforms[world.nodes[node_index].transform_index].translation =
+                nalgebra_glm::vec3(x, 0.0, 0.0);
+            world.scenes[0].graph.add_node(node_index);
+        }
+        let view_projection = world::Camera::default().projection_matrix(1.0)
+            * nalgebra_glm::look_at(
+                &nalgebra_glm::vec3(0.0, 0.0, 5.0),
+                &nalgebra_glm::Vec3::zeros(),
+                &nalgebra_glm::Vec3::y(),
+            );
+
+        let meshes =
+            world_mesh_geometry(&world, 0, &view_projection, &world::LodSelection::default());
+
+        assert_eq!(meshes.draws.len(), 1);
+        assert!(meshes
+            .vertices
+            .iter()
+            .all(|vertex| vertex.position == [0.0, 0.0, 0.0, 1.0]));
+    }
+
+    #[test]
+    fn distant_nodes_draw_a_simpler_level_of_detail() {
+        let mut world = world::World {
+            vertices: vec![world::Vertex::default(); 4],
+            indices: vec![0, 1, 2, 0, 2, 3, 0, 1, 2],
+            meshes: vec![world::Mesh {
+                primitives: vec![world::Primitive {
+                    number_of_vertices: 4,
+                    number_of_indices: 6,
+                    lods: vec![world::PrimitiveLod {
+                        index_offset: 6,
+                        number_of_indices: 3,
+                        error: 0.1,
+                    }],
+                    ..Default::default()
+                }],
+                weights: Vec::new(),
+            }],
+            aabbs: vec![world::AxisAlignedBoundingBox::new(
+                nalgebra_glm::vec3(-0.5, -0.5, -0.5),
+                nalgebra_glm::vec3(0.5, 0.5, 0.5),
+            )],
+            scenes: vec![world::Scene::default()],
+            ..Default::default()
+        };
+        let node_index = world.add_node();
+        world.nodes[node_index].mesh_index = Some(0);
+        world.nodes[node_index].aabb_index = Some(0);
+        world.scenes[0].graph.add_node(node_index);
+        let number_of_indices = |distance: f32| {
+            let view_projection = world::Camera::default().projection_matrix(1.0)
+                * nalgebra_glm::look_at(
+                    &nalgebra_glm::vec3(0.0, 0.0, distance),
+                    &nalgebra_glm::Vec3::zeros(),
+                    &nalgebra_glm::Vec3::y(),
+                );
+            world_mesh_geometry(&world, 0, &view_projection, &world::LodSelection::default())
+                .indices
+                .len()
+        };
+
+        assert_eq!(number_of_indices(3.0), 6);
+        assert_eq!(number_of_indices(100.0), 3);
+    }
+
+    #[test]
+    fn new_mip_levels_and_compressed_copies_change_the_texture_source() {
+        let mut world = world::World {
+            images: vec![world::Image {
+                pixels: vec![255; 4 * 4 * 4],
+                format: world::ImageFormat::R8G8B8A8,
+                width: 4,
+                height: 4,
+                mip_levels: Vec::new(),
+                compressed: None,
+            }],
+            textures: vec![world::Texture {
+                image_index: 0,
+                sampler_index: None,
+            }],
+            ..Default::default()
+        };
+        let source = |world: &world::World| TextureSource::of(world, &world.textures[0]);
+        let uploaded = source(&world);
+
+        world.images[0]
+            .generate_mipmaps(world::MipmapFilter::Box, true)
+            .unwrap();
+        let with_mip_levels = source(&world);
+        assert!(with_mip_levels != uploaded);
+
+        world.images[0].compress_bc7().unwrap();
+        assert!(source(&world) != with_mip_levels);
+    }
+}
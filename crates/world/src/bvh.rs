@@ -0,0 +1,328 @@
+use crate::{
+    frustum::Frustum,
+    raycast::{Ray, RayHit},
+    world::{AxisAlignedBoundingBox, BoundingSphere, Scene, World},
+};
+use std::collections::HashMap;
+
+/// Bounding volume hierarchy over the world space bounding boxes of a scene's nodes.
+/// Only nodes with an `aabb_index` are inserted.
+#[derive(Default, Debug, Clone)]
+pub struct BoundingVolumeHierarchy {
+    pub nodes: Vec<BvhNode>,
+    leaf_indices: HashMap<petgraph::graph::NodeIndex, usize>,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct BvhNode {
+    pub aabb: AxisAlignedBoundingBox,
+    pub parent_index: Option<usize>,
+    pub kind: BvhNodeKind,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub enum BvhNodeKind {
+    Branch { left: usize, right: usize },
+    Leaf(petgraph::graph::NodeIndex),
+}
+
+impl BoundingVolumeHierarchy {
+    pub fn new(world: &World, scene: &Scene) -> Self {
+        let mut leaves = scene
+            .graph
+            .node_indices()
+            .filter_map(|graph_node_index| {
+                world_space_aabb(world, scene, graph_node_index)
+                    .map(|aabb| (graph_node_index, aabb))
+            })
+            .collect::<Vec<_>>();
+        let mut bvh = Self::default();
+        if !leaves.is_empty() {
+            bvh.build(&mut leaves, None);
+        }
+        bvh
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn contains(&self, graph_node_index: petgraph::graph::NodeIndex) -> bool {
+        self.leaf_indices.contains_key(&graph_node_index)
+    }
+
+    // Children are always pushed after their parent, which `refit` relies on
+    fn build(
+        &mut self,
+        leaves: &mut [(petgraph::graph::NodeIndex, AxisAlignedBoundingBox)],
+        parent_index: Option<usize>,
+    ) -> usize {
+        let index = self.nodes.len();
+        let mut aabb = leaves[0].1;
+        leaves
+            .iter()
+            .skip(1)
+            .for_each(|(_, leaf_aabb)| aabb.expand_to_include(leaf_aabb));
+
+        if let [(graph_node_index, _)] = leaves {
+            self.nodes.push(BvhNode {
+                aabb,
+                parent_index,
+                kind: BvhNodeKind::Leaf(*graph_node_index),
+            });
+            self.leaf_indices.insert(*graph_node_index, index);
+            return index;
+        }
+
+        self.nodes.push(BvhNode {
+            aabb,
+            parent_index,
+            kind: BvhNodeKind::Branch { left: 0, right: 0 },
+        });
+
+        // Split at the median centroid along the axis where the centroids are most spread out
+        let mut centroid_bounds =
+            AxisAlignedBoundingBox::new(leaves[0].1.center(), leaves[0].1.center());
+        leaves.iter().for_each(|(_, leaf_aabb)| {
+            let center = leaf_aabb.center();
+            centroid_bounds.expand_to_include(&AxisAlignedBoundingBox::new(center, center));
+        });
+        let extents = centroid_bounds.extents();
+        let axis = if extents.x >= extents.y && extents.x >= extents.z {
+            0
+        } else if extents.y >= extents.z {
+            1
+        } else {
+            2
+        };
+        leaves.sort_by(|(_, a), (_, b)| a.center()[axis].total_cmp(&b.center()[axis]));
+        let (left_leaves, right_leaves) = leaves.split_at_mut(leaves.len() / 2);
+
+        let left = self.build(left_leaves, Some(index));
+        let right = self.build(right_leaves, Some(index));
+        self.nodes[index].kind = BvhNodeKind::Branch { left, right };
+        index
+    }
+
+    /// Recomputes every leaf from the current transforms and refits all branches
+    pub fn refit(&mut self, world: &World, scene: &Scene) {
+        (0..self.nodes.len())
+            .rev()
+            .for_each(|index| match self.nodes[index].kind {
+                BvhNodeKind::Leaf(graph_node_index) => {
+                    if let Some(aabb) = world_space_aabb(world, scene, graph_node_index) {
+                        self.nodes[index].aabb = aabb;
+                    }
+                }
+                BvhNodeKind::Branch { left, right } => {
+                    self.nodes[index].aabb = self.merged_aabb(left, right);
+                }
+            });
+    }
+
+    /// Refits the leaves of a node whose transform changed and of all of its descendants
+    pub fn refit_subtree(
+        &mut self,
+        world: &World,
+        scene: &Scene,
+        graph_node_index: petgraph::graph::NodeIndex,
+    ) {
+        let mut dfs = petgraph::visit::Dfs::new(&scene.graph, graph_node_index);
+        while let Some(graph_node_index) = dfs.next(&scene.graph) {
+            if let Some(aabb) = world_space_aabb(world, scene, graph_node_index) {
+                self.update_leaf(graph_node_index, aabb);
+            }
+        }
+    }
+
+    /// Replaces a leaf's bounding box and refits its ancestors,
+    /// returning false if the node is not part of the hierarchy
+    pub fn update_leaf(
+        &mut self,
+        graph_node_index: petgraph::graph::NodeIndex,
+        aabb: AxisAlignedBoundingBox,
+    ) -> bool {
+        let Some(&leaf_index) = self.leaf_indices.get(&graph_node_index) else {
+            return false;
+        };
+        self.nodes[leaf_index].aabb = aabb;
+        let mut parent_index = self.nodes[leaf_index].parent_index;
+        while let Some(index) = parent_index {
+            if let BvhNodeKind::Branch { left, right } = self.nodes[index].kind {
+                self.nodes[index].aabb = self.merged_aabb(left, right);
+            }
+            parent_index = self.nodes[index].parent_index;
+        }
+        true
+    }
+
+    fn merged_aabb(&self, left: usize, right: usize) -> AxisAlignedBoundingBox {
+        let mut aabb = self.nodes[left].aabb;
+        aabb.expand_to_include(&self.nodes[right].aabb);
+        aabb
+    }
+
+    /// Collects every leaf whose bounding box passes the test,
+    /// skipping subtrees whose bounds fail it
+    pub fn query(
+        &self,
+        mut test: impl FnMut(&AxisAlignedBoundingBox) -> bool,
+    ) -> Vec<petgraph::graph::NodeIndex> {
+        let mut results = Vec::new();
+        if self.nodes.is_empty() {
+            return results;
+        }
+        let mut stack = vec![0];
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if !test(&node.aabb) {
+                continue;
+            }
+            match node.kind {
+                BvhNodeKind::Leaf(graph_node_index) => results.push(graph_node_index),
+                BvhNodeKind::Branch { left, right } => {
+                    stack.push(right);
+                    stack.push(left);
+                }
+            }
+        }
+        results
+    }
+
+    pub fn query_aabb(&self, aabb: &AxisAlignedBoundingBox) -> Vec<petgraph::graph::NodeIndex> {
+        self.query(|node_aabb| node_aabb.intersects(aabb))
+    }
+
+    pub fn query_sphere(&self, sphere: &BoundingSphere) -> Vec<petgraph::graph::NodeIndex> {
+        self.query(|node_aabb| sphere.intersects_aabb(node_aabb))
+    }
+
+    pub fn query_frustum(&self, frustum: &Frustum) -> Vec<petgraph::graph::NodeIndex> {
+        self.query(|node_aabb| frustum.intersects_aabb(node_aabb))
+    }
+
+    /// Returns the leaves hit by the ray, nearest first
+    pub fn query_ray(&self, ray: &Ray) -> Vec<(petgraph::graph::NodeIndex, f32)> {
+        let mut hits = self
+            .query(|node_aabb| ray.intersect_aabb(node_aabb).is_some())
+            .into_iter()
+            .filter_map(|graph_node_index| {
+                let aabb = &self.nodes[self.leaf_indices[&graph_node_index]].aabb;
+                ray.intersect_aabb(aabb)
+                    .map(|distance| (graph_node_index, distance))
+            })
+            .collect::<Vec<_>>();
+        hits.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+        hits
+    }
+
+    /// Returns every pair of leaves with overlapping bounding boxes, for use as a broadphase
+    pub fn overlapping_pairs(
+        &self,
+    ) -> Vec<(petgraph::graph::NodeIndex, petgraph::graph::NodeIndex)> {
+        self.nodes
+            .iter()
+            .filter_map(|node| match node.kind {
+                BvhNodeKind::Leaf(graph_node_index) => Some((graph_node_index, node.aabb)),
+                BvhNodeKind::Branch { .. } => None,
+            })
+            .flat_map(|(graph_node_index, aabb)| {
+                self.query_aabb(&aabb)
+                    .into_iter()
+                    .filter(move |other| graph_node_index < *other)
+                    .map(move |other| (graph_node_index, other))
+            })
+            .collect()
+    }
+}
+
+pub fn world_space_aabb(
+    world: &World,
+    scene: &Scene,
+    graph_node_index: petgraph::graph::NodeIndex,
+) -> Option<AxisAlignedBoundingBox> {
+    let node = &world.nodes[scene.graph[graph_node_index]];
+    let aabb = world.aabbs[node.aabb_index?];
+    Some(aabb.transform(&world.global_transform(&scene.graph, graph_node_index)))
+}
+
+impl World {
+    /// Same as `raycast`, but only tests the triangles of nodes whose bounds the ray enters
+    pub fn raycast_with_bvh(
+        &self,
+        scene_index: usize,
+        bvh: &BoundingVolumeHierarchy,
+        ray: &Ray,
+    ) -> Option<RayHit> {
+        let mut closest_hit: Option<RayHit> = None;
+        for (graph_node_index, distance) in bvh.query_ray(ray) {
+            let max_distance = closest_hit.map_or(f32::INFINITY, |hit| hit.distance);
+            if distance > max_distance {
+                break;
+            }
+            if let Some(hit) = self.raycast_node(scene_index, graph_node_index, ray, max_distance) {
+                closest_hit = Some(hit);
+            }
+        }
+        closest_hit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BoundingVolumeHierarchy;
+    use crate::world::{AxisAlignedBoundingBox, BoundingSphere, Scene, World};
+
+    fn create_world(positions: &[f32]) -> World {
+        let mut world = World {
+            aabbs: vec![AxisAlignedBoundingBox::new(
+                nalgebra_glm::vec3(-0.5, -0.5, -0.5),
+                nalgebra_glm::vec3(0.5, 0.5, 0.5),
+            )],
+            ..Default::default()
+        };
+        let mut scene = Scene::default();
+        positions.iter().for_each(|x| {
+            let node_index = world.add_node();
+            world.nodes[node_index].aabb_index = Some(0);
+            world.transforms[node_index].translation = nalgebra_glm::vec3(*x, 0.0, 0.0);
+            scene.graph.add_node(node_index);
+        });
+        world.scenes.push(scene);
+        world
+    }
+
+    #[test]
+    fn queries_return_overlapping_leaves() {
+        let world = create_world(&[0.0, 2.0, 4.0, 6.0, 8.0]);
+        let bvh = BoundingVolumeHierarchy::new(&world, &world.scenes[0]);
+        let mut hits = bvh
+            .query_aabb(&AxisAlignedBoundingBox::new(
+                nalgebra_glm::vec3(1.8, -1.0, -1.0),
+                nalgebra_glm::vec3(4.2, 1.0, 1.0),
+            ))
+            .into_iter()
+            .map(|graph_node_index| graph_node_index.index())
+            .collect::<Vec<_>>();
+        hits.sort();
+        assert_eq!(hits, vec![1, 2]);
+
+        let hits = bvh.query_sphere(&BoundingSphere::new(nalgebra_glm::vec3(8.0, 2.0, 0.0), 1.6));
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].index(), 4);
+    }
+
+    #[test]
+    fn refit_follows_transform_changes() {
+        let mut world = create_world(&[0.0, 2.0, 4.0]);
+        let mut bvh = BoundingVolumeHierarchy::new(&world, &world.scenes[0]);
+        assert!(bvh.overlapping_pairs().is_empty());
+
+        world.transforms[2].translation = nalgebra_glm::vec3(2.5, 0.0, 0.0);
+        bvh.refit_subtree(&world, &world.scenes[0], petgraph::graph::NodeIndex::new(2));
+        let pairs = bvh.overlapping_pairs();
+        assert_eq!(pairs.len(), 1);
+        assert_eq!((pairs[0].0.index(), pairs[0].1.index()), (1, 2));
+        assert!((bvh.nodes[0].aabb.max.x - 3.0).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,341 @@
+use crate::{
+    file::WorldFileError,
+    world::{
+        Animation, AxisAlignedBoundingBox, Camera, Light, Material, Mesh, Node, NodeMetadata,
+        PrimitiveMesh, Scene, Skin, Transform, World,
+    },
+};
+
+/// The elements of one of the world's collections that differ from a previous state.
+/// Elements past `length` were removed, and changed or appended elements are listed by index.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct CollectionDelta<T> {
+    pub length: usize,
+    pub changes: Vec<(usize, T)>,
+}
+
+impl<T: Clone> CollectionDelta<T> {
+    fn between(previous: &[T], current: &[T], equal: impl Fn(&T, &T) -> bool) -> Option<Self> {
+        let changes = current
+            .iter()
+            .enumerate()
+            .filter(|(index, element)| {
+                previous
+                    .get(*index)
+                    .map_or(true, |previous_element| !equal(previous_element, element))
+            })
+            .map(|(index, element)| (index, element.clone()))
+            .collect::<Vec<_>>();
+        if changes.is_empty() && previous.len() == current.len() {
+            return None;
+        }
+        Some(Self {
+            length: current.len(),
+            changes,
+        })
+    }
+
+    fn apply(self, collection: &mut Vec<T>) {
+        collection.truncate(self.length);
+        // Changes are in ascending order, so appended elements arrive in the right place
+        self.changes.into_iter().for_each(|(index, element)| {
+            if index < collection.len() {
+                collection[index] = element;
+            } else {
+                collection.push(element);
+            }
+        });
+    }
+}
+
+/// The changes to a world's frequently edited state,
+/// which can be sent to processes that mirror an authoritative world.
+///
+/// Vertices, indices, morph targets and images are not tracked,
+/// so mirrors are expected to start from a full copy of the world such as a world file.
+/// Meshes that refer to newly added geometry only make sense once a mirror has reloaded it.
+#[derive(Default, Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct WorldDelta {
+    pub nodes: Option<CollectionDelta<Node>>,
+    pub transforms: Option<CollectionDelta<Transform>>,
+    pub metadata: Option<CollectionDelta<NodeMetadata>>,
+    pub materials: Option<CollectionDelta<Material>>,
+    pub cameras: Option<CollectionDelta<Camera>>,
+    pub lights: Option<CollectionDelta<Light>>,
+    pub aabbs: Option<CollectionDelta<AxisAlignedBoundingBox>>,
+    pub morph_weights: Option<CollectionDelta<Vec<f32>>>,
+    pub scenes: Option<CollectionDelta<Scene>>,
+    pub meshes: Option<CollectionDelta<Mesh>>,
+    pub primitive_meshes: Option<CollectionDelta<PrimitiveMesh>>,
+    pub skins: Option<CollectionDelta<Skin>>,
+    pub animations: Option<CollectionDelta<Animation>>,
+    pub default_scene_index: Option<usize>,
+}
+
+impl WorldDelta {
+    /// Computes the delta that turns `previous` into `current`
+    pub fn between(previous: &World, current: &World) -> Self {
+        Self {
+            nodes: CollectionDelta::between(&previous.nodes, &current.nodes, PartialEq::eq),
+            transforms: CollectionDelta::between(
+                &previous.transforms,
+                &current.transforms,
+                PartialEq::eq,
+            ),
+            metadata: CollectionDelta::between(
+                &previous.metadata,
+                &current.metadata,
+                PartialEq::eq,
+            ),
+            materials: CollectionDelta::between(
+                &previous.materials,
+                &current.materials,
+                PartialEq::eq,
+            ),
+            cameras: CollectionDelta::between(&previous.cameras, &current.cameras, PartialEq::eq),
+            lights: CollectionDelta::between(&previous.lights, &current.lights, PartialEq::eq),
+            aabbs: CollectionDelta::between(&previous.aabbs, &current.aabbs, PartialEq::eq),
+            morph_weights: CollectionDelta::between(
+                &previous.morph_weights,
+                &current.morph_weights,
+                PartialEq::eq,
+            ),
+            scenes: CollectionDelta::between(&previous.scenes, &current.scenes, scenes_equal),
+            meshes: CollectionDelta::between(&previous.meshes, &current.meshes, PartialEq::eq),
+            primitive_meshes: CollectionDelta::between(
+                &previous.primitive_meshes,
+                &current.primitive_meshes,
+                PartialEq::eq,
+            ),
+            skins: CollectionDelta::between(&previous.skins, &current.skins, PartialEq::eq),
+            animations: CollectionDelta::between(
+                &previous.animations,
+                &current.animations,
+                PartialEq::eq,
+            ),
+            default_scene_index: (previous.default_scene_index != current.default_scene_index)
+                .then_some(current.default_scene_index),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_none()
+            && self.transforms.is_none()
+            && self.metadata.is_none()
+            && self.materials.is_none()
+            && self.cameras.is_none()
+            && self.lights.is_none()
+            && self.aabbs.is_none()
+            && self.morph_weights.is_none()
+            && self.scenes.is_none()
+            && self.meshes.is_none()
+            && self.primitive_meshes.is_none()
+            && self.skins.is_none()
+            && self.animations.is_none()
+            && self.default_scene_index.is_none()
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, WorldFileError> {
+        crate::file::encode(self)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, WorldFileError> {
+        crate::file::decode(bytes)
+    }
+}
+
+impl World {
+    pub fn apply_delta(&mut self, delta: WorldDelta) {
+        let WorldDelta {
+            nodes,
+            transforms,
+            metadata,
+            materials,
+            cameras,
+            lights,
+            aabbs,
+            morph_weights,
+            scenes,
+            meshes,
+            primitive_meshes,
+            skins,
+            animations,
+            default_scene_index,
+        } = delta;
+        fn apply<T: Clone>(delta: Option<CollectionDelta<T>>, collection: &mut Vec<T>) {
+            if let Some(delta) = delta {
+                delta.apply(collection);
+            }
+        }
+        apply(nodes, &mut self.nodes);
+        apply(transforms, &mut self.transforms);
+        apply(metadata, &mut self.metadata);
+        apply(materials, &mut self.materials);
+        apply(cameras, &mut self.cameras);
+        apply(lights, &mut self.lights);
+        apply(aabbs, &mut self.aabbs);
+        apply(morph_weights, &mut self.morph_weights);
+        apply(scenes, &mut self.scenes);
+        apply(meshes, &mut self.meshes);
+        apply(primitive_meshes, &mut self.primitive_meshes);
+        apply(skins, &mut self.skins);
+        apply(animations, &mut self.animations);
+        if let Some(default_scene_index) = default_scene_index {
+            self.default_scene_index = default_scene_index;
+        }
+    }
+}
+
+/// Records the changes made to an authoritative world between calls to `delta`.
+///
+/// Only the tracked collections are copied, so tracking a world with large assets stays cheap.
+#[derive(Default, Debug, Clone)]
+pub struct ChangeTracker {
+    snapshot: World,
+}
+
+impl ChangeTracker {
+    pub fn new(world: &World) -> Self {
+        let mut tracker = Self::default();
+        tracker
+            .snapshot
+            .apply_delta(WorldDelta::between(&tracker.snapshot, world));
+        tracker
+    }
+
+    /// Returns the changes made since the last call and starts tracking from the world's current state
+    pub fn delta(&mut self, world: &World) -> WorldDelta {
+        let delta = WorldDelta::between(&self.snapshot, world);
+        self.snapshot.apply_delta(delta.clone());
+        delta
+    }
+}
+
+// Scene graphs are compared by their node weights and edges at each index, skipping the
+// vacant indices of removed nodes, which is what their serialized form and every traversal depend on
+fn scenes_equal(a: &Scene, b: &Scene) -> bool {
+    a.default_camera_graph_node_index == b.default_camera_graph_node_index
+        && a.graph.node_indices().eq(b.graph.node_indices())
+        && a.graph.edge_indices().eq(b.graph.edge_indices())
+        && a.graph
+            .node_indices()
+            .all(|graph_node_index| a.graph[graph_node_index] == b.graph[graph_node_index])
+        && a.graph.edge_indices().all(|edge_index| {
+            a.graph.edge_endpoints(edge_index) == b.graph.edge_endpoints(edge_index)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ChangeTracker, WorldDelta};
+    use crate::{
+        file::Compression,
+        world::{
+            Animation, Channel, Interpolation, Joint, Material, Mesh, Primitive, PrimitiveMesh,
+            Scene, Skin, TransformationSet, World,
+        },
+    };
+
+    fn create_world() -> World {
+        let mut world = World::default();
+        let mut scene = Scene::default();
+        let root_node_index = world.add_node();
+        let root = scene.graph.add_node(root_node_index);
+        let camera_node_index = world.add_node();
+        world.add_camera_to_node(camera_node_index);
+        let camera = scene.graph.add_node(camera_node_index);
+        scene.graph.add_edge(root, camera, ());
+        scene.default_camera_graph_node_index = Some(camera);
+        world.scenes.push(scene);
+        world.materials.push(Material::default());
+        world
+    }
+
+    #[test]
+    fn unchanged_worlds_produce_empty_deltas() {
+        let world = create_world();
+        let mut tracker = ChangeTracker::new(&world);
+        assert!(tracker.delta(&world).is_empty());
+    }
+
+    #[test]
+    fn applying_every_delta_reproduces_the_source_world() {
+        let mut source = create_world();
+        let mut mirror = source.clone();
+        let mut tracker = ChangeTracker::new(&source);
+
+        for frame in 0..20 {
+            let time = frame as f32 * 0.1;
+            source.transforms[0].translation = nalgebra_glm::vec3(time.sin(), 0.0, time.cos());
+
+            if frame % 4 == 0 {
+                let node_index = source.add_node();
+                source.metadata[node_index].name = format!("Node {frame}");
+                source.add_child_node(0, petgraph::graph::NodeIndex::new(0), node_index);
+            }
+            if frame % 7 == 6 {
+                let graph = &mut source.scenes[0].graph;
+                let last = graph.node_indices().last().unwrap();
+                graph.remove_node(last);
+            }
+            if frame == 10 {
+                source.materials[0].base_color_factor = nalgebra_glm::vec4(1.0, 0.0, 0.0, 1.0);
+                source.materials.push(Material::default());
+            }
+            if frame == 15 {
+                source.materials.truncate(1);
+            }
+            if frame == 3 {
+                source.meshes.push(Mesh {
+                    primitives: vec![Primitive {
+                        number_of_vertices: 3,
+                        ..Default::default()
+                    }],
+                    weights: vec![0.5],
+                });
+                source.nodes[0].mesh_index = Some(0);
+                source.primitive_meshes.push(PrimitiveMesh::default());
+                source.skins.push(Skin {
+                    joints: vec![Joint {
+                        target_node_index: 1,
+                        inverse_bind_matrix: nalgebra_glm::Mat4::identity(),
+                    }],
+                });
+                source.animations.push(Animation {
+                    channels: vec![Channel {
+                        target_node_index: 0,
+                        inputs: vec![0.0, 1.0],
+                        transformations: TransformationSet::Scales(vec![
+                            nalgebra_glm::vec3(1.0, 1.0, 1.0),
+                            nalgebra_glm::vec3(2.0, 2.0, 2.0),
+                        ]),
+                        interpolation: Interpolation::Linear,
+                        tangents: None,
+                    }],
+                    max_animation_time: 1.0,
+                    ..Default::default()
+                });
+                source.scenes.push(source.scenes[0].clone());
+                source.default_scene_index = 1;
+            }
+            if frame > 3 {
+                source.animations[0].time = time;
+                source.meshes[0].weights[0] = time;
+            }
+            if frame == 12 {
+                source.skins.clear();
+                source.primitive_meshes[0].color = nalgebra_glm::vec4(0.0, 1.0, 0.0, 1.0);
+                source.default_scene_index = 0;
+            }
+
+            let bytes = tracker.delta(&source).to_bytes().unwrap();
+            mirror.apply_delta(WorldDelta::from_bytes(&bytes).unwrap());
+            assert_eq!(
+                mirror.to_bytes(Compression::None).unwrap(),
+                source.to_bytes(Compression::None).unwrap(),
+                "Mirror diverged on frame {frame}"
+            );
+        }
+        assert!(tracker.delta(&source).is_empty());
+    }
+}
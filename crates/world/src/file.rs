@@ -0,0 +1,454 @@
+use crate::world::World;
+use std::io::{Read, Write};
+
+/// Binary world files start with these bytes, followed by the little endian
+/// format version and flags and then the world encoded as MessagePack
+pub const WORLD_FILE_MAGIC: [u8; 4] = *b"DOGE";
+
+/// Structs are written as maps keyed by field name, so fields added with `#[serde(default)]`
+/// still load from older files. Other layout changes bump this version,
+/// and `from_bytes` and `from_json` upgrade worlds written with an older one.
+pub const WORLD_FILE_VERSION: u32 = 2;
+
+const COMPRESSED_FLAG: u32 = 1;
+
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Compression {
+    #[default]
+    None,
+    Deflate,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum WorldFileError {
+    #[error("Failed to access world file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("The data does not start with a world file header")]
+    InvalidMagic,
+    #[error("World file version {0} is newer than the supported version {WORLD_FILE_VERSION}")]
+    UnsupportedVersion(u32),
+    #[error("World file has unknown flags {0:#x}")]
+    UnsupportedFlags(u32),
+    #[error("Failed to encode or decode JSON world: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Failed to encode binary world: {0}")]
+    Encode(#[from] rmp_serde::encode::Error),
+    #[error("Failed to decode binary world: {0}")]
+    Decode(#[from] rmp_serde::decode::Error),
+    #[error("Binary world has {0} bytes left over after decoding")]
+    TrailingBytes(usize),
+}
+
+#[derive(serde::Serialize)]
+struct JsonWorldFileRef<'a> {
+    version: u32,
+    world: &'a World,
+}
+
+#[derive(serde::Deserialize)]
+struct JsonWorldFile {
+    version: u32,
+    world: serde_json::Value,
+}
+
+impl World {
+    pub fn save(
+        &self,
+        path: impl AsRef<std::path::Path>,
+        compression: Compression,
+    ) -> Result<(), WorldFileError> {
+        std::fs::write(path, self.to_bytes(compression)?)?;
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<std::path::Path>) -> Result<Self, WorldFileError> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+
+    pub fn save_json(&self, path: impl AsRef<std::path::Path>) -> Result<(), WorldFileError> {
+        std::fs::write(path, self.to_json()?)?;
+        Ok(())
+    }
+
+    pub fn load_json(path: impl AsRef<std::path::Path>) -> Result<Self, WorldFileError> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn to_bytes(&self, compression: Compression) -> Result<Vec<u8>, WorldFileError> {
+        let flags = match compression {
+            Compression::None => 0,
+            Compression::Deflate => COMPRESSED_FLAG,
+        };
+        let payload = encode(self)?;
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&WORLD_FILE_MAGIC);
+        bytes.extend_from_slice(&WORLD_FILE_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&flags.to_le_bytes());
+        match compression {
+            Compression::None => bytes.extend_from_slice(&payload),
+            Compression::Deflate => {
+                let mut encoder =
+                    flate2::write::DeflateEncoder::new(&mut bytes, flate2::Compression::default());
+                encoder.write_all(&payload)?;
+                encoder.finish()?;
+            }
+        }
+        Ok(bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, WorldFileError> {
+        let header = bytes.get(..12).ok_or(WorldFileError::InvalidMagic)?;
+        if header[..4] != WORLD_FILE_MAGIC {
+            return Err(WorldFileError::InvalidMagic);
+        }
+        let version = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+        if version > WORLD_FILE_VERSION {
+            return Err(WorldFileError::UnsupportedVersion(version));
+        }
+        let flags = u32::from_le_bytes([header[8], header[9], header[10], header[11]]);
+        if flags & !COMPRESSED_FLAG != 0 {
+            return Err(WorldFileError::UnsupportedFlags(flags));
+        }
+
+        let payload = &bytes[12..];
+        let decompressed;
+        let payload = if flags & COMPRESSED_FLAG != 0 {
+            let mut buffer = Vec::new();
+            flate2::read::DeflateDecoder::new(payload).read_to_end(&mut buffer)?;
+            decompressed = buffer;
+            &decompressed[..]
+        } else {
+            payload
+        };
+
+        Ok(match version {
+            1 => decode::<v1::World>(payload)?.into(),
+            _ => decode(payload)?,
+        })
+    }
+
+    pub fn to_json(&self) -> Result<String, WorldFileError> {
+        Ok(serde_json::to_string_pretty(&JsonWorldFileRef {
+            version: WORLD_FILE_VERSION,
+            world: self,
+        })?)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, WorldFileError> {
+        let JsonWorldFile { version, world } = serde_json::from_str(json)?;
+        if version > WORLD_FILE_VERSION {
+            return Err(WorldFileError::UnsupportedVersion(version));
+        }
+        Ok(match version {
+            1 => serde_json::from_value::<v1::World>(world)?.into(),
+            _ => serde_json::from_value(world)?,
+        })
+    }
+}
+
+/// Encodes a value in the binary world format, without a header
+pub(crate) fn encode<T: serde::Serialize>(value: &T) -> Result<Vec<u8>, WorldFileError> {
+    Ok(rmp_serde::to_vec_named(value)?)
+}
+
+/// Decodes a value written by `encode`, requiring that all of the bytes are consumed
+pub(crate) fn decode<T: serde::de::DeserializeOwned>(bytes: &[u8]) -> Result<T, WorldFileError> {
+    let mut remaining = bytes;
+    let value = T::deserialize(&mut rmp_serde::Deserializer::new(&mut remaining))?;
+    if !remaining.is_empty() {
+        return Err(WorldFileError::TrailingBytes(remaining.len()));
+    }
+    Ok(value)
+}
+
+/// The version 1 layout, whose materials held a texture index for every slot
+mod v1 {
+    use crate::world::{
+        AlphaMode, Clearcoat, Specular, TextureInfo, TextureTransform, Transmission,
+    };
+
+    #[derive(serde::Deserialize)]
+    pub struct World {
+        materials: Vec<Material>,
+        #[serde(flatten)]
+        world: crate::world::World,
+    }
+
+    #[derive(serde::Deserialize)]
+    struct Material {
+        base_color_factor: nalgebra_glm::Vec4,
+        base_color_texture_index: usize,
+        emissive_texture_index: usize,
+        emissive_factor: nalgebra_glm::Vec3,
+        alpha_mode: AlphaMode,
+        alpha_cutoff: Option<f32>,
+        #[serde(default)]
+        base_color_texture_transform: Option<TextureTransform>,
+        #[serde(default)]
+        emissive_texture_transform: Option<TextureTransform>,
+        #[serde(default)]
+        emissive_strength: Option<f32>,
+        #[serde(default)]
+        unlit: bool,
+        #[serde(default)]
+        ior: Option<f32>,
+        #[serde(default)]
+        specular: Option<Specular>,
+        #[serde(default)]
+        transmission: Option<Transmission>,
+        #[serde(default)]
+        clearcoat: Option<Clearcoat>,
+    }
+
+    impl From<World> for crate::world::World {
+        fn from(World { materials, world }: World) -> Self {
+            let number_of_textures = world.textures.len();
+            // Materials without a texture pointed at texture zero, which can only
+            // be told apart from a real texture when there are no textures at all
+            let texture = |texture_index: usize, transform: Option<TextureTransform>| {
+                (texture_index < number_of_textures).then_some(TextureInfo {
+                    texture_index,
+                    tex_coord: 0,
+                    transform,
+                })
+            };
+            let materials = materials
+                .into_iter()
+                .map(|material| crate::world::Material {
+                    base_color_factor: material.base_color_factor,
+                    base_color_texture: texture(
+                        material.base_color_texture_index,
+                        material.base_color_texture_transform,
+                    ),
+                    emissive_texture: texture(
+                        material.emissive_texture_index,
+                        material.emissive_texture_transform,
+                    ),
+                    emissive_factor: material.emissive_factor,
+                    alpha_mode: material.alpha_mode,
+                    alpha_cutoff: material.alpha_cutoff,
+                    emissive_strength: material.emissive_strength,
+                    unlit: material.unlit,
+                    ior: material.ior,
+                    specular: material.specular,
+                    transmission: material.transmission,
+                    clearcoat: material.clearcoat,
+                })
+                .collect();
+            Self { materials, ..world }
+        }
+    }
+}
+
+/// Serializes byte buffers such as image pixels in one piece rather than byte by byte
+pub(crate) mod bytes {
+    pub fn serialize<S: serde::Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(bytes)
+    }
+
+    pub fn deserialize<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<u8>, D::Error> {
+        struct BytesVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for BytesVisitor {
+            type Value = Vec<u8>;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("a byte buffer")
+            }
+
+            fn visit_bytes<E: serde::de::Error>(self, bytes: &[u8]) -> Result<Vec<u8>, E> {
+                Ok(bytes.to_vec())
+            }
+
+            fn visit_byte_buf<E: serde::de::Error>(self, bytes: Vec<u8>) -> Result<Vec<u8>, E> {
+                Ok(bytes)
+            }
+
+            fn visit_seq<A: serde::de::SeqAccess<'de>>(
+                self,
+                mut sequence: A,
+            ) -> Result<Vec<u8>, A::Error> {
+                let mut bytes = Vec::with_capacity(sequence.size_hint().unwrap_or_default());
+                while let Some(byte) = sequence.next_element()? {
+                    bytes.push(byte);
+                }
+                Ok(bytes)
+            }
+        }
+
+        deserializer.deserialize_byte_buf(BytesVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encode, Compression, WorldFileError, WORLD_FILE_MAGIC};
+    use crate::world::{AlphaMode, ImageFormat, NodeMetadata, Texture, TextureInfo, World};
+
+    /// A binary world file without compression, as written by the given format version
+    fn world_file(version: u32, payload: Vec<u8>) -> Vec<u8> {
+        [
+            &WORLD_FILE_MAGIC[..],
+            &version.to_le_bytes(),
+            &0_u32.to_le_bytes(),
+            &payload,
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn corrupt_worlds_are_decode_errors() {
+        let mut world = World::default();
+        world.add_node();
+        world.metadata[0] = NodeMetadata {
+            name: "Helmet".to_string(),
+        };
+        let bytes = world.to_bytes(Compression::None).unwrap();
+        let loaded = World::from_bytes(&bytes).unwrap();
+        assert_eq!(loaded.metadata, world.metadata);
+
+        assert!(matches!(
+            World::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(WorldFileError::Decode(_))
+        ));
+        let trailing = [&bytes[..], &[0]].concat();
+        assert!(matches!(
+            World::from_bytes(&trailing),
+            Err(WorldFileError::TrailingBytes(1))
+        ));
+    }
+
+    #[test]
+    fn version_one_materials_keep_only_existing_textures() {
+        #[derive(serde::Serialize)]
+        struct VersionOneWorld {
+            textures: Vec<Texture>,
+            materials: Vec<VersionOneMaterial>,
+        }
+
+        #[derive(serde::Serialize)]
+        struct VersionOneMaterial {
+            base_color_factor: nalgebra_glm::Vec4,
+            base_color_texture_index: usize,
+            emissive_texture_index: usize,
+            emissive_factor: nalgebra_glm::Vec3,
+            alpha_mode: AlphaMode,
+            alpha_cutoff: Option<f32>,
+        }
+
+        let payload = encode(&VersionOneWorld {
+            textures: vec![Texture {
+                image_index: 0,
+                sampler_index: None,
+            }],
+            materials: vec![VersionOneMaterial {
+                base_color_factor: nalgebra_glm::vec4(1.0, 1.0, 1.0, 1.0),
+                base_color_texture_index: 0,
+                emissive_texture_index: 1,
+                emissive_factor: nalgebra_glm::Vec3::zeros(),
+                alpha_mode: AlphaMode::Opaque,
+                alpha_cutoff: None,
+            }],
+        })
+        .unwrap();
+        let world = World::from_bytes(&world_file(1, payload)).unwrap();
+        let material = &world.materials[0];
+        assert_eq!(
+            material.base_color_texture,
+            Some(TextureInfo {
+                texture_index: 0,
+                tex_coord: 0,
+                transform: None,
+            })
+        );
+        assert_eq!(material.emissive_texture, None);
+        assert!(!material.unlit);
+        assert_eq!(world.textures.len(), 1);
+    }
+
+    #[test]
+    fn worlds_written_before_skins_and_material_extensions_load_with_defaults() {
+        #[derive(serde::Serialize)]
+        struct OlderWorld {
+            images: Vec<OlderImage>,
+            materials: Vec<OlderMaterial>,
+            nodes: Vec<OlderNode>,
+        }
+
+        #[derive(serde::Serialize)]
+        struct OlderImage {
+            #[serde(with = "super::bytes")]
+            pixels: Vec<u8>,
+            format: ImageFormat,
+            width: u32,
+            height: u32,
+        }
+
+        #[derive(serde::Serialize)]
+        struct OlderMaterial {
+            base_color_factor: nalgebra_glm::Vec4,
+            base_color_texture: Option<TextureInfo>,
+            emissive_texture: Option<TextureInfo>,
+            emissive_factor: nalgebra_glm::Vec3,
+            alpha_mode: AlphaMode,
+            alpha_cutoff: Option<f32>,
+        }
+
+        #[derive(serde::Serialize)]
+        struct OlderNode {
+            metadata_index: usize,
+            transform_index: usize,
+            camera_index: Option<usize>,
+            mesh_index: Option<usize>,
+            light_index: Option<usize>,
+            rigid_body_index: Option<usize>,
+            primitive_mesh_index: Option<usize>,
+            aabb_index: Option<usize>,
+            morph_weights_index: Option<usize>,
+        }
+
+        let payload = encode(&OlderWorld {
+            images: vec![OlderImage {
+                pixels: vec![255; 4],
+                format: ImageFormat::R8G8B8A8,
+                width: 1,
+                height: 1,
+            }],
+            materials: vec![OlderMaterial {
+                base_color_factor: nalgebra_glm::vec4(1.0, 0.5, 0.25, 1.0),
+                base_color_texture: None,
+                emissive_texture: None,
+                emissive_factor: nalgebra_glm::Vec3::zeros(),
+                alpha_mode: AlphaMode::Blend,
+                alpha_cutoff: None,
+            }],
+            nodes: vec![OlderNode {
+                metadata_index: 0,
+                transform_index: 0,
+                camera_index: None,
+                mesh_index: Some(0),
+                light_index: None,
+                rigid_body_index: None,
+                primitive_mesh_index: None,
+                aabb_index: None,
+                morph_weights_index: None,
+            }],
+        })
+        .unwrap();
+        let world = World::from_bytes(&world_file(2, payload)).unwrap();
+
+        assert_eq!(world.images[0].pixels, vec![255; 4]);
+        assert!(world.images[0].mip_levels.is_empty());
+        assert!(world.images[0].compressed.is_none());
+        let material = &world.materials[0];
+        assert_eq!(material.base_color_factor.y, 0.5);
+        assert_eq!(material.alpha_mode, AlphaMode::Blend);
+        assert_eq!(material.emissive_strength, None);
+        assert!(!material.unlit);
+        assert!(material.clearcoat.is_none());
+        assert_eq!(world.nodes[0].mesh_index, Some(0));
+        assert_eq!(world.nodes[0].skin_index, None);
+    }
+}
@@ -0,0 +1,198 @@
+use crate::world::{AxisAlignedBoundingBox, BoundingSphere, World};
+
+/// A plane where points with a positive signed distance lie in front of it
+#[derive(Debug, Copy, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Plane {
+    pub normal: nalgebra_glm::Vec3,
+    pub distance: f32,
+}
+
+impl Plane {
+    pub fn new(normal: nalgebra_glm::Vec3, distance: f32) -> Self {
+        let length = normal.norm();
+        if length <= f32::EPSILON {
+            // Degenerate planes, such as the far plane of an infinite projection,
+            // are kept as-is so that they accept or reject everything by the sign of `distance`
+            return Self { normal, distance };
+        }
+        Self {
+            normal: normal / length,
+            distance: distance / length,
+        }
+    }
+
+    pub fn signed_distance(&self, point: &nalgebra_glm::Vec3) -> f32 {
+        self.normal.dot(point) + self.distance
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Frustum {
+    pub planes: [Plane; 6],
+}
+
+impl Frustum {
+    /// Extracts the frustum planes from a view projection matrix whose clip space depth ranges from zero to one,
+    /// like those returned by `create_camera_matrices`
+    pub fn from_view_projection(view_projection: &nalgebra_glm::Mat4) -> Self {
+        let row = |index: usize| view_projection.row(index).transpose();
+        let plane =
+            |coefficients: nalgebra_glm::Vec4| Plane::new(coefficients.xyz(), coefficients.w);
+        let (x, y, z, w) = (row(0), row(1), row(2), row(3));
+        Self {
+            planes: [
+                plane(w + x),
+                plane(w - x),
+                plane(w + y),
+                plane(w - y),
+                plane(z),
+                plane(w - z),
+            ],
+        }
+    }
+
+    /// Conservative test that only rejects boxes lying fully behind one of the planes
+    pub fn intersects_aabb(&self, aabb: &AxisAlignedBoundingBox) -> bool {
+        self.planes.iter().all(|plane| {
+            let farthest_corner = nalgebra_glm::vec3(
+                if plane.normal.x >= 0.0 {
+                    aabb.max.x
+                } else {
+                    aabb.min.x
+                },
+                if plane.normal.y >= 0.0 {
+                    aabb.max.y
+                } else {
+                    aabb.min.y
+                },
+                if plane.normal.z >= 0.0 {
+                    aabb.max.z
+                } else {
+                    aabb.min.z
+                },
+            );
+            plane.signed_distance(&farthest_corner) >= 0.0
+        })
+    }
+
+    pub fn intersects_sphere(&self, sphere: &BoundingSphere) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.signed_distance(&sphere.center) >= -sphere.radius)
+    }
+}
+
+impl World {
+    /// Returns the graph nodes of the scene's mesh nodes that are inside the default camera's view.
+    /// Mesh nodes without a bounding box are always considered visible,
+    /// while scenes without a default camera have nothing in view.
+    pub fn visible_nodes(
+        &self,
+        scene_index: usize,
+        aspect_ratio: f32,
+    ) -> Vec<petgraph::graph::NodeIndex> {
+        let scene = &self.scenes[scene_index];
+        let Some((_, projection, view)) =
+            crate::world::create_camera_matrices(self, scene, aspect_ratio)
+        else {
+            return Vec::new();
+        };
+        let frustum = Frustum::from_view_projection(&(projection * view));
+        scene
+            .graph
+            .node_indices()
+            .filter(|graph_node_index| {
+                let node = &self.nodes[scene.graph[*graph_node_index]];
+                if node.mesh_index.is_none() {
+                    return false;
+                }
+                crate::bvh::world_space_aabb(self, scene, *graph_node_index)
+                    .map_or(true, |aabb| frustum.intersects_aabb(&aabb))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Frustum;
+    use crate::world::{AxisAlignedBoundingBox, PerspectiveCamera, Scene, Transform, World};
+
+    fn unit_box_at(x: f32, y: f32, z: f32) -> AxisAlignedBoundingBox {
+        let center = nalgebra_glm::vec3(x, y, z);
+        AxisAlignedBoundingBox::new(
+            center - nalgebra_glm::vec3(0.5, 0.5, 0.5),
+            center + nalgebra_glm::vec3(0.5, 0.5, 0.5),
+        )
+    }
+
+    fn frustum_looking_down_negative_z(z_far: Option<f32>) -> Frustum {
+        let camera = PerspectiveCamera {
+            z_far,
+            ..Default::default()
+        };
+        let view = nalgebra_glm::look_at(
+            &nalgebra_glm::vec3(0.0, 0.0, 5.0),
+            &nalgebra_glm::vec3(0.0, 0.0, 0.0),
+            &nalgebra_glm::Vec3::y(),
+        );
+        Frustum::from_view_projection(&(camera.matrix(1.0) * view))
+    }
+
+    #[test]
+    fn boxes_in_front_of_the_camera_are_visible() {
+        let frustum = frustum_looking_down_negative_z(Some(100.0));
+        assert!(frustum.intersects_aabb(&unit_box_at(0.0, 0.0, 0.0)));
+        assert!(frustum.intersects_aabb(&unit_box_at(4.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn boxes_outside_the_planes_are_culled() {
+        let frustum = frustum_looking_down_negative_z(Some(100.0));
+        // behind the camera
+        assert!(!frustum.intersects_aabb(&unit_box_at(0.0, 0.0, 10.0)));
+        // outside of the 90 degree field of view
+        assert!(!frustum.intersects_aabb(&unit_box_at(8.0, 0.0, 0.0)));
+        assert!(!frustum.intersects_aabb(&unit_box_at(0.0, -8.0, 0.0)));
+        // beyond the far plane
+        assert!(!frustum.intersects_aabb(&unit_box_at(0.0, 0.0, -200.0)));
+    }
+
+    #[test]
+    fn infinite_projection_has_no_far_plane() {
+        let frustum = frustum_looking_down_negative_z(None);
+        assert!(frustum.intersects_aabb(&unit_box_at(0.0, 0.0, -10_000.0)));
+        assert!(!frustum.intersects_aabb(&unit_box_at(0.0, 0.0, 10.0)));
+    }
+
+    #[test]
+    fn visible_nodes_uses_the_scene_camera() {
+        let mut world = World {
+            aabbs: vec![unit_box_at(0.0, 0.0, 0.0)],
+            ..Default::default()
+        };
+        let mut scene = Scene::default();
+
+        let camera_node_index = world.add_node();
+        world.add_camera_to_node(camera_node_index);
+        world.transforms[camera_node_index] = Transform {
+            translation: nalgebra_glm::vec3(0.0, 0.0, 5.0),
+            ..Default::default()
+        };
+        scene.default_camera_graph_node_index = Some(scene.graph.add_node(camera_node_index));
+
+        let graph_node_indices = [0.0, 20.0, -30.0]
+            .iter()
+            .map(|x| {
+                let node_index = world.add_node();
+                world.nodes[node_index].mesh_index = Some(0);
+                world.nodes[node_index].aabb_index = Some(0);
+                world.transforms[node_index].translation = nalgebra_glm::vec3(*x, 0.0, 0.0);
+                scene.graph.add_node(node_index)
+            })
+            .collect::<Vec<_>>();
+        world.scenes.push(scene);
+
+        assert_eq!(world.visible_nodes(0, 1.0), vec![graph_node_indices[0]]);
+    }
+}
@@ -0,0 +1,541 @@
+use crate::world::{Camera, PrimitiveMesh, SceneGraph, Transform, World};
+use contract::WorldChange;
+
+/// A reversible edit to the world.
+///
+/// Commands refer to nodes by their index in `World::nodes`, which never changes,
+/// rather than by graph node index, since a removed node that is restored gets a new graph node.
+#[derive(Debug, Clone)]
+pub enum WorldCommand {
+    /// Adds a new node to a scene, as a root when no parent is given
+    AddNode {
+        scene_index: usize,
+        parent_node_index: Option<usize>,
+        name: String,
+    },
+    /// Removes a node and its descendants from a scene's graph
+    RemoveNode {
+        scene_index: usize,
+        node_index: usize,
+    },
+    SetTransform {
+        node_index: usize,
+        transform: Transform,
+    },
+    SetMaterial {
+        mesh_index: usize,
+        primitive_index: usize,
+        material_index: Option<usize>,
+    },
+    AttachCamera {
+        node_index: usize,
+        camera: Camera,
+    },
+    AttachPrimitiveMesh {
+        node_index: usize,
+        primitive_mesh: PrimitiveMesh,
+    },
+}
+
+/// The minimal state change a command boils down to once any new data it needs has been appended.
+/// Applying an edit yields the edit that reverts it.
+#[derive(Debug, Clone)]
+enum Edit {
+    InsertNodes {
+        scene_index: usize,
+        // (node index, parent node index) in depth first order, so parents are inserted first
+        nodes: Vec<(usize, Option<usize>)>,
+        // The inserted node that becomes the scene's default camera again
+        default_camera_node_index: Option<usize>,
+    },
+    RemoveNodes {
+        scene_index: usize,
+        node_index: usize,
+    },
+    SetTransform {
+        node_index: usize,
+        transform: Transform,
+    },
+    SetMaterial {
+        mesh_index: usize,
+        primitive_index: usize,
+        material_index: Option<usize>,
+    },
+    SetCamera {
+        node_index: usize,
+        camera_index: Option<usize>,
+    },
+    SetPrimitiveMesh {
+        node_index: usize,
+        primitive_mesh_index: Option<usize>,
+    },
+}
+
+impl Edit {
+    fn apply(self, world: &mut World) -> (Edit, WorldChange) {
+        match self {
+            Edit::InsertNodes {
+                scene_index,
+                nodes,
+                default_camera_node_index,
+            } => {
+                let scene = &mut world.scenes[scene_index];
+                let graph = &mut scene.graph;
+                nodes.iter().for_each(|(node_index, parent_node_index)| {
+                    let graph_node_index = graph.add_node(*node_index);
+                    if let Some(parent_graph_node_index) = parent_node_index
+                        .and_then(|parent_node_index| find_graph_node(graph, parent_node_index))
+                    {
+                        graph.add_edge(parent_graph_node_index, graph_node_index, ());
+                    }
+                    if default_camera_node_index == Some(*node_index) {
+                        scene.default_camera_graph_node_index = Some(graph_node_index);
+                    }
+                });
+                let node_index = nodes[0].0;
+                (
+                    Edit::RemoveNodes {
+                        scene_index,
+                        node_index,
+                    },
+                    WorldChange::NodeAdded {
+                        scene_index,
+                        node_index,
+                    },
+                )
+            }
+            Edit::RemoveNodes {
+                scene_index,
+                node_index,
+            } => {
+                let scene = &mut world.scenes[scene_index];
+                let default_camera_graph_node_index = scene.default_camera_graph_node_index;
+                let graph = &mut scene.graph;
+                let mut nodes = Vec::new();
+                let mut default_camera_node_index = None;
+                if let Some(graph_node_index) = find_graph_node(graph, node_index) {
+                    let mut dfs = petgraph::visit::Dfs::new(&*graph, graph_node_index);
+                    while let Some(graph_node_index) = dfs.next(&*graph) {
+                        let parent_node_index = graph
+                            .neighbors_directed(graph_node_index, petgraph::Direction::Incoming)
+                            .next()
+                            .map(|parent_graph_node_index| graph[parent_graph_node_index]);
+                        nodes.push((graph[graph_node_index], parent_node_index));
+                        if Some(graph_node_index) == default_camera_graph_node_index {
+                            default_camera_node_index = Some(graph[graph_node_index]);
+                        }
+                    }
+                }
+                nodes.iter().for_each(|(node_index, _)| {
+                    if let Some(graph_node_index) = find_graph_node(graph, *node_index) {
+                        graph.remove_node(graph_node_index);
+                    }
+                });
+                if default_camera_node_index.is_some() {
+                    scene.default_camera_graph_node_index = None;
+                }
+                let inverse = if nodes.is_empty() {
+                    // The node was not in the scene, so there is nothing to put back
+                    Edit::RemoveNodes {
+                        scene_index,
+                        node_index,
+                    }
+                } else {
+                    Edit::InsertNodes {
+                        scene_index,
+                        nodes,
+                        default_camera_node_index,
+                    }
+                };
+                (
+                    inverse,
+                    WorldChange::NodeRemoved {
+                        scene_index,
+                        node_index,
+                    },
+                )
+            }
+            Edit::SetTransform {
+                node_index,
+                transform,
+            } => {
+                let transform_index = world.nodes[node_index].transform_index;
+                let previous = std::mem::replace(&mut world.transforms[transform_index], transform);
+                (
+                    Edit::SetTransform {
+                        node_index,
+                        transform: previous,
+                    },
+                    WorldChange::TransformChanged { node_index },
+                )
+            }
+            Edit::SetMaterial {
+                mesh_index,
+                primitive_index,
+                material_index,
+            } => {
+                let primitive = &mut world.meshes[mesh_index].primitives[primitive_index];
+                let previous = std::mem::replace(&mut primitive.material_index, material_index);
+                (
+                    Edit::SetMaterial {
+                        mesh_index,
+                        primitive_index,
+                        material_index: previous,
+                    },
+                    WorldChange::MaterialChanged {
+                        mesh_index,
+                        primitive_index,
+                    },
+                )
+            }
+            Edit::SetCamera {
+                node_index,
+                camera_index,
+            } => {
+                let previous =
+                    std::mem::replace(&mut world.nodes[node_index].camera_index, camera_index);
+                (
+                    Edit::SetCamera {
+                        node_index,
+                        camera_index: previous,
+                    },
+                    WorldChange::CameraChanged { node_index },
+                )
+            }
+            Edit::SetPrimitiveMesh {
+                node_index,
+                primitive_mesh_index,
+            } => {
+                let previous = std::mem::replace(
+                    &mut world.nodes[node_index].primitive_mesh_index,
+                    primitive_mesh_index,
+                );
+                (
+                    Edit::SetPrimitiveMesh {
+                        node_index,
+                        primitive_mesh_index: previous,
+                    },
+                    WorldChange::PrimitiveMeshChanged { node_index },
+                )
+            }
+        }
+    }
+}
+
+/// Undo and redo stacks for world edits.
+///
+/// Executing, undoing and redoing return the change that was made,
+/// which can be published with `ServiceBus::publish_engine_event(change.into())`.
+#[derive(Default, Debug, Clone)]
+pub struct CommandHistory {
+    undo_stack: Vec<Edit>,
+    redo_stack: Vec<Edit>,
+    // The merge id of the command on top of the undo stack, when it was executed merged
+    merge_id: Option<u64>,
+}
+
+impl CommandHistory {
+    pub fn execute(&mut self, world: &mut World, command: WorldCommand) -> WorldChange {
+        let (inverse, change) = Self::edit(world, command).apply(world);
+        self.undo_stack.push(inverse);
+        self.redo_stack.clear();
+        self.merge_id = None;
+        change
+    }
+
+    /// Executes a command, folding it into the previous one when both were executed
+    /// with the same merge id and set the same node's transform.
+    /// Giving every edit of a continuous drag the same id, and each drag a new one,
+    /// undoes a drag in a single step.
+    pub fn execute_merged(
+        &mut self,
+        world: &mut World,
+        command: WorldCommand,
+        merge_id: u64,
+    ) -> WorldChange {
+        let mergeable = match (&command, self.undo_stack.last()) {
+            (
+                WorldCommand::SetTransform { node_index, .. },
+                Some(Edit::SetTransform {
+                    node_index: previous_node_index,
+                    ..
+                }),
+            ) => node_index == previous_node_index && self.merge_id == Some(merge_id),
+            _ => false,
+        };
+        if !mergeable {
+            let change = self.execute(world, command);
+            self.merge_id = Some(merge_id);
+            return change;
+        }
+        // The original transform is already on the undo stack, so the new inverse is dropped
+        let (_, change) = Self::edit(world, command).apply(world);
+        self.redo_stack.clear();
+        change
+    }
+
+    pub fn undo(&mut self, world: &mut World) -> Option<WorldChange> {
+        self.merge_id = None;
+        let (inverse, change) = self.undo_stack.pop()?.apply(world);
+        self.redo_stack.push(inverse);
+        Some(change)
+    }
+
+    pub fn redo(&mut self, world: &mut World) -> Option<WorldChange> {
+        self.merge_id = None;
+        let (inverse, change) = self.redo_stack.pop()?.apply(world);
+        self.undo_stack.push(inverse);
+        Some(change)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.merge_id = None;
+    }
+
+    /// Appends any data the command creates, which stays in the world when it is undone,
+    /// and returns the edit that links it in
+    fn edit(world: &mut World, command: WorldCommand) -> Edit {
+        match command {
+            WorldCommand::AddNode {
+                scene_index,
+                parent_node_index,
+                name,
+            } => {
+                let node_index = world.add_node();
+                world.metadata[world.nodes[node_index].metadata_index].name = name;
+                Edit::InsertNodes {
+                    scene_index,
+                    nodes: vec![(node_index, parent_node_index)],
+                    default_camera_node_index: None,
+                }
+            }
+            WorldCommand::RemoveNode {
+                scene_index,
+                node_index,
+            } => Edit::RemoveNodes {
+                scene_index,
+                node_index,
+            },
+            WorldCommand::SetTransform {
+                node_index,
+                transform,
+            } => Edit::SetTransform {
+                node_index,
+                transform,
+            },
+            WorldCommand::SetMaterial {
+                mesh_index,
+                primitive_index,
+                material_index,
+            } => Edit::SetMaterial {
+                mesh_index,
+                primitive_index,
+                material_index,
+            },
+            WorldCommand::AttachCamera { node_index, camera } => {
+                world.cameras.push(camera);
+                Edit::SetCamera {
+                    node_index,
+                    camera_index: Some(world.cameras.len() - 1),
+                }
+            }
+            WorldCommand::AttachPrimitiveMesh {
+                node_index,
+                primitive_mesh,
+            } => {
+                world.primitive_meshes.push(primitive_mesh);
+                Edit::SetPrimitiveMesh {
+                    node_index,
+                    primitive_mesh_index: Some(world.primitive_meshes.len() - 1),
+                }
+            }
+        }
+    }
+}
+
+fn find_graph_node(graph: &SceneGraph, node_index: usize) -> Option<petgraph::graph::NodeIndex> {
+    graph
+        .node_indices()
+        .find(|graph_node_index| graph[*graph_node_index] == node_index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CommandHistory, WorldCommand};
+    use crate::world::{Scene, Transform, World};
+    use contract::WorldChange;
+
+    fn create_world() -> World {
+        let mut world = World::default();
+        let root_node_index = world.add_node();
+        let mut scene = Scene::default();
+        scene.graph.add_node(root_node_index);
+        world.scenes.push(scene);
+        world
+    }
+
+    fn add_node(history: &mut CommandHistory, world: &mut World, parent: usize) -> usize {
+        history.execute(
+            world,
+            WorldCommand::AddNode {
+                scene_index: 0,
+                parent_node_index: Some(parent),
+                name: "Child".to_string(),
+            },
+        );
+        world.nodes.len() - 1
+    }
+
+    #[test]
+    fn removed_subtrees_are_restored() {
+        let mut world = create_world();
+        let mut history = CommandHistory::default();
+        let child = add_node(&mut history, &mut world, 0);
+        let grandchild = add_node(&mut history, &mut world, child);
+        assert_eq!(world.scenes[0].graph.edge_count(), 2);
+
+        let change = history.execute(
+            &mut world,
+            WorldCommand::RemoveNode {
+                scene_index: 0,
+                node_index: child,
+            },
+        );
+        assert_eq!(
+            change,
+            WorldChange::NodeRemoved {
+                scene_index: 0,
+                node_index: child,
+            }
+        );
+        assert_eq!(world.scenes[0].graph.node_count(), 1);
+
+        history.undo(&mut world);
+        let graph = &world.scenes[0].graph;
+        assert_eq!(graph.node_count(), 3);
+        let parent_of = |node_index| {
+            let graph_node_index = super::find_graph_node(graph, node_index).unwrap();
+            graph
+                .neighbors_directed(graph_node_index, petgraph::Direction::Incoming)
+                .next()
+                .map(|parent| graph[parent])
+        };
+        assert_eq!(parent_of(grandchild), Some(child));
+        assert_eq!(parent_of(child), Some(0));
+
+        // Undoing the additions and redoing them reuses the same nodes
+        history.undo(&mut world);
+        history.undo(&mut world);
+        assert_eq!(world.scenes[0].graph.node_count(), 1);
+        assert!(!history.can_undo());
+        history.redo(&mut world);
+        history.redo(&mut world);
+        assert_eq!(world.scenes[0].graph.node_count(), 3);
+        assert_eq!(world.nodes.len(), 3);
+    }
+
+    #[test]
+    fn merged_transforms_undo_in_one_step() {
+        let mut world = create_world();
+        let mut history = CommandHistory::default();
+        let drag = |history: &mut CommandHistory, world: &mut World, merge_id, range| {
+            for x in range {
+                history.execute_merged(
+                    world,
+                    WorldCommand::SetTransform {
+                        node_index: 0,
+                        transform: Transform {
+                            translation: nalgebra_glm::vec3(x as f32, 0.0, 0.0),
+                            ..Default::default()
+                        },
+                    },
+                    merge_id,
+                );
+            }
+        };
+        drag(&mut history, &mut world, 1, 1..=10);
+        assert_eq!(world.transforms[0].translation.x, 10.0);
+
+        // A second drag of the same node is its own step
+        drag(&mut history, &mut world, 2, 11..=20);
+        history.undo(&mut world);
+        assert_eq!(world.transforms[0].translation.x, 10.0);
+
+        // Undoing ends the drag, so reusing its id starts a new step
+        drag(&mut history, &mut world, 2, 21..=30);
+        history.undo(&mut world);
+        assert_eq!(world.transforms[0].translation.x, 10.0);
+
+        history.undo(&mut world);
+        assert_eq!(world.transforms[0].translation.x, 0.0);
+        assert!(!history.can_undo());
+
+        history.redo(&mut world);
+        assert_eq!(world.transforms[0].translation.x, 10.0);
+    }
+
+    #[test]
+    fn changes_are_published_on_the_bus() {
+        use service::{client::Client, Broker, Service, ServiceBus};
+        use std::{cell::RefCell, rc::Rc};
+
+        #[derive(Default)]
+        struct ChangeListener {
+            client: Client<(), ()>,
+            subscribed: bool,
+            changes: Rc<RefCell<Vec<WorldChange>>>,
+        }
+
+        impl Service<(), ()> for ChangeListener {
+            fn update(&mut self, broker: &mut Broker<(), ()>) {
+                if !self.subscribed {
+                    self.client
+                        .subscribe_to_topic(contract::ENGINE_EVENT_TOPIC, broker);
+                    self.subscribed = true;
+                }
+                while let Some(contract::EngineMessage::EngineEvent {
+                    event: contract::EngineEvent::WorldChanged { change },
+                }) = self.client.next_message()
+                {
+                    self.changes.borrow_mut().push(change);
+                }
+            }
+        }
+
+        let changes = Rc::new(RefCell::new(Vec::new()));
+        let mut bus = ServiceBus::<(), ()>::default();
+        bus.register_service(ChangeListener {
+            changes: changes.clone(),
+            ..Default::default()
+        });
+        bus.update();
+
+        let mut world = create_world();
+        let mut history = CommandHistory::default();
+        let change = history.execute(
+            &mut world,
+            WorldCommand::AttachCamera {
+                node_index: 0,
+                camera: Default::default(),
+            },
+        );
+        bus.publish_engine_event(change.into());
+        bus.update();
+
+        assert_eq!(
+            *changes.borrow(),
+            vec![WorldChange::CameraChanged { node_index: 0 }]
+        );
+        assert_eq!(world.nodes[0].camera_index, Some(0));
+    }
+}
@@ -0,0 +1,824 @@
+use crate::world::{Image, ImageFormat, MipLevel, World};
+use std::collections::HashSet;
+
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MipmapFilter {
+    /// Averages the texels that each smaller texel covers
+    #[default]
+    Box,
+    /// A Kaiser windowed sinc, which keeps smaller levels sharper than a box filter
+    Kaiser,
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum ImageProcessingError {
+    #[error("Images in the {0:?} format cannot be processed")]
+    UnsupportedFormat(ImageFormat),
+
+    #[error("The KTX2 container could not be read: {0}")]
+    Ktx2(String),
+
+    #[error("KTX2 containers with {0} are not supported")]
+    UnsupportedKtx2(&'static str),
+
+    #[error("The compressed copy is {width}x{height}, which doesn't match the image")]
+    MismatchedCompressedSize { width: u32, height: u32 },
+}
+
+/// The Kaiser filter reaches this many destination texels to each side of a texel
+const KAISER_RADIUS: f32 = 3.0;
+
+/// The Kaiser window shape, where higher values ring less but blur more
+const KAISER_ALPHA: f32 = 4.0;
+
+/// BC7 interpolation weights out of 64 for 4-bit indices
+const BC7_WEIGHTS: [u32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+impl Image {
+    /// The number of levels in a full mip chain down to 1x1, including the image itself
+    pub fn full_mip_level_count(&self) -> u32 {
+        u32::BITS - self.width.max(self.height).max(1).leading_zeros()
+    }
+
+    /// Replaces the mip levels with a full chain, each level filtered from the one before.
+    /// `srgb` marks the color channels as sRGB encoded.
+    pub fn generate_mipmaps(
+        &mut self,
+        filter: MipmapFilter,
+        srgb: bool,
+    ) -> Result<(), ImageProcessingError> {
+        let layout = ChannelLayout::of(self.format)
+            .ok_or(ImageProcessingError::UnsupportedFormat(self.format))?;
+        let (mut width, mut height) = (self.width as usize, self.height as usize);
+        let mut texels = layout.decode(&self.pixels, srgb);
+        self.mip_levels.clear();
+        while width > 1 || height > 1 {
+            let (next_width, next_height) = ((width / 2).max(1), (height / 2).max(1));
+            texels = resample(
+                &texels,
+                layout.channels,
+                (width, height),
+                (next_width, next_height),
+                filter,
+            );
+            self.mip_levels.push(MipLevel {
+                pixels: layout.encode(&texels, srgb),
+                width: next_width as u32,
+                height: next_height as u32,
+            });
+            (width, height) = (next_width, next_height);
+        }
+        Ok(())
+    }
+
+    /// Encodes a BC7 copy of the image and its mip levels into `compressed`, keeping alpha
+    /// and leaving the pixels as they are. Generate mip levels first so the copy has them too.
+    pub fn compress_bc7(&mut self) -> Result<(), ImageProcessingError> {
+        if !matches!(
+            self.format,
+            ImageFormat::R8G8B8
+                | ImageFormat::R8G8B8A8
+                | ImageFormat::B8G8R8
+                | ImageFormat::B8G8R8A8
+        ) {
+            return Err(ImageProcessingError::UnsupportedFormat(self.format));
+        }
+        let format = self.format;
+        self.compressed = Some(Box::new(Self {
+            pixels: encode_bc7(&self.pixels, format, self.width, self.height),
+            format: ImageFormat::BC7,
+            width: self.width,
+            height: self.height,
+            mip_levels: self
+                .mip_levels
+                .iter()
+                .map(|level| MipLevel {
+                    pixels: encode_bc7(&level.pixels, format, level.width, level.height),
+                    width: level.width,
+                    height: level.height,
+                })
+                .collect(),
+            compressed: None,
+        }));
+        Ok(())
+    }
+
+    /// Reads a single 2D image and its mip levels from a KTX2 container,
+    /// such as a texture compressed to BC7 by an offline encoder
+    pub fn from_ktx2(bytes: &[u8]) -> Result<Self, ImageProcessingError> {
+        let reader = ktx2::Reader::new(bytes)
+            .map_err(|error| ImageProcessingError::Ktx2(error.to_string()))?;
+        let header = reader.header();
+        if header.supercompression_scheme.is_some() {
+            return Err(ImageProcessingError::UnsupportedKtx2("supercompression"));
+        }
+        if header.pixel_depth > 1 || header.layer_count > 1 || header.face_count > 1 {
+            return Err(ImageProcessingError::UnsupportedKtx2(
+                "depth, array layers or cube faces",
+            ));
+        }
+        let format = header
+            .format
+            .and_then(ktx2_image_format)
+            .ok_or(ImageProcessingError::UnsupportedKtx2("this format"))?;
+        let (width, height) = (header.pixel_width, header.pixel_height.max(1));
+        let mut levels = reader.levels().map(|level| level.data.to_vec());
+        let pixels = levels.next().unwrap_or_default();
+        let mip_levels = levels
+            .enumerate()
+            .map(|(index, pixels)| MipLevel {
+                pixels,
+                width: (width >> (index + 1)).max(1),
+                height: (height >> (index + 1)).max(1),
+            })
+            .collect();
+        Ok(Self {
+            pixels,
+            format,
+            width,
+            height,
+            mip_levels,
+            compressed: None,
+        })
+    }
+
+    /// Keeps a block compressed copy of the image read from a KTX2 container,
+    /// which renderers sample instead of the pixels when the GPU supports its format
+    pub fn load_compressed_ktx2(&mut self, bytes: &[u8]) -> Result<(), ImageProcessingError> {
+        let compressed = Self::from_ktx2(bytes)?;
+        if (compressed.width, compressed.height) != (self.width, self.height) {
+            return Err(ImageProcessingError::MismatchedCompressedSize {
+                width: compressed.width,
+                height: compressed.height,
+            });
+        }
+        self.compressed = Some(Box::new(compressed));
+        Ok(())
+    }
+}
+
+fn ktx2_image_format(format: ktx2::Format) -> Option<ImageFormat> {
+    Some(match format {
+        ktx2::Format::R8_UNORM => ImageFormat::R8,
+        ktx2::Format::R8G8_UNORM => ImageFormat::R8G8,
+        ktx2::Format::R8G8B8A8_UNORM | ktx2::Format::R8G8B8A8_SRGB => ImageFormat::R8G8B8A8,
+        ktx2::Format::B8G8R8A8_UNORM | ktx2::Format::B8G8R8A8_SRGB => ImageFormat::B8G8R8A8,
+        ktx2::Format::R16_UNORM => ImageFormat::R16,
+        ktx2::Format::R16G16_UNORM => ImageFormat::R16G16,
+        ktx2::Format::R16G16B16A16_UNORM => ImageFormat::R16G16B16A16,
+        ktx2::Format::R16G16B16A16_SFLOAT => ImageFormat::R16G16B16A16F,
+        ktx2::Format::R32_SFLOAT => ImageFormat::R32F,
+        ktx2::Format::R32G32_SFLOAT => ImageFormat::R32G32F,
+        ktx2::Format::R32G32B32A32_SFLOAT => ImageFormat::R32G32B32A32F,
+        ktx2::Format::BC7_UNORM_BLOCK | ktx2::Format::BC7_SRGB_BLOCK => ImageFormat::BC7,
+        _ => return None,
+    })
+}
+
+impl World {
+    /// The images that materials sample as sRGB color rather than as linear data
+    pub fn color_image_indices(&self) -> HashSet<usize> {
+        self.materials
+            .iter()
+            .flat_map(|material| {
+                [
+                    &material.base_color_texture,
+                    &material.emissive_texture,
+                    material
+                        .specular
+                        .as_ref()
+                        .map_or(&None, |specular| &specular.color_texture),
+                ]
+            })
+            .flatten()
+            .filter_map(|info| self.textures.get(info.texture_index))
+            .map(|texture| texture.image_index)
+            .collect()
+    }
+
+    /// Generates a full mip chain for every image,
+    /// treating the images that materials sample as color as sRGB
+    pub fn generate_mipmaps(&mut self, filter: MipmapFilter) -> Result<(), ImageProcessingError> {
+        let color_image_indices = self.color_image_indices();
+        self.images
+            .iter_mut()
+            .enumerate()
+            .try_for_each(|(image_index, image)| {
+                image.generate_mipmaps(filter, color_image_indices.contains(&image_index))
+            })
+    }
+}
+
+#[derive(Copy, Clone)]
+enum ChannelKind {
+    Unorm8,
+    Unorm16,
+    Float32,
+}
+
+/// How the channels of a texel are stored
+#[derive(Copy, Clone)]
+struct ChannelLayout {
+    channels: usize,
+    kind: ChannelKind,
+}
+
+impl ChannelLayout {
+    fn of(format: ImageFormat) -> Option<Self> {
+        let (channels, kind) = match format {
+            ImageFormat::R8 => (1, ChannelKind::Unorm8),
+            ImageFormat::R8G8 => (2, ChannelKind::Unorm8),
+            ImageFormat::R8G8B8 | ImageFormat::B8G8R8 => (3, ChannelKind::Unorm8),
+            ImageFormat::R8G8B8A8 | ImageFormat::B8G8R8A8 => (4, ChannelKind::Unorm8),
+            ImageFormat::R16 => (1, ChannelKind::Unorm16),
+            ImageFormat::R16G16 => (2, ChannelKind::Unorm16),
+            ImageFormat::R16G16B16 => (3, ChannelKind::Unorm16),
+            ImageFormat::R16G16B16A16 => (4, ChannelKind::Unorm16),
+            ImageFormat::R32F => (1, ChannelKind::Float32),
+            ImageFormat::R32G32F => (2, ChannelKind::Float32),
+            ImageFormat::R32G32B32F => (3, ChannelKind::Float32),
+            ImageFormat::R32G32B32A32F => (4, ChannelKind::Float32),
+            _ => return None,
+        };
+        Some(Self { channels, kind })
+    }
+
+    /// The last channel of two and four channel images is alpha, as in luma with alpha and RGBA
+    fn is_color(&self, channel: usize) -> bool {
+        match self.channels {
+            2 => channel == 0,
+            _ => channel < 3,
+        }
+    }
+
+    fn decode(&self, pixels: &[u8], srgb: bool) -> Vec<f32> {
+        let values: Vec<f32> = match self.kind {
+            ChannelKind::Unorm8 => pixels.iter().map(|value| *value as f32 / 255.0).collect(),
+            ChannelKind::Unorm16 => pixels
+                .chunks_exact(2)
+                .map(|value| u16::from_ne_bytes([value[0], value[1]]) as f32 / 65535.0)
+                .collect(),
+            ChannelKind::Float32 => pixels
+                .chunks_exact(4)
+                .map(|value| f32::from_ne_bytes([value[0], value[1], value[2], value[3]]))
+                .collect(),
+        };
+        if !srgb {
+            return values;
+        }
+        values
+            .into_iter()
+            .enumerate()
+            .map(|(index, value)| {
+                if self.is_color(index % self.channels) {
+                    srgb_to_linear(value)
+                } else {
+                    value
+                }
+            })
+            .collect()
+    }
+
+    fn encode(&self, values: &[f32], srgb: bool) -> Vec<u8> {
+        let values = values.iter().enumerate().map(|(index, value)| {
+            if srgb && self.is_color(index % self.channels) {
+                linear_to_srgb(*value)
+            } else {
+                *value
+            }
+        });
+        match self.kind {
+            ChannelKind::Unorm8 => values
+                .map(|value| (value.clamp(0.0, 1.0) * 255.0).round() as u8)
+                .collect(),
+            ChannelKind::Unorm16 => values
+                .flat_map(|value| ((value.clamp(0.0, 1.0) * 65535.0).round() as u16).to_ne_bytes())
+                .collect(),
+            ChannelKind::Float32 => values.flat_map(f32::to_ne_bytes).collect(),
+        }
+    }
+}
+
+fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.max(0.0).powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Resizes interleaved texels with a separable filter, clamping at the edges
+fn resample(
+    texels: &[f32],
+    channels: usize,
+    (width, height): (usize, usize),
+    (new_width, new_height): (usize, usize),
+    filter: MipmapFilter,
+) -> Vec<f32> {
+    let columns = filter_weights(width, new_width, filter);
+    let mut horizontal = vec![0.0; new_width * height * channels];
+    for y in 0..height {
+        for (x, weights) in columns.iter().enumerate() {
+            let destination = (y * new_width + x) * channels;
+            for (source_x, weight) in weights {
+                let source = (y * width + source_x) * channels;
+                for channel in 0..channels {
+                    horizontal[destination + channel] += texels[source + channel] * weight;
+                }
+            }
+        }
+    }
+
+    let rows = filter_weights(height, new_height, filter);
+    let mut resampled = vec![0.0; new_width * new_height * channels];
+    for (y, weights) in rows.iter().enumerate() {
+        for x in 0..new_width {
+            let destination = (y * new_width + x) * channels;
+            for (source_y, weight) in weights {
+                let source = (source_y * new_width + x) * channels;
+                for channel in 0..channels {
+                    resampled[destination + channel] += horizontal[source + channel] * weight;
+                }
+            }
+        }
+    }
+    resampled
+}
+
+/// The normalized source texel weights of every destination texel along one axis
+fn filter_weights(
+    length: usize,
+    new_length: usize,
+    filter: MipmapFilter,
+) -> Vec<Vec<(usize, f32)>> {
+    let scale = length as f32 / new_length as f32;
+    (0..new_length)
+        .map(|index| {
+            let mut weights = Vec::new();
+            match filter {
+                MipmapFilter::Box => {
+                    let (start, end) = (index as f32 * scale, (index + 1) as f32 * scale);
+                    for source in start.floor() as usize..(end.ceil() as usize).min(length) {
+                        let coverage = end.min(source as f32 + 1.0) - start.max(source as f32);
+                        if coverage > 0.0 {
+                            weights.push((source, coverage));
+                        }
+                    }
+                }
+                MipmapFilter::Kaiser => {
+                    let center = (index as f32 + 0.5) * scale;
+                    let reach = KAISER_RADIUS * scale;
+                    let first = (center - reach).floor() as isize;
+                    let last = (center + reach).ceil() as isize;
+                    for source in first..=last {
+                        let offset = (source as f32 + 0.5 - center) / scale;
+                        if offset.abs() >= KAISER_RADIUS {
+                            continue;
+                        }
+                        let weight = sinc(offset) * kaiser_window(offset / KAISER_RADIUS);
+                        let source = source.clamp(0, length as isize - 1) as usize;
+                        match weights.iter_mut().find(|(index, _)| *index == source) {
+                            Some((_, total)) => *total += weight,
+                            None => weights.push((source, weight)),
+                        }
+                    }
+                }
+            }
+            let total = weights.iter().map(|(_, weight)| weight).sum::<f32>();
+            weights.iter_mut().for_each(|(_, weight)| *weight /= total);
+            weights
+        })
+        .collect()
+}
+
+fn sinc(x: f32) -> f32 {
+    if x.abs() < 1e-6 {
+        return 1.0;
+    }
+    let x = x * std::f32::consts::PI;
+    x.sin() / x
+}
+
+/// Kaiser window over -1..1
+fn kaiser_window(x: f32) -> f32 {
+    bessel_i0(KAISER_ALPHA * (1.0 - x * x).max(0.0).sqrt()) / bessel_i0(KAISER_ALPHA)
+}
+
+/// The zeroth order modified Bessel function of the first kind
+fn bessel_i0(x: f32) -> f32 {
+    let (mut sum, mut term, mut k) = (1.0, 1.0, 1.0);
+    while term > sum * 1e-8 {
+        term *= (x * 0.5 / k) * (x * 0.5 / k);
+        sum += term;
+        k += 1.0;
+    }
+    sum
+}
+
+/// Reads a texel of an 8-bit image as RGBA
+fn rgba8(pixels: &[u8], format: ImageFormat, texel: usize) -> [u8; 4] {
+    match format {
+        ImageFormat::R8G8B8 => {
+            let p = &pixels[texel * 3..];
+            [p[0], p[1], p[2], 255]
+        }
+        ImageFormat::B8G8R8 => {
+            let p = &pixels[texel * 3..];
+            [p[2], p[1], p[0], 255]
+        }
+        ImageFormat::B8G8R8A8 => {
+            let p = &pixels[texel * 4..];
+            [p[2], p[1], p[0], p[3]]
+        }
+        _ => {
+            let p = &pixels[texel * 4..];
+            [p[0], p[1], p[2], p[3]]
+        }
+    }
+}
+
+/// Compresses an 8-bit image to BC7, where partial blocks at the edges repeat the last texels
+fn encode_bc7(pixels: &[u8], format: ImageFormat, width: u32, height: u32) -> Vec<u8> {
+    let (width, height) = (width as usize, height as usize);
+    let (blocks_x, blocks_y) = (width.div_ceil(4), height.div_ceil(4));
+    let mut compressed = Vec::with_capacity(blocks_x * blocks_y * 16);
+    for block_y in 0..blocks_y {
+        for block_x in 0..blocks_x {
+            let mut block = [[0u8; 4]; 16];
+            block.iter_mut().enumerate().for_each(|(index, texel)| {
+                let x = (block_x * 4 + index % 4).min(width - 1);
+                let y = (block_y * 4 + index / 4).min(height - 1);
+                *texel = rgba8(pixels, format, y * width + x);
+            });
+            compressed.extend_from_slice(&encode_bc7_block(&block));
+        }
+    }
+    compressed
+}
+
+/// Encodes a block in BC7 mode 6, which has a single pair of RGBA endpoints and
+/// 16 interpolation steps. The endpoints span the block's principal axis.
+fn encode_bc7_block(block: &[[u8; 4]; 16]) -> [u8; 16] {
+    let texels = block.map(|texel| texel.map(|channel| channel as f32));
+    let mean = texels.iter().fold([0.0f32; 4], |sum, texel| {
+        std::array::from_fn(|c| sum[c] + texel[c] / 16.0)
+    });
+
+    let mut covariance = [[0.0f32; 4]; 4];
+    texels.iter().for_each(|texel| {
+        for row in 0..4 {
+            for column in 0..4 {
+                covariance[row][column] +=
+                    (texel[row] - mean[row]) * (texel[column] - mean[column]);
+            }
+        }
+    });
+    // Power iteration, starting along the diagonal so that gray ramps converge immediately
+    let mut axis = [1.0f32; 4];
+    for _ in 0..8 {
+        let next: [f32; 4] = std::array::from_fn(|row| {
+            (0..4)
+                .map(|column| covariance[row][column] * axis[column])
+                .sum()
+        });
+        let length = next.iter().map(|value| value * value).sum::<f32>().sqrt();
+        if length < 1e-6 {
+            break;
+        }
+        axis = next.map(|value| value / length);
+    }
+
+    let projections =
+        texels.map(|texel| (0..4).map(|c| (texel[c] - mean[c]) * axis[c]).sum::<f32>());
+    let (low, high) = projections
+        .iter()
+        .fold((f32::MAX, f32::MIN), |(low, high), t| {
+            (low.min(*t), high.max(*t))
+        });
+    let endpoint =
+        |t: f32| -> [f32; 4] { std::array::from_fn(|c| (mean[c] + axis[c] * t).clamp(0.0, 255.0)) };
+    let (mut endpoints, mut p_bits) = ([[0u8; 4]; 2], [0u8; 2]);
+    for (index, target) in [endpoint(low), endpoint(high)].into_iter().enumerate() {
+        (endpoints[index], p_bits[index]) = quantize_endpoint(&target);
+    }
+
+    let color = |endpoints: &[[u8; 4]; 2], p_bits: &[u8; 2], weight: u32| -> [u8; 4] {
+        std::array::from_fn(|c| {
+            let e0 = ((endpoints[0][c] as u32) << 1) | p_bits[0] as u32;
+            let e1 = ((endpoints[1][c] as u32) << 1) | p_bits[1] as u32;
+            (((64 - weight) * e0 + weight * e1 + 32) >> 6) as u8
+        })
+    };
+    let palette = BC7_WEIGHTS.map(|weight| color(&endpoints, &p_bits, weight));
+    let mut indices = block.map(|texel| {
+        (0..16)
+            .min_by_key(|index| {
+                palette[*index]
+                    .iter()
+                    .zip(texel)
+                    .map(|(a, b)| (*a as i32 - b as i32).pow(2))
+                    .sum::<i32>()
+            })
+            .unwrap_or_default() as u8
+    });
+
+    // The first index is stored without its top bit, so it must be below 8
+    if indices[0] >= 8 {
+        endpoints.swap(0, 1);
+        p_bits.swap(0, 1);
+        indices.iter_mut().for_each(|index| *index = 15 - *index);
+    }
+
+    let mut bits = BitWriter::default();
+    bits.write(1 << 6, 7);
+    for channel in 0..4 {
+        bits.write(endpoints[0][channel] as u128, 7);
+        bits.write(endpoints[1][channel] as u128, 7);
+    }
+    bits.write(p_bits[0] as u128, 1);
+    bits.write(p_bits[1] as u128, 1);
+    bits.write(indices[0] as u128, 3);
+    indices[1..]
+        .iter()
+        .for_each(|index| bits.write(*index as u128, 4));
+    bits.value.to_le_bytes()
+}
+
+/// Picks the 7-bit endpoint and shared low bit that best reproduce an 8-bit color
+fn quantize_endpoint(target: &[f32; 4]) -> ([u8; 4], u8) {
+    (0..2u8)
+        .map(|p_bit| {
+            let endpoint =
+                target.map(|value| ((value - p_bit as f32) / 2.0).round().clamp(0.0, 127.0) as u8);
+            let error = endpoint
+                .iter()
+                .zip(target)
+                .map(|(quantized, value)| (((*quantized << 1) | p_bit) as f32 - value).powi(2))
+                .sum::<f32>();
+            (endpoint, p_bit, error)
+        })
+        .min_by(|a, b| a.2.total_cmp(&b.2))
+        .map(|(endpoint, p_bit, _)| (endpoint, p_bit))
+        .unwrap_or_default()
+}
+
+/// Packs fields from the least significant bit up, as BC blocks are laid out
+#[derive(Default)]
+struct BitWriter {
+    value: u128,
+    position: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u128, bits: u32) {
+        self.value |= value << self.position;
+        self.position += bits;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encode_bc7_block, ImageProcessingError, MipmapFilter, BC7_WEIGHTS};
+    use crate::world::{Image, ImageFormat};
+
+    fn rgba_image(width: u32, height: u32, pixels: Vec<u8>) -> Image {
+        Image {
+            pixels,
+            format: ImageFormat::R8G8B8A8,
+            width,
+            height,
+            mip_levels: Vec::new(),
+            compressed: None,
+        }
+    }
+
+    /// A 2D KTX2 container holding the given mip levels, largest first
+    fn ktx2_file(format: ktx2::Format, width: u32, height: u32, levels: &[Vec<u8>]) -> Vec<u8> {
+        let index_length = levels.len() * ktx2::LevelIndex::LENGTH;
+        // The data format descriptor is left empty, holding only its own length
+        let dfd_byte_offset = (ktx2::Header::LENGTH + index_length) as u32;
+        let mut level_offset = dfd_byte_offset as u64 + 4;
+        let level_index = levels.iter().flat_map(|level| {
+            let entry = ktx2::LevelIndex {
+                byte_offset: level_offset,
+                byte_length: level.len() as u64,
+                uncompressed_byte_length: level.len() as u64,
+            };
+            level_offset += level.len() as u64;
+            entry.as_bytes()
+        });
+        let header = ktx2::Header {
+            format: Some(format),
+            type_size: 1,
+            pixel_width: width,
+            pixel_height: height,
+            pixel_depth: 0,
+            layer_count: 0,
+            face_count: 1,
+            level_count: levels.len() as u32,
+            supercompression_scheme: None,
+            index: ktx2::Index {
+                dfd_byte_offset,
+                dfd_byte_length: 4,
+                kvd_byte_offset: 0,
+                kvd_byte_length: 0,
+                sgd_byte_offset: 0,
+                sgd_byte_length: 0,
+            },
+        };
+        header
+            .as_bytes()
+            .into_iter()
+            .chain(level_index.collect::<Vec<_>>())
+            .chain(4_u32.to_le_bytes())
+            .chain(levels.concat())
+            .collect()
+    }
+
+    /// Decodes a BC7 mode 6 block
+    fn decode_bc7_block(block: [u8; 16]) -> [[u8; 4]; 16] {
+        let bits = u128::from_le_bytes(block);
+        let field = |start: u32, length: u32| ((bits >> start) & ((1 << length) - 1)) as u32;
+        assert_eq!(field(0, 7), 1 << 6, "Only mode 6 is written");
+        let p_bits = [field(63, 1), field(64, 1)];
+        let endpoint = |index: usize, channel: u32| {
+            (field(7 + channel * 14 + index as u32 * 7, 7) << 1) | p_bits[index]
+        };
+        std::array::from_fn(|texel| {
+            let index = match texel {
+                0 => field(65, 3),
+                _ => field(68 + (texel as u32 - 1) * 4, 4),
+            };
+            let weight = BC7_WEIGHTS[index as usize];
+            std::array::from_fn(|channel| {
+                let (e0, e1) = (endpoint(0, channel as u32), endpoint(1, channel as u32));
+                (((64 - weight) * e0 + weight * e1 + 32) >> 6) as u8
+            })
+        })
+    }
+
+    #[test]
+    fn box_mipmaps_average_down_to_one_texel() {
+        let mut image = rgba_image(
+            4,
+            2,
+            [
+                [0, 0, 0, 255],
+                [255, 0, 0, 255],
+                [0, 255, 0, 255],
+                [0, 255, 0, 255],
+                [255, 0, 0, 255],
+                [0, 0, 0, 255],
+                [0, 255, 0, 0],
+                [0, 255, 0, 0],
+            ]
+            .concat(),
+        );
+        image.generate_mipmaps(MipmapFilter::Box, false).unwrap();
+        assert_eq!(image.full_mip_level_count(), 3);
+        let sizes = image
+            .mip_levels
+            .iter()
+            .map(|level| (level.width, level.height))
+            .collect::<Vec<_>>();
+        assert_eq!(sizes, [(2, 1), (1, 1)]);
+        assert_eq!(image.mip_levels[0].pixels, [128, 0, 0, 255, 0, 255, 0, 128]);
+        assert_eq!(image.mip_levels[1].pixels, [64, 128, 0, 191]);
+    }
+
+    #[test]
+    fn srgb_mipmaps_average_in_linear_space() {
+        let pixels = [[0, 0, 0, 0], [255, 255, 255, 255]].concat();
+        let mut srgb = rgba_image(2, 1, pixels.clone());
+        srgb.generate_mipmaps(MipmapFilter::Box, true).unwrap();
+        assert_eq!(srgb.mip_levels[0].pixels, [188, 188, 188, 128]);
+
+        let mut linear = rgba_image(2, 1, pixels);
+        linear.generate_mipmaps(MipmapFilter::Box, false).unwrap();
+        assert_eq!(linear.mip_levels[0].pixels, [128, 128, 128, 128]);
+    }
+
+    #[test]
+    fn kaiser_mipmaps_keep_flat_images_flat() {
+        let mut image = Image {
+            pixels: [0.25f32, 0.5]
+                .repeat(8 * 8)
+                .into_iter()
+                .flat_map(f32::to_ne_bytes)
+                .collect(),
+            format: ImageFormat::R32G32F,
+            width: 8,
+            height: 8,
+            mip_levels: Vec::new(),
+            compressed: None,
+        };
+        image.generate_mipmaps(MipmapFilter::Kaiser, false).unwrap();
+        assert_eq!(image.mip_levels.len(), 3);
+        image.mip_levels.iter().for_each(|level| {
+            level.pixels.chunks_exact(8).for_each(|texel| {
+                let red = f32::from_ne_bytes(texel[..4].try_into().unwrap());
+                let green = f32::from_ne_bytes(texel[4..].try_into().unwrap());
+                assert!((red - 0.25).abs() < 1e-5 && (green - 0.5).abs() < 1e-5);
+            });
+        });
+    }
+
+    #[test]
+    fn two_channel_images_keep_alpha_linear() {
+        let mut image = Image {
+            format: ImageFormat::R8G8,
+            ..rgba_image(2, 1, vec![0, 0, 255, 255])
+        };
+        image.generate_mipmaps(MipmapFilter::Box, true).unwrap();
+        assert_eq!(image.mip_levels[0].pixels, [188, 128]);
+    }
+
+    #[test]
+    fn ktx2_bc7_images_keep_their_mip_levels() {
+        let levels = [vec![1; 32], vec![2; 16], vec![3; 16], vec![4; 16]];
+        let bytes = ktx2_file(ktx2::Format::BC7_SRGB_BLOCK, 8, 4, &levels);
+        let mut image = Image::from_ktx2(&bytes).unwrap();
+        assert_eq!(image.format, ImageFormat::BC7);
+        assert_eq!((image.width, image.height), (8, 4));
+        assert_eq!(image.pixels, levels[0]);
+        let sizes = image
+            .mip_levels
+            .iter()
+            .map(|level| (level.width, level.height, level.pixels[0]))
+            .collect::<Vec<_>>();
+        assert_eq!(sizes, [(4, 2, 2), (2, 1, 3), (1, 1, 4)]);
+        assert_eq!(
+            image.generate_mipmaps(MipmapFilter::Box, false),
+            Err(ImageProcessingError::UnsupportedFormat(ImageFormat::BC7))
+        );
+    }
+
+    #[test]
+    fn compressed_copies_keep_the_source_pixels() {
+        let bytes = ktx2_file(ktx2::Format::BC7_UNORM_BLOCK, 4, 4, &[vec![0; 16]]);
+        let mut image = rgba_image(4, 4, vec![7; 64]);
+        image.load_compressed_ktx2(&bytes).unwrap();
+        assert_eq!(image.format, ImageFormat::R8G8B8A8);
+        assert_eq!(image.pixels, vec![7; 64]);
+        assert!(image
+            .compressed
+            .is_some_and(|compressed| compressed.format == ImageFormat::BC7));
+
+        let mut larger = rgba_image(8, 4, vec![7; 128]);
+        assert_eq!(
+            larger.load_compressed_ktx2(&bytes),
+            Err(ImageProcessingError::MismatchedCompressedSize {
+                width: 4,
+                height: 4
+            })
+        );
+    }
+
+    #[test]
+    fn bc7_blocks_decode_close_to_the_source() {
+        let block: [[u8; 4]; 16] = std::array::from_fn(|index| {
+            [
+                index as u8 * 16,
+                200 - index as u8 * 8,
+                64,
+                255 - index as u8 * 4,
+            ]
+        });
+        let decoded = decode_bc7_block(encode_bc7_block(&block));
+        decoded.iter().zip(&block).for_each(|(decoded, source)| {
+            decoded.iter().zip(source).for_each(|(decoded, source)| {
+                assert!(
+                    (*decoded as i32 - *source as i32).abs() <= 4,
+                    "{decoded} != {source}"
+                );
+            })
+        });
+    }
+
+    #[test]
+    fn bc7_copies_pad_partial_blocks_and_keep_the_source() {
+        let mut image = rgba_image(5, 3, [10, 20, 30, 40].repeat(15));
+        image.generate_mipmaps(MipmapFilter::Box, false).unwrap();
+        image.compress_bc7().unwrap();
+        assert_eq!(image.format, ImageFormat::R8G8B8A8);
+        assert_eq!(image.pixels, [10, 20, 30, 40].repeat(15));
+        let compressed = image.compressed.unwrap();
+        assert_eq!(compressed.format, ImageFormat::BC7);
+        assert_eq!(compressed.pixels.len(), 2 * 16);
+        assert_eq!(compressed.mip_levels.len(), image.mip_levels.len());
+        compressed
+            .mip_levels
+            .iter()
+            .for_each(|level| assert_eq!(level.pixels.len(), 16));
+        let decoded = decode_bc7_block(compressed.pixels[..16].try_into().unwrap());
+        assert!(decoded.iter().all(|texel| *texel == [10, 20, 30, 40]));
+    }
+}
@@ -1,3 +1,19 @@
+mod bvh;
+mod delta;
+mod file;
+mod frustum;
+mod history;
+mod image;
+mod lod;
+mod prefab;
+mod procedural;
+mod processing;
+mod raycast;
+mod traversal;
+mod validate;
 mod world;
 
-pub use self::world::*;
+pub use self::{
+    bvh::*, delta::*, file::*, frustum::*, history::*, image::*, lod::*, prefab::*, procedural::*,
+    processing::*, raycast::*, traversal::*, validate::*, world::*,
+};
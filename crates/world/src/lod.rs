@@ -0,0 +1,599 @@
+use crate::{
+    processing::optimize_vertex_cache,
+    world::{AxisAlignedBoundingBox, Primitive, PrimitiveLod, PrimitiveTopology, World},
+};
+use std::collections::HashMap;
+
+/// How `World::generate_lods` simplifies each primitive
+#[derive(Debug, Copy, Clone)]
+pub struct LodGeneration {
+    /// The most simplified levels to generate, not counting the original
+    pub levels: usize,
+    /// The fraction of the previous level's triangles that each level aims to keep
+    pub reduction: f32,
+    /// The largest error a level may have, relative to the primitive's size
+    pub max_error: f32,
+}
+
+impl Default for LodGeneration {
+    fn default() -> Self {
+        Self {
+            levels: 4,
+            reduction: 0.5,
+            max_error: 0.05,
+        }
+    }
+}
+
+/// How `World::select_lod` trades detail for speed
+#[derive(Debug, Copy, Clone)]
+pub struct LodSelection {
+    pub viewport_height: f32,
+    /// The most pixels a level's error may span on screen before a more detailed level is used
+    pub max_pixel_error: f32,
+}
+
+impl Default for LodSelection {
+    fn default() -> Self {
+        Self {
+            viewport_height: 1080.0,
+            max_pixel_error: 1.0,
+        }
+    }
+}
+
+/// The triangles left after simplification and the quadric error it took to get there
+#[derive(Default, Debug, Clone)]
+pub struct Simplified {
+    pub indices: Vec<u32>,
+    /// The quadric error of the costliest collapse relative to the size of the mesh,
+    /// as described for `PrimitiveLod::error`
+    pub error: f32,
+}
+
+/// Simplifies a triangle list by collapsing edges in order of their quadric error,
+/// until it has at most `target_index_count` indices or any further collapse would exceed `max_error`.
+///
+/// Vertices only ever move onto one of their neighbors, so the simplified indices
+/// still refer to the original vertices. Vertices on open edges, which include the seams
+/// where a mesh's vertices are split for texturing, are never moved so that no gaps open up.
+pub fn simplify(
+    positions: &[nalgebra_glm::Vec3],
+    indices: &[u32],
+    target_index_count: usize,
+    max_error: f32,
+) -> Simplified {
+    let mut triangles = indices
+        .chunks_exact(3)
+        .map(|triangle| [triangle[0], triangle[1], triangle[2]])
+        .collect::<Vec<_>>();
+    let position = |index: u32| positions[index as usize].cast::<f64>();
+
+    let mut quadrics = vec![Quadric::default(); positions.len()];
+    let mut incident = vec![Vec::new(); positions.len()];
+    let mut edge_counts = HashMap::new();
+    triangles
+        .iter()
+        .enumerate()
+        .for_each(|(triangle_index, triangle)| {
+            let quadric = Quadric::from_triangle(triangle.map(position));
+            for corner in 0..3 {
+                let (a, b) = (triangle[corner], triangle[(corner + 1) % 3]);
+                quadrics[a as usize].add(&quadric);
+                incident[a as usize].push(triangle_index);
+                *edge_counts.entry((a.min(b), a.max(b))).or_insert(0) += 1;
+            }
+        });
+    let mut locked = vec![false; positions.len()];
+    edge_counts
+        .iter()
+        .filter(|(_, count)| **count != 2)
+        .for_each(|((a, b), _)| {
+            locked[*a as usize] = true;
+            locked[*b as usize] = true;
+        });
+
+    let size = if positions.is_empty() {
+        0.0
+    } else {
+        let aabb = positions.iter().skip(1).fold(
+            AxisAlignedBoundingBox::new(positions[0], positions[0]),
+            |aabb, position| {
+                AxisAlignedBoundingBox::new(
+                    nalgebra_glm::min2(&aabb.min, position),
+                    nalgebra_glm::max2(&aabb.max, position),
+                )
+            },
+        );
+        aabb.extents().norm() as f64
+    };
+    let max_squared_error = (max_error as f64 * size).powi(2);
+
+    let mut alive = vec![true; triangles.len()];
+    let mut number_of_triangles = triangles.len();
+    let mut squared_error = 0.0_f64;
+    while number_of_triangles * 3 > target_index_count {
+        let mut candidates = triangles
+            .iter()
+            .enumerate()
+            .filter(|(triangle_index, _)| alive[*triangle_index])
+            .flat_map(|(_, triangle)| {
+                (0..3).flat_map(move |corner| {
+                    let (a, b) = (triangle[corner], triangle[(corner + 1) % 3]);
+                    [(a, b), (b, a)]
+                })
+            })
+            .filter(|(from, _)| !locked[*from as usize])
+            .map(|(from, to)| {
+                let mut quadric = quadrics[from as usize];
+                quadric.add(&quadrics[to as usize]);
+                (quadric.error(&position(to)), from, to)
+            })
+            .collect::<Vec<_>>();
+        candidates.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        // Each pass collapses the cheapest edges that do not share a vertex,
+        // since collapsing one edge changes the cost of its neighbors
+        let mut touched = vec![false; positions.len()];
+        let mut collapsed = false;
+        for (error, from, to) in candidates {
+            if number_of_triangles * 3 <= target_index_count || error > max_squared_error {
+                break;
+            }
+            if touched[from as usize] || touched[to as usize] {
+                continue;
+            }
+            let flips = incident[from as usize]
+                .iter()
+                .filter(|triangle_index| alive[**triangle_index])
+                .map(|triangle_index| triangles[*triangle_index])
+                .filter(|triangle| !triangle.contains(&to))
+                .any(|triangle| {
+                    let before = triangle.map(position);
+                    let after =
+                        triangle.map(|index| position(if index == from { to } else { index }));
+                    let (before, after) = (normal(before), normal(after));
+                    before.dot(&after) <= 1e-2 * before.norm() * after.norm()
+                });
+            if flips {
+                continue;
+            }
+
+            for triangle_index in std::mem::take(&mut incident[from as usize]) {
+                if !alive[triangle_index] {
+                    continue;
+                }
+                let triangle = &mut triangles[triangle_index];
+                if triangle.contains(&to) {
+                    alive[triangle_index] = false;
+                    number_of_triangles -= 1;
+                } else {
+                    triangle
+                        .iter_mut()
+                        .filter(|index| **index == from)
+                        .for_each(|index| *index = to);
+                    incident[to as usize].push(triangle_index);
+                }
+            }
+            let from_quadric = quadrics[from as usize];
+            quadrics[to as usize].add(&from_quadric);
+            touched[from as usize] = true;
+            touched[to as usize] = true;
+            squared_error = squared_error.max(error);
+            collapsed = true;
+        }
+        if !collapsed {
+            break;
+        }
+    }
+
+    let mut indices = triangles
+        .iter()
+        .zip(alive.iter())
+        .filter(|(_, alive)| **alive)
+        .flat_map(|(triangle, _)| *triangle)
+        .collect::<Vec<_>>();
+    optimize_vertex_cache(&mut indices, positions.len());
+    Simplified {
+        indices,
+        error: if size > 0.0 {
+            (squared_error.sqrt() / size) as f32
+        } else {
+            0.0
+        },
+    }
+}
+
+/// Returns how much of the viewport the box covers once projected, where 1.0 fills it.
+/// Boxes that cross the camera's near plane are treated as infinitely large.
+pub fn screen_size(
+    aabb: &AxisAlignedBoundingBox,
+    model_view_projection: &nalgebra_glm::Mat4,
+) -> f32 {
+    let mut min = nalgebra_glm::vec2(f32::MAX, f32::MAX);
+    let mut max = nalgebra_glm::vec2(f32::MIN, f32::MIN);
+    for corner in 0..8 {
+        let point = nalgebra_glm::vec3(
+            if corner & 1 == 0 {
+                aabb.min.x
+            } else {
+                aabb.max.x
+            },
+            if corner & 2 == 0 {
+                aabb.min.y
+            } else {
+                aabb.max.y
+            },
+            if corner & 4 == 0 {
+                aabb.min.z
+            } else {
+                aabb.max.z
+            },
+        );
+        let clip = model_view_projection * point.push(1.0);
+        if clip.w <= f32::EPSILON {
+            return f32::INFINITY;
+        }
+        let ndc = clip.xy() / clip.w;
+        min = nalgebra_glm::min2(&min, &ndc);
+        max = nalgebra_glm::max2(&max, &ndc);
+    }
+    // Normalized device coordinates span two units across the viewport
+    (max - min).max() / 2.0
+}
+
+impl Primitive {
+    /// Returns the index offset and count to draw at a level of detail,
+    /// where level 0 is the original and levels past the last one use the last one
+    pub fn lod_indices(&self, level: usize) -> (usize, usize) {
+        match level.checked_sub(1) {
+            None => (self.index_offset, self.number_of_indices),
+            Some(lod_index) => self
+                .lods
+                .get(lod_index)
+                .or(self.lods.last())
+                .map_or((self.index_offset, self.number_of_indices), |lod| {
+                    (lod.index_offset, lod.number_of_indices)
+                }),
+        }
+    }
+}
+
+impl World {
+    /// Generates simplified levels of detail for each of the mesh's triangle list primitives,
+    /// replacing any they already had and reclaiming their indices. Each level is simplified
+    /// from the original triangles, and generation stops early once a level cannot get
+    /// meaningfully smaller within the error limit.
+    pub fn generate_lods(&mut self, mesh_index: usize, generation: &LodGeneration) {
+        let mut old_ranges = self.meshes[mesh_index]
+            .primitives
+            .iter_mut()
+            .flat_map(|primitive| std::mem::take(&mut primitive.lods))
+            .map(|lod| (lod.index_offset, lod.number_of_indices))
+            .collect::<Vec<_>>();
+        // Later ranges go first, so that removing one leaves the offsets of the rest intact
+        old_ranges.sort_unstable_by(|a, b| b.cmp(a));
+        old_ranges
+            .into_iter()
+            .for_each(|(index_offset, number_of_indices)| {
+                self.remove_indices(index_offset, number_of_indices)
+            });
+
+        let primitives = &self.meshes[mesh_index].primitives;
+        let lods = primitives
+            .iter()
+            .map(|primitive| {
+                if !matches!(primitive.topology, PrimitiveTopology::Triangles) {
+                    return Vec::new();
+                }
+                let positions = self.vertices[primitive.vertex_offset
+                    ..primitive.vertex_offset + primitive.number_of_vertices]
+                    .iter()
+                    .map(|vertex| vertex.position)
+                    .collect::<Vec<_>>();
+                let indices = if primitive.number_of_indices > 0 {
+                    self.indices[primitive.index_offset
+                        ..primitive.index_offset + primitive.number_of_indices]
+                        .to_vec()
+                } else {
+                    (0..primitive.number_of_vertices as u32).collect()
+                };
+
+                let mut levels: Vec<Simplified> = Vec::new();
+                let mut target = indices.len() as f32 / 3.0;
+                for _ in 0..generation.levels {
+                    target *= generation.reduction;
+                    let simplified = simplify(
+                        &positions,
+                        &indices,
+                        target as usize * 3,
+                        generation.max_error,
+                    );
+                    let previous = levels
+                        .last()
+                        .map_or(indices.len(), |level| level.indices.len());
+                    if simplified.indices.is_empty()
+                        || simplified.indices.len() as f32 > previous as f32 * 0.9
+                    {
+                        break;
+                    }
+                    levels.push(simplified);
+                }
+                levels
+            })
+            .collect::<Vec<_>>();
+
+        let indices = &mut self.indices;
+        self.meshes[mesh_index]
+            .primitives
+            .iter_mut()
+            .zip(lods)
+            .for_each(|(primitive, levels)| {
+                primitive.lods = levels
+                    .into_iter()
+                    .map(|level| {
+                        let index_offset = indices.len();
+                        let number_of_indices = level.indices.len();
+                        indices.extend(level.indices);
+                        PrimitiveLod {
+                            index_offset,
+                            number_of_indices,
+                            error: level.error,
+                        }
+                    })
+                    .collect();
+            });
+    }
+
+    /// Removes a range of indices, moving the ranges of every primitive and level after it down
+    fn remove_indices(&mut self, index_offset: usize, number_of_indices: usize) {
+        let end = index_offset + number_of_indices;
+        self.indices.drain(index_offset..end);
+        self.meshes
+            .iter_mut()
+            .flat_map(|mesh| mesh.primitives.iter_mut())
+            .for_each(|primitive| {
+                if primitive.index_offset >= end {
+                    primitive.index_offset -= number_of_indices;
+                }
+                primitive
+                    .lods
+                    .iter_mut()
+                    .filter(|lod| lod.index_offset >= end)
+                    .for_each(|lod| lod.index_offset -= number_of_indices);
+            });
+    }
+
+    /// Picks the least detailed level whose error stays within the allowed number of pixels,
+    /// judging the node's size on screen by its projected bounding box
+    pub fn select_lod(
+        &self,
+        node_index: usize,
+        global_transform: &nalgebra_glm::Mat4,
+        view_projection: &nalgebra_glm::Mat4,
+        selection: &LodSelection,
+    ) -> usize {
+        let node = &self.nodes[node_index];
+        let (Some(mesh_index), Some(aabb_index)) = (node.mesh_index, node.aabb_index) else {
+            return 0;
+        };
+        let primitives = &self.meshes[mesh_index].primitives;
+        let size_in_pixels = screen_size(
+            &self.aabbs[aabb_index],
+            &(view_projection * global_transform),
+        ) * selection.viewport_height;
+        let number_of_levels = primitives
+            .iter()
+            .map(|primitive| primitive.lods.len())
+            .max()
+            .unwrap_or_default();
+
+        // A level is only as good as its worst primitive
+        (1..=number_of_levels)
+            .take_while(|level| {
+                let error = primitives
+                    .iter()
+                    .filter_map(|primitive| primitive.lods.get(level - 1).or(primitive.lods.last()))
+                    .map(|lod| lod.error)
+                    .fold(0.0, f32::max);
+                error * size_in_pixels <= selection.max_pixel_error
+            })
+            .last()
+            .unwrap_or_default()
+    }
+}
+
+/// The sum of squared distances to a set of planes, weighted by the area of the triangles they came from
+#[derive(Default, Debug, Copy, Clone)]
+struct Quadric {
+    // The upper triangle of the symmetric 4x4 matrix, row by row
+    coefficients: [f64; 10],
+    weight: f64,
+}
+
+impl Quadric {
+    fn from_triangle(corners: [nalgebra_glm::DVec3; 3]) -> Self {
+        let normal = normal(corners);
+        let double_area = normal.norm();
+        if double_area <= f64::EPSILON {
+            return Self::default();
+        }
+        let normal = normal / double_area;
+        let (a, b, c) = (normal.x, normal.y, normal.z);
+        let d = -normal.dot(&corners[0]);
+        let weight = double_area / 2.0;
+        Self {
+            coefficients: [
+                a * a,
+                a * b,
+                a * c,
+                a * d,
+                b * b,
+                b * c,
+                b * d,
+                c * c,
+                c * d,
+                d * d,
+            ]
+            .map(|coefficient| coefficient * weight),
+            weight,
+        }
+    }
+
+    fn add(&mut self, other: &Quadric) {
+        self.coefficients
+            .iter_mut()
+            .zip(other.coefficients.iter())
+            .for_each(|(coefficient, other)| *coefficient += other);
+        self.weight += other.weight;
+    }
+
+    /// The area weighted mean of the squared distances from the point to the planes
+    fn error(&self, point: &nalgebra_glm::DVec3) -> f64 {
+        if self.weight <= 0.0 {
+            return 0.0;
+        }
+        let [aa, ab, ac, ad, bb, bc, bd, cc, cd, dd] = self.coefficients;
+        let (x, y, z) = (point.x, point.y, point.z);
+        let error = aa * x * x
+            + 2.0 * ab * x * y
+            + 2.0 * ac * x * z
+            + 2.0 * ad * x
+            + bb * y * y
+            + 2.0 * bc * y * z
+            + 2.0 * bd * y
+            + cc * z * z
+            + 2.0 * cd * z
+            + dd;
+        // Rounding can make the sum slightly negative for points on every plane
+        (error / self.weight).max(0.0)
+    }
+}
+
+fn normal(corners: [nalgebra_glm::DVec3; 3]) -> nalgebra_glm::DVec3 {
+    (corners[1] - corners[0]).cross(&(corners[2] - corners[0]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{screen_size, simplify, LodGeneration, LodSelection};
+    use crate::{procedural::MeshData, world::World};
+
+    fn positions(mesh: &MeshData) -> Vec<nalgebra_glm::Vec3> {
+        mesh.vertices.iter().map(|vertex| vertex.position).collect()
+    }
+
+    #[test]
+    fn flat_surfaces_simplify_without_error() {
+        let plane = MeshData::plane(4.0, 4.0, 16);
+        let simplified = simplify(&positions(&plane), &plane.indices, 0, 0.01);
+        assert!(simplified.error < 1e-6, "{}", simplified.error);
+        // Only the border vertices are left to hold the outline in place
+        assert!(
+            simplified.indices.len() * 8 < plane.indices.len(),
+            "{} indices left",
+            simplified.indices.len()
+        );
+    }
+
+    #[test]
+    fn lod_chains_reduce_triangles_within_error_bounds() {
+        let mut world = World::default();
+        let sphere = MeshData::icosphere(1.0, 4);
+        let number_of_triangles = sphere.indices.len() / 3;
+        let mesh_index = world.add_mesh(sphere, None);
+        let generation = LodGeneration::default();
+        world.generate_lods(mesh_index, &generation);
+
+        let primitive = &world.meshes[mesh_index].primitives[0];
+        assert!(primitive.lods.len() >= 3, "{} levels", primitive.lods.len());
+        let mut previous = (number_of_triangles, 0.0);
+        for (level, lod) in primitive.lods.iter().enumerate() {
+            let triangles = lod.number_of_indices / 3;
+            let target = number_of_triangles as f32 * generation.reduction.powi(level as i32 + 1);
+            assert!(triangles < previous.0, "Level {level} did not get smaller");
+            assert!(
+                triangles as f32 <= target.ceil(),
+                "Level {level} has {triangles} triangles"
+            );
+            assert!(lod.error >= previous.1 && lod.error <= generation.max_error);
+            previous = (triangles, lod.error);
+
+            // No triangle sinks further into the sphere than the error allows,
+            // where the error is relative to the diagonal of the sphere's bounds
+            let allowed = lod.error * 2.0 * 3.0_f32.sqrt() + 0.02;
+            world.indices[lod.index_offset..lod.index_offset + lod.number_of_indices]
+                .chunks_exact(3)
+                .for_each(|triangle| {
+                    let centroid = triangle
+                        .iter()
+                        .map(|index| world.vertices[*index as usize].position)
+                        .sum::<nalgebra_glm::Vec3>()
+                        / 3.0;
+                    assert!(
+                        1.0 - centroid.norm() <= allowed,
+                        "Level {level} deviates too far"
+                    );
+                });
+        }
+        assert!(world.validate().is_valid());
+    }
+
+    #[test]
+    fn regenerating_lods_reclaims_their_indices() {
+        let mut world = World::default();
+        let sphere = world.add_mesh(MeshData::icosphere(1.0, 3), None);
+        world.generate_lods(sphere, &LodGeneration::default());
+        let cube = world.add_mesh(MeshData::cube(1.0), None);
+        let number_of_indices = world.indices.len();
+        let cube_indices = world.indices[world.meshes[cube].primitives[0].index_offset..]
+            [..world.meshes[cube].primitives[0].number_of_indices]
+            .to_vec();
+
+        world.generate_lods(sphere, &LodGeneration::default());
+        assert_eq!(world.indices.len(), number_of_indices);
+        let primitive = &world.meshes[cube].primitives[0];
+        assert_eq!(
+            world.indices[primitive.index_offset..][..primitive.number_of_indices],
+            cube_indices[..]
+        );
+        assert!(world.validate().is_valid());
+    }
+
+    #[test]
+    fn lods_are_selected_by_screen_size() {
+        let mut world = World::default();
+        let node_index = world.add_node();
+        world.add_mesh_to_node(node_index, MeshData::icosphere(1.0, 4), None);
+        world.generate_lods(0, &LodGeneration::default());
+        let number_of_levels = world.meshes[0].primitives[0].lods.len();
+
+        let projection = nalgebra_glm::perspective_zo(1.0, 60_f32.to_radians(), 0.1, 1000.0);
+        let select = |distance: f32| {
+            let view = nalgebra_glm::look_at(
+                &nalgebra_glm::vec3(0.0, 0.0, distance),
+                &nalgebra_glm::Vec3::zeros(),
+                &nalgebra_glm::Vec3::y(),
+            );
+            world.select_lod(
+                node_index,
+                &nalgebra_glm::Mat4::identity(),
+                &(projection * view),
+                &LodSelection::default(),
+            )
+        };
+        let levels = [2.0, 20.0, 200.0, 2000.0].map(select);
+        assert_eq!(levels[0], 0);
+        assert!(
+            levels.windows(2).all(|pair| pair[0] <= pair[1]),
+            "{levels:?}"
+        );
+        assert_eq!(levels[3], number_of_levels);
+
+        let size = screen_size(
+            &world.aabbs[0],
+            &(projection * nalgebra_glm::translation(&nalgebra_glm::vec3(0.0, 0.0, -0.5))),
+        );
+        assert_eq!(size, f32::INFINITY);
+    }
+}
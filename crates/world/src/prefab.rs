@@ -0,0 +1,484 @@
+use crate::world::{Animation, Channel, Image, Mesh, Node, Sampler, Skin, Texture, World};
+use std::{
+    collections::{HashMap, HashSet},
+    hash::{Hash, Hasher},
+};
+
+#[derive(Default, Debug, Copy, Clone)]
+pub struct InstantiateOptions {
+    /// The scene of this world that `parent` belongs to, the default scene when `None`
+    pub target_scene_index: Option<usize>,
+
+    /// Reuses samplers, images, textures and materials that are identical to existing ones
+    /// instead of appending copies. Animations that were copied before gain the channels
+    /// of the new instance, so that one animation plays every instance.
+    pub deduplicate: bool,
+
+    /// Also copies the source scene's default camera node and its descendants when the camera
+    /// is synthetic. Importers add such a camera to scenes without one, and it is usually
+    /// unwanted in a prefab instance. Authored cameras are always copied.
+    pub include_default_camera: bool,
+}
+
+impl World {
+    /// Copies a scene of another world into the default scene of this world under `parent`,
+    /// returning the graph nodes of the copied roots
+    pub fn instantiate(
+        &mut self,
+        other: &World,
+        scene_index: usize,
+        parent: petgraph::graph::NodeIndex,
+    ) -> Vec<petgraph::graph::NodeIndex> {
+        self.instantiate_with_options(other, scene_index, parent, &InstantiateOptions::default())
+    }
+
+    pub fn instantiate_with_options(
+        &mut self,
+        other: &World,
+        scene_index: usize,
+        parent: petgraph::graph::NodeIndex,
+        options: &InstantiateOptions,
+    ) -> Vec<petgraph::graph::NodeIndex> {
+        let source_scene = &other.scenes[scene_index];
+        let target_scene_index = options
+            .target_scene_index
+            .unwrap_or(self.default_scene_index);
+        let mut instancer = Instancer::new(self, other, options.deduplicate);
+
+        let mut excluded = HashSet::new();
+        let excluded_camera =
+            source_scene
+                .default_camera_graph_node_index
+                .filter(|camera_graph_node_index| {
+                    !options.include_default_camera
+                        && source_scene
+                            .graph
+                            .node_weight(*camera_graph_node_index)
+                            .and_then(|node_index| other.nodes[*node_index].camera_index)
+                            .is_some_and(|camera_index| other.cameras[camera_index].synthetic)
+                });
+        if let Some(camera_graph_node_index) = excluded_camera {
+            let mut dfs = petgraph::visit::Dfs::new(&source_scene.graph, camera_graph_node_index);
+            while let Some(graph_node_index) = dfs.next(&source_scene.graph) {
+                excluded.insert(graph_node_index);
+            }
+        }
+
+        let mut graph_node_map = HashMap::new();
+        for source_graph_node_index in source_scene.graph.node_indices() {
+            if excluded.contains(&source_graph_node_index) {
+                continue;
+            }
+            let node_index = instancer.node(source_scene.graph[source_graph_node_index]);
+            let graph_node_index = instancer.world.scenes[target_scene_index]
+                .graph
+                .add_node(node_index);
+            graph_node_map.insert(source_graph_node_index, graph_node_index);
+        }
+
+        let graph = &mut instancer.world.scenes[target_scene_index].graph;
+        let mut roots = Vec::new();
+        for source_graph_node_index in source_scene.graph.node_indices() {
+            let Some(&graph_node_index) = graph_node_map.get(&source_graph_node_index) else {
+                continue;
+            };
+            match source_scene
+                .graph
+                .neighbors_directed(source_graph_node_index, petgraph::Direction::Incoming)
+                .next()
+            {
+                Some(source_parent) => {
+                    if let Some(&new_parent) = graph_node_map.get(&source_parent) {
+                        graph.add_edge(new_parent, graph_node_index, ());
+                    }
+                }
+                None => {
+                    graph.add_edge(parent, graph_node_index, ());
+                    roots.push(graph_node_index);
+                }
+            }
+        }
+
+        instancer.skins();
+        instancer.animations();
+        roots
+    }
+}
+
+/// Copies the parts of a source world referenced by its nodes,
+/// remembering where everything went so shared data is only copied once
+struct Instancer<'a> {
+    world: &'a mut World,
+    other: &'a World,
+    deduplicate: bool,
+    image_hashes: HashMap<u64, Vec<usize>>,
+    samplers: HashMap<usize, usize>,
+    images: HashMap<usize, usize>,
+    textures: HashMap<usize, usize>,
+    materials: HashMap<usize, usize>,
+    meshes: HashMap<usize, usize>,
+    cameras: HashMap<usize, usize>,
+    lights: HashMap<usize, usize>,
+    primitive_meshes: HashMap<usize, usize>,
+    aabbs: HashMap<usize, usize>,
+    nodes: HashMap<usize, usize>,
+}
+
+impl<'a> Instancer<'a> {
+    fn new(world: &'a mut World, other: &'a World, deduplicate: bool) -> Self {
+        let mut image_hashes: HashMap<u64, Vec<usize>> = HashMap::new();
+        if deduplicate {
+            world.images.iter().enumerate().for_each(|(index, image)| {
+                image_hashes
+                    .entry(hash_image(image))
+                    .or_default()
+                    .push(index)
+            });
+        }
+        Self {
+            world,
+            other,
+            deduplicate,
+            image_hashes,
+            samplers: HashMap::new(),
+            images: HashMap::new(),
+            textures: HashMap::new(),
+            materials: HashMap::new(),
+            meshes: HashMap::new(),
+            cameras: HashMap::new(),
+            lights: HashMap::new(),
+            primitive_meshes: HashMap::new(),
+            aabbs: HashMap::new(),
+            nodes: HashMap::new(),
+        }
+    }
+
+    fn node(&mut self, source_index: usize) -> usize {
+        if let Some(&node_index) = self.nodes.get(&source_index) {
+            return node_index;
+        }
+        let source = &self.other.nodes[source_index];
+
+        let transform_index = self.world.transforms.len();
+        self.world
+            .transforms
+            .push(self.other.transforms[source.transform_index]);
+        let metadata_index = self.world.metadata.len();
+        self.world
+            .metadata
+            .push(self.other.metadata[source.metadata_index].clone());
+        let morph_weights_index = source.morph_weights_index.map(|index| {
+            self.world
+                .morph_weights
+                .push(self.other.morph_weights[index].clone());
+            self.world.morph_weights.len() - 1
+        });
+
+        let node = Node {
+            metadata_index,
+            transform_index,
+            camera_index: source.camera_index.map(|index| self.camera(index)),
+            mesh_index: source.mesh_index.map(|index| self.mesh(index)),
+            light_index: source.light_index.map(|index| self.light(index)),
+            // Rigid bodies live in the physics world, which is not part of the copy
+            rigid_body_index: None,
+            primitive_mesh_index: source
+                .primitive_mesh_index
+                .map(|index| self.primitive_mesh(index)),
+            aabb_index: source.aabb_index.map(|index| self.aabb(index)),
+            morph_weights_index,
+            // Assigned once the skins are copied, because they reference nodes
+            skin_index: None,
+        };
+        let node_index = self.world.nodes.len();
+        self.world.nodes.push(node);
+        self.nodes.insert(source_index, node_index);
+        node_index
+    }
+
+    fn camera(&mut self, source_index: usize) -> usize {
+        *self.cameras.entry(source_index).or_insert_with(|| {
+            self.world
+                .cameras
+                .push(self.other.cameras[source_index].clone());
+            self.world.cameras.len() - 1
+        })
+    }
+
+    fn light(&mut self, source_index: usize) -> usize {
+        *self.lights.entry(source_index).or_insert_with(|| {
+            self.world.lights.push(self.other.lights[source_index]);
+            self.world.lights.len() - 1
+        })
+    }
+
+    fn primitive_mesh(&mut self, source_index: usize) -> usize {
+        *self
+            .primitive_meshes
+            .entry(source_index)
+            .or_insert_with(|| {
+                self.world
+                    .primitive_meshes
+                    .push(self.other.primitive_meshes[source_index].clone());
+                self.world.primitive_meshes.len() - 1
+            })
+    }
+
+    fn aabb(&mut self, source_index: usize) -> usize {
+        *self.aabbs.entry(source_index).or_insert_with(|| {
+            self.world.aabbs.push(self.other.aabbs[source_index]);
+            self.world.aabbs.len() - 1
+        })
+    }
+
+    fn mesh(&mut self, source_index: usize) -> usize {
+        if let Some(&mesh_index) = self.meshes.get(&source_index) {
+            return mesh_index;
+        }
+        let source = &self.other.meshes[source_index];
+        let primitives = source
+            .primitives
+            .iter()
+            .map(|primitive| {
+                let vertex_offset = self.world.vertices.len();
+                self.world.vertices.extend_from_slice(
+                    &self.other.vertices[primitive.vertex_offset
+                        ..(primitive.vertex_offset + primitive.number_of_vertices)],
+                );
+
+                // Indices are relative to the primitive's first vertex and are copied unchanged
+                let index_offset = self.world.indices.len();
+                self.world.indices.extend_from_slice(
+                    &self.other.indices[primitive.index_offset
+                        ..(primitive.index_offset + primitive.number_of_indices)],
+                );
+
+                let lods = primitive
+                    .lods
+                    .iter()
+                    .map(|lod| {
+                        let index_offset = self.world.indices.len();
+                        self.world.indices.extend_from_slice(
+                            &self.other.indices
+                                [lod.index_offset..(lod.index_offset + lod.number_of_indices)],
+                        );
+                        crate::world::PrimitiveLod {
+                            index_offset,
+                            ..*lod
+                        }
+                    })
+                    .collect();
+
+                let morph_target_offset = self.world.morph_target_vertices.len();
+                let number_of_morph_target_vertices =
+                    primitive.number_of_morph_targets * primitive.number_of_vertices;
+                self.world.morph_target_vertices.extend_from_slice(
+                    &self.other.morph_target_vertices[primitive.morph_target_offset
+                        ..(primitive.morph_target_offset + number_of_morph_target_vertices)],
+                );
+
+                crate::world::Primitive {
+                    vertex_offset,
+                    index_offset,
+                    morph_target_offset,
+                    material_index: primitive.material_index.map(|index| self.material(index)),
+                    lods,
+                    ..primitive.clone()
+                }
+            })
+            .collect();
+        let mesh_index = self.world.meshes.len();
+        self.world.meshes.push(Mesh {
+            primitives,
+            weights: source.weights.clone(),
+        });
+        self.meshes.insert(source_index, mesh_index);
+        mesh_index
+    }
+
+    fn material(&mut self, source_index: usize) -> usize {
+        if let Some(&material_index) = self.materials.get(&source_index) {
+            return material_index;
+        }
+        let source = &self.other.materials[source_index];
+        let mut material = source.clone();
+        material
+            .textures_mut()
+            .for_each(|info| info.texture_index = self.texture(info.texture_index));
+        let material_index = self.find_or_push(material, |world| &mut world.materials);
+        self.materials.insert(source_index, material_index);
+        material_index
+    }
+
+    fn texture(&mut self, source_index: usize) -> usize {
+        if let Some(&texture_index) = self.textures.get(&source_index) {
+            return texture_index;
+        }
+        let source = &self.other.textures[source_index];
+        let texture = Texture {
+            image_index: self.image(source.image_index),
+            sampler_index: source.sampler_index.map(|index| self.sampler(index)),
+        };
+        let texture_index = self.find_or_push(texture, |world| &mut world.textures);
+        self.textures.insert(source_index, texture_index);
+        texture_index
+    }
+
+    fn sampler(&mut self, source_index: usize) -> usize {
+        if let Some(&sampler_index) = self.samplers.get(&source_index) {
+            return sampler_index;
+        }
+        let sampler: Sampler = self.other.samplers[source_index].clone();
+        let sampler_index = self.find_or_push(sampler, |world| &mut world.samplers);
+        self.samplers.insert(source_index, sampler_index);
+        sampler_index
+    }
+
+    fn image(&mut self, source_index: usize) -> usize {
+        if let Some(&image_index) = self.images.get(&source_index) {
+            return image_index;
+        }
+        let source = &self.other.images[source_index];
+        let hash = hash_image(source);
+        let existing = self.deduplicate.then(|| {
+            self.image_hashes.get(&hash).and_then(|candidates| {
+                candidates
+                    .iter()
+                    .copied()
+                    .find(|index| images_match(&self.world.images[*index], source))
+            })
+        });
+        let image_index = match existing.flatten() {
+            Some(image_index) => image_index,
+            None => {
+                let image_index = self.world.images.len();
+                self.world.images.push(source.clone());
+                if self.deduplicate {
+                    self.image_hashes.entry(hash).or_default().push(image_index);
+                }
+                image_index
+            }
+        };
+        self.images.insert(source_index, image_index);
+        image_index
+    }
+
+    fn find_or_push<T: PartialEq>(
+        &mut self,
+        value: T,
+        collection: impl Fn(&mut World) -> &mut Vec<T>,
+    ) -> usize {
+        let collection = collection(self.world);
+        if self.deduplicate {
+            if let Some(index) = collection.iter().position(|existing| *existing == value) {
+                return index;
+            }
+        }
+        collection.push(value);
+        collection.len() - 1
+    }
+
+    /// Skins are only copied when every joint was copied,
+    /// since the joint order is referenced by the vertices
+    fn skins(&mut self) {
+        let mut skins = HashMap::new();
+        for (source_index, skin) in self.other.skins.iter().enumerate() {
+            let joints = skin
+                .joints
+                .iter()
+                .map(|joint| {
+                    self.nodes
+                        .get(&joint.target_node_index)
+                        .map(|&target_node_index| crate::world::Joint {
+                            target_node_index,
+                            ..joint.clone()
+                        })
+                })
+                .collect::<Option<Vec<_>>>();
+            if let Some(joints) = joints {
+                skins.insert(source_index, self.world.skins.len());
+                self.world.skins.push(Skin { joints });
+            }
+        }
+        for (&source_index, &node_index) in self.nodes.iter() {
+            self.world.nodes[node_index].skin_index = self.other.nodes[source_index]
+                .skin_index
+                .and_then(|index| skins.get(&index).copied());
+        }
+    }
+
+    /// Animations keep the channels that target copied nodes
+    fn animations(&mut self) {
+        for animation in self.other.animations.iter() {
+            let channels = animation
+                .channels
+                .iter()
+                .filter_map(|channel| {
+                    self.nodes
+                        .get(&channel.target_node_index)
+                        .map(|&target_node_index| crate::world::Channel {
+                            target_node_index,
+                            ..channel.clone()
+                        })
+                })
+                .collect::<Vec<_>>();
+            if channels.is_empty() {
+                continue;
+            }
+            let existing = self.deduplicate.then(|| {
+                self.world.animations.iter().position(|existing| {
+                    existing.name == animation.name
+                        && existing.max_animation_time == animation.max_animation_time
+                        && existing.channels.len() >= channels.len()
+                        && existing
+                            .channels
+                            .iter()
+                            .zip(&channels)
+                            .all(|(a, b)| keyframes_match(a, b))
+                })
+            });
+            match existing.flatten() {
+                Some(animation_index) => self.world.animations[animation_index]
+                    .channels
+                    .extend(channels),
+                None => self.world.animations.push(Animation {
+                    channels,
+                    ..animation.clone()
+                }),
+            }
+        }
+    }
+}
+
+fn hash_image(image: &Image) -> u64 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    image.format.hash(&mut hasher);
+    image.width.hash(&mut hasher);
+    image.height.hash(&mut hasher);
+    image.pixels.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Whether two channels animate the same way, whichever nodes they target
+fn keyframes_match(a: &Channel, b: &Channel) -> bool {
+    a.inputs == b.inputs
+        && a.transformations == b.transformations
+        && a.interpolation == b.interpolation
+        && a.tangents == b.tangents
+}
+
+fn images_match(a: &Image, b: &Image) -> bool {
+    a.format == b.format
+        && a.width == b.width
+        && a.height == b.height
+        && a.pixels == b.pixels
+        && a.mip_levels.len() == b.mip_levels.len()
+        && a.mip_levels
+            .iter()
+            .zip(&b.mip_levels)
+            .all(|(a, b)| a.pixels == b.pixels)
+        && match (&a.compressed, &b.compressed) {
+            (Some(a), Some(b)) => images_match(a, b),
+            (a, b) => a.is_none() && b.is_none(),
+        }
+}
@@ -0,0 +1,504 @@
+use crate::{
+    processing::generate_tangents,
+    world::{AxisAlignedBoundingBox, Mesh, Primitive, PrimitiveTopology, Vertex, World},
+};
+use std::{collections::HashMap, f32::consts::PI};
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum TerrainError {
+    #[error("A terrain needs at least 2x2 height samples, but has {columns}x{rows}")]
+    TooFewSamples { columns: usize, rows: usize },
+    #[error("A {columns}x{rows} terrain needs {} heights, but {actual} were given", columns * rows)]
+    HeightCountMismatch {
+        columns: usize,
+        rows: usize,
+        actual: usize,
+    },
+}
+
+/// Triangle geometry built in code, centered on the origin with counter-clockwise front faces
+#[derive(Default, Debug, Clone)]
+pub struct MeshData {
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>,
+}
+
+impl MeshData {
+    /// A flat plane on the XZ plane facing +Y, split into a grid of quads
+    pub fn plane(width: f32, depth: f32, subdivisions: u32) -> Self {
+        let subdivisions = subdivisions.max(1);
+        Self::surface(subdivisions, subdivisions, |u, v| {
+            vertex(
+                nalgebra_glm::vec3((u - 0.5) * width, 0.0, (v - 0.5) * depth),
+                nalgebra_glm::Vec3::y(),
+                nalgebra_glm::vec2(u, v),
+            )
+        })
+    }
+
+    /// A cube whose faces each map the whole texture
+    pub fn cube(size: f32) -> Self {
+        let half_size = size / 2.0;
+        let faces = [
+            (nalgebra_glm::Vec3::x(), -nalgebra_glm::Vec3::y()),
+            (-nalgebra_glm::Vec3::x(), -nalgebra_glm::Vec3::y()),
+            (nalgebra_glm::Vec3::y(), nalgebra_glm::Vec3::z()),
+            (-nalgebra_glm::Vec3::y(), -nalgebra_glm::Vec3::z()),
+            (nalgebra_glm::Vec3::z(), -nalgebra_glm::Vec3::y()),
+            (-nalgebra_glm::Vec3::z(), -nalgebra_glm::Vec3::y()),
+        ];
+        let mut mesh = Self::default();
+        for (normal, down) in faces {
+            let right = normal.cross(&down);
+            mesh.append(Self::surface(1, 1, |u, v| {
+                vertex(
+                    normal * half_size + right * (u - 0.5) * size + down * (v - 0.5) * size,
+                    normal,
+                    nalgebra_glm::vec2(u, v),
+                )
+            }));
+        }
+        mesh
+    }
+
+    /// A sphere made of rings of latitude and segments of longitude
+    pub fn uv_sphere(radius: f32, segments: u32, rings: u32) -> Self {
+        Self::surface(segments.max(3), rings.max(2), |u, v| {
+            let normal = spherical(u * 2.0 * PI, v * PI);
+            vertex(normal * radius, normal, nalgebra_glm::vec2(u, v))
+        })
+    }
+
+    /// A sphere made by repeatedly subdividing an icosahedron, giving evenly sized triangles.
+    /// Texture coordinates are a spherical projection, so they wrap around at a seam.
+    pub fn icosphere(radius: f32, subdivisions: u32) -> Self {
+        let t = (1.0 + 5.0_f32.sqrt()) / 2.0;
+        let mut positions = [
+            [-1.0, t, 0.0],
+            [1.0, t, 0.0],
+            [-1.0, -t, 0.0],
+            [1.0, -t, 0.0],
+            [0.0, -1.0, t],
+            [0.0, 1.0, t],
+            [0.0, -1.0, -t],
+            [0.0, 1.0, -t],
+            [t, 0.0, -1.0],
+            [t, 0.0, 1.0],
+            [-t, 0.0, -1.0],
+            [-t, 0.0, 1.0],
+        ]
+        .iter()
+        .map(|[x, y, z]| nalgebra_glm::vec3(*x, *y, *z).normalize())
+        .collect::<Vec<_>>();
+        let mut triangles: Vec<[u32; 3]> = vec![
+            [0, 11, 5],
+            [0, 5, 1],
+            [0, 1, 7],
+            [0, 7, 10],
+            [0, 10, 11],
+            [1, 5, 9],
+            [5, 11, 4],
+            [11, 10, 2],
+            [10, 7, 6],
+            [7, 1, 8],
+            [3, 9, 4],
+            [3, 4, 2],
+            [3, 2, 6],
+            [3, 6, 8],
+            [3, 8, 9],
+            [4, 9, 5],
+            [2, 4, 11],
+            [6, 2, 10],
+            [8, 6, 7],
+            [9, 8, 1],
+        ];
+
+        for _ in 0..subdivisions {
+            let mut midpoints = HashMap::new();
+            let mut midpoint = |a: u32, b: u32| {
+                *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                    let position = (positions[a as usize] + positions[b as usize]).normalize();
+                    positions.push(position);
+                    positions.len() as u32 - 1
+                })
+            };
+            triangles = triangles
+                .iter()
+                .flat_map(|&[a, b, c]| {
+                    let (ab, bc, ca) = (midpoint(a, b), midpoint(b, c), midpoint(c, a));
+                    [[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]
+                })
+                .collect();
+        }
+
+        Self::with_tangents(
+            positions
+                .iter()
+                .map(|normal| {
+                    let uv = nalgebra_glm::vec2(
+                        0.5 + normal.x.atan2(normal.z) / (2.0 * PI),
+                        normal.y.clamp(-1.0, 1.0).acos() / PI,
+                    );
+                    vertex(normal * radius, *normal, uv)
+                })
+                .collect(),
+            triangles.into_iter().flatten().collect(),
+        )
+    }
+
+    /// A cylinder along the Y axis with capped ends
+    pub fn cylinder(radius: f32, height: f32, segments: u32) -> Self {
+        let segments = segments.max(3);
+        let mut mesh = Self::surface(segments, 1, |u, v| {
+            let normal = spherical(u * 2.0 * PI, PI / 2.0);
+            let position = normal * radius + nalgebra_glm::vec3(0.0, (0.5 - v) * height, 0.0);
+            vertex(position, normal, nalgebra_glm::vec2(u, v))
+        });
+        mesh.append(Self::cap(radius, height / 2.0, segments, true));
+        mesh.append(Self::cap(radius, -height / 2.0, segments, false));
+        mesh
+    }
+
+    /// A cone along the Y axis with its tip at the top and a capped base
+    pub fn cone(radius: f32, height: f32, segments: u32) -> Self {
+        let segments = segments.max(3);
+        let mut mesh = Self::surface(segments, 1, |u, v| {
+            let angle = u * 2.0 * PI;
+            let outward = nalgebra_glm::vec3(angle.sin(), 0.0, angle.cos());
+            let normal = (outward * height + nalgebra_glm::vec3(0.0, radius, 0.0)).normalize();
+            let position = outward * radius * v + nalgebra_glm::vec3(0.0, (0.5 - v) * height, 0.0);
+            vertex(position, normal, nalgebra_glm::vec2(u, v))
+        });
+        mesh.append(Self::cap(radius, -height / 2.0, segments, false));
+        mesh
+    }
+
+    /// A cylinder along the Y axis with hemispherical ends.
+    /// The height is the length of the cylindrical part.
+    pub fn capsule(radius: f32, height: f32, segments: u32, rings: u32) -> Self {
+        let rings = rings.max(1);
+        let total_height = height + 2.0 * radius;
+        // Each hemisphere has `rings` bands and the cylinder is the band between them
+        Self::surface(segments.max(3), 2 * rings + 1, |u, v| {
+            let row = (v * (2 * rings + 1) as f32).round() as u32;
+            let (latitude, offset) = if row <= rings {
+                (row as f32 / rings as f32 * PI / 2.0, height / 2.0)
+            } else {
+                (
+                    PI / 2.0 + (row - rings - 1) as f32 / rings as f32 * PI / 2.0,
+                    -height / 2.0,
+                )
+            };
+            let normal = spherical(u * 2.0 * PI, latitude);
+            let position = normal * radius + nalgebra_glm::vec3(0.0, offset, 0.0);
+            let uv = nalgebra_glm::vec2(u, (total_height / 2.0 - position.y) / total_height);
+            vertex(position, normal, uv)
+        })
+    }
+
+    /// A torus lying on the XZ plane
+    pub fn torus(
+        major_radius: f32,
+        minor_radius: f32,
+        major_segments: u32,
+        minor_segments: u32,
+    ) -> Self {
+        Self::surface(major_segments.max(3), minor_segments.max(3), |u, v| {
+            let (around, tube) = (u * 2.0 * PI, -v * 2.0 * PI);
+            let outward = nalgebra_glm::vec3(around.sin(), 0.0, around.cos());
+            let normal = outward * tube.cos() + nalgebra_glm::Vec3::y() * tube.sin();
+            let position = outward * major_radius + normal * minor_radius;
+            vertex(position, normal, nalgebra_glm::vec2(u, v))
+        })
+    }
+
+    /// A terrain on the XZ plane from a row-major grid of heights,
+    /// with one vertex per height sample
+    pub fn terrain(
+        heights: &[f32],
+        columns: usize,
+        rows: usize,
+        width: f32,
+        depth: f32,
+        height_scale: f32,
+    ) -> Result<Self, TerrainError> {
+        if columns < 2 || rows < 2 {
+            return Err(TerrainError::TooFewSamples { columns, rows });
+        }
+        if columns.checked_mul(rows) != Some(heights.len()) {
+            return Err(TerrainError::HeightCountMismatch {
+                columns,
+                rows,
+                actual: heights.len(),
+            });
+        }
+        let height = |column: usize, row: usize| {
+            heights[row.min(rows - 1) * columns + column.min(columns - 1)] * height_scale
+        };
+        let (step_x, step_z) = (width / (columns - 1) as f32, depth / (rows - 1) as f32);
+        Ok(Self::surface(
+            columns as u32 - 1,
+            rows as u32 - 1,
+            |u, v| {
+                let column = (u * (columns - 1) as f32).round() as usize;
+                let row = (v * (rows - 1) as f32).round() as usize;
+                // Central differences, falling back to one sided ones at the edges
+                let (left, right) = (column.saturating_sub(1), column + 1);
+                let (up, down) = (row.saturating_sub(1), row + 1);
+                let slope_x = (height(right, row) - height(left, row))
+                    / ((right.min(columns - 1) - left) as f32 * step_x);
+                let slope_z = (height(column, down) - height(column, up))
+                    / ((down.min(rows - 1) - up) as f32 * step_z);
+                vertex(
+                    nalgebra_glm::vec3((u - 0.5) * width, height(column, row), (v - 0.5) * depth),
+                    nalgebra_glm::vec3(-slope_x, 1.0, -slope_z).normalize(),
+                    nalgebra_glm::vec2(u, v),
+                )
+            },
+        ))
+    }
+
+    pub fn aabb(&self) -> AxisAlignedBoundingBox {
+        AxisAlignedBoundingBox::from_vertices(&self.vertices)
+    }
+
+    /// Adds another mesh's triangles to this one
+    pub fn append(&mut self, other: MeshData) {
+        let offset = self.vertices.len() as u32;
+        self.vertices.extend(other.vertices);
+        self.indices
+            .extend(other.indices.into_iter().map(|index| index + offset));
+    }
+
+    /// Builds a grid of `columns` by `rows` quads from a function of normalized (u, v) coordinates.
+    /// The cross product of the v and u directions must point out of the front face.
+    fn surface(columns: u32, rows: u32, vertex: impl Fn(f32, f32) -> Vertex) -> Self {
+        let vertices = (0..=rows)
+            .flat_map(|row| (0..=columns).map(move |column| (column, row)))
+            .map(|(column, row)| vertex(column as f32 / columns as f32, row as f32 / rows as f32))
+            .collect();
+        let indices = (0..rows)
+            .flat_map(|row| (0..columns).map(move |column| (column, row)))
+            .flat_map(|(column, row)| {
+                let a = row * (columns + 1) + column;
+                let (b, c) = (a + 1, a + columns + 1);
+                let d = c + 1;
+                [a, c, b, b, c, d]
+            })
+            .collect();
+        Self::with_tangents(vertices, indices)
+    }
+
+    fn with_tangents(mut vertices: Vec<Vertex>, mut indices: Vec<u32>) -> Self {
+        generate_tangents(&mut vertices, &mut indices);
+        Self { vertices, indices }
+    }
+
+    /// A disc closing the end of a shape around the Y axis
+    fn cap(radius: f32, y: f32, segments: u32, facing_up: bool) -> Self {
+        let normal = if facing_up {
+            nalgebra_glm::Vec3::y()
+        } else {
+            -nalgebra_glm::Vec3::y()
+        };
+        let mut vertices = vec![vertex(
+            nalgebra_glm::vec3(0.0, y, 0.0),
+            normal,
+            nalgebra_glm::vec2(0.5, 0.5),
+        )];
+        vertices.extend((0..=segments).map(|segment| {
+            let angle = segment as f32 / segments as f32 * 2.0 * PI;
+            let (sin, cos) = angle.sin_cos();
+            vertex(
+                nalgebra_glm::vec3(sin * radius, y, cos * radius),
+                normal,
+                nalgebra_glm::vec2(0.5 + sin * 0.5, 0.5 + cos * 0.5),
+            )
+        }));
+        let indices = (1..=segments)
+            .flat_map(|segment| {
+                if facing_up {
+                    [0, segment, segment + 1]
+                } else {
+                    [0, segment + 1, segment]
+                }
+            })
+            .collect();
+        Self::with_tangents(vertices, indices)
+    }
+}
+
+fn vertex(
+    position: nalgebra_glm::Vec3,
+    normal: nalgebra_glm::Vec3,
+    uv: nalgebra_glm::Vec2,
+) -> Vertex {
+    Vertex {
+        position,
+        normal,
+        uv_0: uv,
+        ..Default::default()
+    }
+}
+
+/// The unit vector at a longitude around the Y axis, starting from +Z, and a latitude down from +Y
+fn spherical(longitude: f32, latitude: f32) -> nalgebra_glm::Vec3 {
+    nalgebra_glm::vec3(
+        latitude.sin() * longitude.sin(),
+        latitude.cos(),
+        latitude.sin() * longitude.cos(),
+    )
+}
+
+impl World {
+    /// Adds a mesh with a single primitive made from generated geometry, returning the mesh index
+    pub fn add_mesh(&mut self, mesh: MeshData, material_index: Option<usize>) -> usize {
+        let primitive = Primitive {
+            vertex_offset: self.vertices.len(),
+            index_offset: self.indices.len(),
+            number_of_vertices: mesh.vertices.len(),
+            number_of_indices: mesh.indices.len(),
+            topology: PrimitiveTopology::Triangles,
+            material_index,
+            ..Default::default()
+        };
+        self.vertices.extend(mesh.vertices);
+        self.indices.extend(mesh.indices);
+        let mesh_index = self.meshes.len();
+        self.meshes.push(Mesh {
+            primitives: vec![primitive],
+            ..Default::default()
+        });
+        mesh_index
+    }
+
+    /// Adds generated geometry as the node's mesh, along with its bounding box
+    pub fn add_mesh_to_node(
+        &mut self,
+        node_index: usize,
+        mesh: MeshData,
+        material_index: Option<usize>,
+    ) {
+        let aabb_index = self.aabbs.len();
+        self.aabbs.push(mesh.aabb());
+        let mesh_index = self.add_mesh(mesh, material_index);
+        let node = &mut self.nodes[node_index];
+        node.mesh_index = Some(mesh_index);
+        node.aabb_index = Some(aabb_index);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MeshData, TerrainError};
+    use crate::world::World;
+
+    fn generated_meshes() -> Vec<(&'static str, MeshData)> {
+        let heights = (0..25)
+            .map(|index| ((index % 5) as f32 * 0.7).sin() + (index / 5) as f32 * 0.2)
+            .collect::<Vec<_>>();
+        vec![
+            ("plane", MeshData::plane(2.0, 3.0, 4)),
+            ("cube", MeshData::cube(2.0)),
+            ("uv sphere", MeshData::uv_sphere(1.0, 16, 8)),
+            ("icosphere", MeshData::icosphere(1.0, 2)),
+            ("cylinder", MeshData::cylinder(1.0, 2.0, 12)),
+            ("cone", MeshData::cone(1.0, 2.0, 12)),
+            ("capsule", MeshData::capsule(0.5, 1.0, 12, 4)),
+            ("torus", MeshData::torus(1.0, 0.25, 16, 8)),
+            (
+                "terrain",
+                MeshData::terrain(&heights, 5, 5, 4.0, 4.0, 1.0).unwrap(),
+            ),
+        ]
+    }
+
+    #[test]
+    fn generated_meshes_are_well_formed() {
+        for (name, mesh) in generated_meshes() {
+            assert_eq!(mesh.indices.len() % 3, 0, "{name}");
+            for vertex in mesh.vertices.iter() {
+                assert!((vertex.normal.norm() - 1.0).abs() < 1e-4, "{name}");
+                assert!(
+                    vertex.tangent.xyz().dot(&vertex.normal).abs() < 1e-4,
+                    "{name}"
+                );
+                assert!(
+                    (0.0..=1.0).contains(&vertex.uv_0.x) && (0.0..=1.0).contains(&vertex.uv_0.y),
+                    "{name}"
+                );
+            }
+            for triangle in mesh.indices.chunks(3) {
+                let [a, b, c] = [0, 1, 2].map(|corner| mesh.vertices[triangle[corner] as usize]);
+                let face_normal = (b.position - a.position).cross(&(c.position - a.position));
+                // Triangles at the poles of a sphere collapse to a point
+                if face_normal.norm() < 1e-6 {
+                    continue;
+                }
+                let vertex_normal = a.normal + b.normal + c.normal;
+                assert!(
+                    face_normal.dot(&vertex_normal) > 0.0,
+                    "{name} has a triangle facing inwards"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn generated_meshes_have_expected_sizes() {
+        let cube = MeshData::cube(2.0);
+        assert_eq!((cube.vertices.len(), cube.indices.len()), (24, 36));
+        let aabb = cube.aabb();
+        assert_eq!(aabb.min, nalgebra_glm::vec3(-1.0, -1.0, -1.0));
+        assert_eq!(aabb.max, nalgebra_glm::vec3(1.0, 1.0, 1.0));
+
+        // Tangent generation splits the 42 shared vertices where the texture wraps around
+        let icosphere = MeshData::icosphere(2.0, 1);
+        assert_eq!(
+            (icosphere.vertices.len(), icosphere.indices.len()),
+            (57, 240)
+        );
+        assert!(icosphere
+            .vertices
+            .iter()
+            .all(|vertex| (vertex.position.norm() - 2.0).abs() < 1e-4));
+
+        let capsule = MeshData::capsule(0.5, 1.0, 8, 3);
+        let aabb = capsule.aabb();
+        assert!((aabb.max.y - 1.0).abs() < 1e-5 && (aabb.min.y + 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn malformed_heightmaps_are_rejected() {
+        assert_eq!(
+            MeshData::terrain(&[0.0; 3], 3, 1, 1.0, 1.0, 1.0).unwrap_err(),
+            TerrainError::TooFewSamples {
+                columns: 3,
+                rows: 1
+            }
+        );
+        assert_eq!(
+            MeshData::terrain(&[0.0; 5], 2, 2, 1.0, 1.0, 1.0).unwrap_err(),
+            TerrainError::HeightCountMismatch {
+                columns: 2,
+                rows: 2,
+                actual: 5
+            }
+        );
+    }
+
+    #[test]
+    fn generated_meshes_attach_to_nodes() {
+        let mut world = World::default();
+        let node_index = world.add_node();
+        world.add_mesh_to_node(node_index, MeshData::cube(1.0), None);
+        let node_index = world.add_node();
+        world.add_mesh_to_node(node_index, MeshData::uv_sphere(1.0, 8, 4), None);
+
+        let primitive = &world.meshes[1].primitives[0];
+        assert_eq!(primitive.vertex_offset, 24);
+        assert_eq!(primitive.index_offset, 36);
+        assert_eq!(world.nodes[node_index].aabb_index, Some(1));
+        assert_eq!(world.primitive_triangles(primitive).len(), 8 * 4 * 2);
+    }
+}
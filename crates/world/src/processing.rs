@@ -0,0 +1,591 @@
+use crate::world::{PrimitiveTopology, Vertex, World};
+use std::collections::HashMap;
+
+/// The number of vertices the cache optimizer assumes the GPU keeps around
+pub const VERTEX_CACHE_SIZE: usize = 32;
+
+/// Gives every triangle its own vertices so that each can have the triangle's normal.
+/// Returns the index of the original vertex that each new vertex was copied from,
+/// which can be used to remap other per-vertex data such as morph targets.
+pub fn compute_flat_normals(vertices: &mut Vec<Vertex>, indices: &mut Vec<u32>) -> Vec<u32> {
+    let sources = triangle_indices(indices, vertices.len());
+    let mut flat_vertices = sources
+        .iter()
+        .map(|index| vertices[*index as usize])
+        .collect::<Vec<_>>();
+    flat_vertices.chunks_exact_mut(3).for_each(|triangle| {
+        let normal = face_normal(&triangle[0], &triangle[1], &triangle[2]).normalize();
+        let normal = if normal.iter().all(|value| value.is_finite()) {
+            normal
+        } else {
+            nalgebra_glm::Vec3::y()
+        };
+        triangle
+            .iter_mut()
+            .for_each(|vertex| vertex.normal = normal);
+    });
+    *vertices = flat_vertices;
+    *indices = (0..vertices.len() as u32).collect();
+    sources
+}
+
+/// Sets each vertex normal to the area weighted average of the faces around it.
+/// Vertices at the same position are smoothed together, so seams in the texture coordinates
+/// do not show up as creases.
+pub fn compute_smooth_normals(vertices: &mut [Vertex], indices: &[u32]) {
+    let triangles = triangle_indices(indices, vertices.len());
+    let snap = PositionSnap::new(vertices);
+    let mut normals = HashMap::<[i64; 3], nalgebra_glm::Vec3>::new();
+    triangles.chunks_exact(3).for_each(|triangle| {
+        let [a, b, c] = [0, 1, 2].map(|corner| vertices[triangle[corner] as usize]);
+        // The cross product's length is twice the triangle's area
+        let normal = face_normal(&a, &b, &c);
+        [a, b, c].iter().for_each(|vertex| {
+            *normals
+                .entry(snap.key(vertex))
+                .or_insert_with(nalgebra_glm::Vec3::zeros) += normal;
+        });
+    });
+    vertices.iter_mut().for_each(|vertex| {
+        vertex.normal = normals
+            .get(&snap.key(vertex))
+            .map(|normal| normal.normalize())
+            .filter(|normal| normal.iter().all(|value| value.is_finite()))
+            .unwrap_or_else(nalgebra_glm::Vec3::y);
+    });
+}
+
+/// Turns a triangle strip or fan into a triangle list with the same winding,
+/// where empty indices mean the vertices are used in order.
+/// Triangle lists are returned unchanged and other topologies have no triangles.
+pub fn triangulate(
+    topology: PrimitiveTopology,
+    indices: &[u32],
+    number_of_vertices: usize,
+) -> Vec<u32> {
+    let strip = if indices.is_empty() {
+        (0..number_of_vertices as u32).collect::<Vec<_>>()
+    } else {
+        indices.to_vec()
+    };
+    let number_of_triangles = strip.len().saturating_sub(2);
+    match topology {
+        PrimitiveTopology::Triangles => indices.to_vec(),
+        // Every other triangle of a strip is flipped to keep the winding consistent
+        PrimitiveTopology::TriangleStrip => (0..number_of_triangles)
+            .flat_map(|triangle| {
+                let flip = triangle % 2;
+                [
+                    strip[triangle],
+                    strip[triangle + 1 + flip],
+                    strip[triangle + 2 - flip],
+                ]
+            })
+            .collect(),
+        PrimitiveTopology::TriangleFan => (0..number_of_triangles)
+            .flat_map(|triangle| [strip[triangle + 1], strip[triangle + 2], strip[0]])
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Generates MikkTSpace tangents from the first texture coordinates,
+/// matching the tangents that normal maps are baked against. Normals must already be present.
+/// Vertices whose triangles need different tangents are split, so this returns the index
+/// of the original vertex that each vertex was copied from, which can be used to remap
+/// other per-vertex data such as morph targets.
+pub fn generate_tangents(vertices: &mut Vec<Vertex>, indices: &mut [u32]) -> Vec<u32> {
+    let triangles = triangle_indices(indices, vertices.len());
+    let mut geometry = TangentGeometry {
+        vertices,
+        triangles: &triangles,
+        tangents: vec![None; triangles.len()],
+    };
+    bevy_mikktspace::generate_tangents(&mut geometry);
+    let corner_tangents = geometry.tangents;
+
+    let mut sources = (0..vertices.len() as u32).collect::<Vec<_>>();
+    let mut assigned = vec![false; vertices.len()];
+    let mut splits = HashMap::<(u32, [u32; 4]), u32>::new();
+    corner_tangents
+        .into_iter()
+        .enumerate()
+        .for_each(|(corner, tangent)| {
+            let index = triangles[corner] as usize;
+            // Triangles without a usable texture mapping get an arbitrary tangent,
+            // so it is made perpendicular to the normal like any other
+            let normal = vertices[index].normal;
+            let tangent = tangent.unwrap_or_else(|| nalgebra_glm::vec4(1.0, 0.0, 0.0, 1.0));
+            let mut orthogonal = tangent.xyz() - normal * normal.dot(&tangent.xyz());
+            if orthogonal.norm() <= f32::EPSILON {
+                orthogonal = perpendicular(&normal);
+            }
+            let orthogonal = orthogonal.normalize();
+            let tangent = nalgebra_glm::vec4(orthogonal.x, orthogonal.y, orthogonal.z, tangent.w);
+            if !assigned[index] {
+                assigned[index] = true;
+                vertices[index].tangent = tangent;
+                return;
+            }
+            if vertices[index].tangent == tangent {
+                return;
+            }
+            let split_index = *splits
+                .entry((index as u32, tangent.map(f32::to_bits).into()))
+                .or_insert_with(|| {
+                    let mut vertex = vertices[index];
+                    vertex.tangent = tangent;
+                    vertices.push(vertex);
+                    sources.push(index as u32);
+                    vertices.len() as u32 - 1
+                });
+            // Corners only share a vertex through the indices, so unindexed vertices are never split
+            indices[corner] = split_index;
+        });
+    sources
+}
+
+struct TangentGeometry<'a> {
+    vertices: &'a [Vertex],
+    triangles: &'a [u32],
+    tangents: Vec<Option<nalgebra_glm::Vec4>>,
+}
+
+impl TangentGeometry<'_> {
+    fn vertex(&self, face: usize, vert: usize) -> &Vertex {
+        &self.vertices[self.triangles[face * 3 + vert] as usize]
+    }
+}
+
+impl bevy_mikktspace::Geometry for TangentGeometry<'_> {
+    fn num_faces(&self) -> usize {
+        self.triangles.len() / 3
+    }
+
+    fn num_vertices_of_face(&self, _face: usize) -> usize {
+        3
+    }
+
+    fn position(&self, face: usize, vert: usize) -> [f32; 3] {
+        self.vertex(face, vert).position.into()
+    }
+
+    fn normal(&self, face: usize, vert: usize) -> [f32; 3] {
+        self.vertex(face, vert).normal.into()
+    }
+
+    fn tex_coord(&self, face: usize, vert: usize) -> [f32; 2] {
+        self.vertex(face, vert).uv_0.into()
+    }
+
+    fn set_tangent_encoded(&mut self, tangent: [f32; 4], face: usize, vert: usize) {
+        self.tangents[face * 3 + vert] = Some(tangent.into());
+    }
+}
+
+/// Merges vertices whose attributes are identical, rewriting the indices to match.
+/// Returns the index of the original vertex that each remaining vertex was taken from.
+pub fn weld_vertices(vertices: &mut Vec<Vertex>, indices: &mut Vec<u32>) -> Vec<u32> {
+    let mut welded = HashMap::<&[u8], u32>::new();
+    let mut sources = Vec::new();
+    let mut remapped = Vec::with_capacity(vertices.len());
+    vertices.iter().enumerate().for_each(|(index, vertex)| {
+        let welded_index = *welded.entry(bytemuck::bytes_of(vertex)).or_insert_with(|| {
+            sources.push(index as u32);
+            sources.len() as u32 - 1
+        });
+        remapped.push(welded_index);
+    });
+    *indices = if indices.is_empty() {
+        remapped
+    } else {
+        indices
+            .iter()
+            .map(|index| remapped[*index as usize])
+            .collect()
+    };
+    *vertices = sources
+        .iter()
+        .map(|index| vertices[*index as usize])
+        .collect();
+    sources
+}
+
+/// Reorders triangles so that vertices are reused while they are still in the GPU's
+/// post-transform cache, using Tom Forsyth's linear-speed vertex cache optimization.
+/// The triangles themselves and their winding are unchanged.
+pub fn optimize_vertex_cache(indices: &mut [u32], number_of_vertices: usize) {
+    let number_of_triangles = indices.len() / 3;
+    if number_of_triangles == 0 {
+        return;
+    }
+
+    let mut vertex_triangles = vec![Vec::new(); number_of_vertices];
+    indices
+        .chunks_exact(3)
+        .enumerate()
+        .for_each(|(triangle, corners)| {
+            corners
+                .iter()
+                .for_each(|index| vertex_triangles[*index as usize].push(triangle));
+        });
+    let mut cache_positions = vec![None; number_of_vertices];
+    let mut vertex_scores = (0..number_of_vertices)
+        .map(|vertex| vertex_score(None, vertex_triangles[vertex].len()))
+        .collect::<Vec<_>>();
+    let triangle_score = |triangle: usize, vertex_scores: &[f32]| {
+        indices[triangle * 3..triangle * 3 + 3]
+            .iter()
+            .map(|index| vertex_scores[*index as usize])
+            .sum::<f32>()
+    };
+    let mut triangle_scores = (0..number_of_triangles)
+        .map(|triangle| triangle_score(triangle, &vertex_scores))
+        .collect::<Vec<_>>();
+    let mut emitted = vec![false; number_of_triangles];
+    let mut cache: Vec<u32> = Vec::with_capacity(VERTEX_CACHE_SIZE + 3);
+    let mut optimized = Vec::with_capacity(indices.len());
+    let mut best_triangle = None;
+
+    for _ in 0..number_of_triangles {
+        // Fall back to searching every triangle when nothing in the cache has work left
+        let triangle = best_triangle.unwrap_or_else(|| {
+            (0..number_of_triangles)
+                .filter(|triangle| !emitted[*triangle])
+                .max_by(|a, b| triangle_scores[*a].total_cmp(&triangle_scores[*b]))
+                .unwrap()
+        });
+        emitted[triangle] = true;
+        let corners = [
+            indices[triangle * 3],
+            indices[triangle * 3 + 1],
+            indices[triangle * 3 + 2],
+        ];
+        optimized.extend(corners);
+        corners.iter().for_each(|index| {
+            vertex_triangles[*index as usize].retain(|other| *other != triangle);
+        });
+
+        let mut updated_cache = corners.to_vec();
+        updated_cache.extend(cache.iter().filter(|index| !corners.contains(index)));
+        let evicted = updated_cache.split_off(updated_cache.len().min(VERTEX_CACHE_SIZE));
+        evicted
+            .iter()
+            .for_each(|index| cache_positions[*index as usize] = None);
+        updated_cache
+            .iter()
+            .enumerate()
+            .for_each(|(position, index)| cache_positions[*index as usize] = Some(position));
+        cache = updated_cache;
+
+        let touched = cache
+            .iter()
+            .chain(evicted.iter())
+            .copied()
+            .collect::<Vec<_>>();
+        touched.iter().for_each(|index| {
+            let index = *index as usize;
+            vertex_scores[index] =
+                vertex_score(cache_positions[index], vertex_triangles[index].len());
+        });
+        best_triangle = None;
+        let mut best_score = f32::MIN;
+        touched.iter().for_each(|index| {
+            vertex_triangles[*index as usize].iter().for_each(|other| {
+                triangle_scores[*other] = triangle_score(*other, &vertex_scores);
+                if triangle_scores[*other] > best_score {
+                    best_score = triangle_scores[*other];
+                    best_triangle = Some(*other);
+                }
+            });
+        });
+    }
+
+    indices[..optimized.len()].copy_from_slice(&optimized);
+}
+
+/// The average number of vertices transformed per triangle with a FIFO cache of the given size.
+/// Lower is better, with 0.5 being ideal for large grids and 3.0 the worst case.
+pub fn average_cache_miss_ratio(indices: &[u32], cache_size: usize) -> f32 {
+    let number_of_triangles = indices.len() / 3;
+    if number_of_triangles == 0 {
+        return 0.0;
+    }
+    let mut cache = std::collections::VecDeque::with_capacity(cache_size);
+    let misses = indices
+        .iter()
+        .filter(|index| {
+            if cache.contains(*index) {
+                return false;
+            }
+            if cache.len() == cache_size {
+                cache.pop_front();
+            }
+            cache.push_back(**index);
+            true
+        })
+        .count();
+    misses as f32 / number_of_triangles as f32
+}
+
+impl World {
+    /// Reorders the indices of every indexed triangle list for vertex cache locality
+    pub fn optimize_vertex_caches(&mut self) {
+        let Self {
+            meshes, indices, ..
+        } = self;
+        meshes
+            .iter()
+            .flat_map(|mesh| mesh.primitives.iter())
+            .filter(|primitive| matches!(primitive.topology, PrimitiveTopology::Triangles))
+            .for_each(|primitive| {
+                optimize_vertex_cache(
+                    &mut indices[primitive.index_offset
+                        ..primitive.index_offset + primitive.number_of_indices],
+                    primitive.number_of_vertices,
+                );
+            });
+    }
+}
+
+// Scores range from 0 for vertices with nothing left to draw to a little over 2
+fn vertex_score(cache_position: Option<usize>, remaining_triangles: usize) -> f32 {
+    const CACHE_DECAY_POWER: f32 = 1.5;
+    const LAST_TRIANGLE_SCORE: f32 = 0.75;
+    const VALENCE_BOOST_SCALE: f32 = 2.0;
+    const VALENCE_BOOST_POWER: f32 = 0.5;
+
+    if remaining_triangles == 0 {
+        return 0.0;
+    }
+    let cache_score = match cache_position {
+        None => 0.0,
+        // The most recent triangle's vertices get a fixed score so that
+        // the next triangle does not just reuse its newest edge, making strips
+        Some(position) if position < 3 => LAST_TRIANGLE_SCORE,
+        Some(position) => {
+            let scale = 1.0 / (VERTEX_CACHE_SIZE - 3) as f32;
+            (1.0 - (position - 3) as f32 * scale).powf(CACHE_DECAY_POWER)
+        }
+    };
+    // Vertices with few triangles left are preferred so that they can leave the cache
+    cache_score + VALENCE_BOOST_SCALE * (remaining_triangles as f32).powf(-VALENCE_BOOST_POWER)
+}
+
+fn triangle_indices(indices: &[u32], number_of_vertices: usize) -> Vec<u32> {
+    let mut triangles = if indices.is_empty() {
+        (0..number_of_vertices as u32).collect::<Vec<_>>()
+    } else {
+        indices.to_vec()
+    };
+    triangles.truncate(triangles.len() / 3 * 3);
+    triangles
+}
+
+fn face_normal(a: &Vertex, b: &Vertex, c: &Vertex) -> nalgebra_glm::Vec3 {
+    (b.position - a.position).cross(&(c.position - a.position))
+}
+
+/// Groups positions that are within a millionth of the mesh's size of each other,
+/// since vertices along seams are often computed separately and differ by rounding
+struct PositionSnap {
+    spacing: f32,
+}
+
+impl PositionSnap {
+    fn new(vertices: &[Vertex]) -> Self {
+        let extent = vertices
+            .iter()
+            .map(|vertex| vertex.position.abs().max())
+            .fold(0.0, f32::max);
+        Self {
+            spacing: (extent * 1e-6).max(f32::MIN_POSITIVE),
+        }
+    }
+
+    fn key(&self, vertex: &Vertex) -> [i64; 3] {
+        [0, 1, 2].map(|axis| (vertex.position[axis] / self.spacing).round() as i64)
+    }
+}
+
+fn perpendicular(normal: &nalgebra_glm::Vec3) -> nalgebra_glm::Vec3 {
+    let axis = if normal.x.abs() < 0.9 {
+        nalgebra_glm::Vec3::x()
+    } else {
+        nalgebra_glm::Vec3::y()
+    };
+    normal.cross(&axis).cross(normal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        average_cache_miss_ratio, compute_flat_normals, compute_smooth_normals, generate_tangents,
+        optimize_vertex_cache, triangulate, weld_vertices,
+    };
+    use crate::{
+        procedural::MeshData,
+        world::{PrimitiveTopology, Vertex},
+    };
+
+    #[test]
+    fn missing_normals_are_computed() {
+        let sphere = MeshData::uv_sphere(1.0, 16, 8);
+        let mut vertices = sphere.vertices.clone();
+        vertices
+            .iter_mut()
+            .for_each(|vertex| vertex.normal = nalgebra_glm::Vec3::zeros());
+        compute_smooth_normals(&mut vertices, &sphere.indices);
+        vertices
+            .iter()
+            .zip(sphere.vertices.iter())
+            .for_each(|(smooth, expected)| {
+                assert!(smooth.normal.dot(&expected.normal) > 0.99);
+            });
+
+        let cube = MeshData::cube(1.0);
+        let (mut vertices, mut indices) = (cube.vertices.clone(), cube.indices.clone());
+        vertices
+            .iter_mut()
+            .for_each(|vertex| vertex.normal = nalgebra_glm::Vec3::zeros());
+        let sources = compute_flat_normals(&mut vertices, &mut indices);
+        assert_eq!(vertices.len(), 36);
+        assert_eq!(indices, (0..36).collect::<Vec<_>>());
+        sources
+            .iter()
+            .zip(vertices.iter())
+            .for_each(|(source, flat)| {
+                assert_eq!(flat.normal, cube.vertices[*source as usize].normal);
+            });
+    }
+
+    #[test]
+    fn tangents_follow_texture_coordinates() {
+        let mut plane = MeshData::plane(2.0, 2.0, 2);
+        let number_of_vertices = plane.vertices.len();
+        generate_tangents(&mut plane.vertices, &mut plane.indices);
+        assert_eq!(plane.vertices.len(), number_of_vertices);
+        plane.vertices.iter().for_each(|vertex| {
+            assert!((vertex.tangent.xyz() - nalgebra_glm::Vec3::x()).norm() < 1e-5);
+            // Texture coordinates increase towards +Z, which is the bitangent
+            let bitangent = vertex.normal.cross(&vertex.tangent.xyz()) * vertex.tangent.w;
+            assert!((bitangent - nalgebra_glm::Vec3::z()).norm() < 1e-5);
+        });
+
+        let mut sphere = MeshData::uv_sphere(1.0, 16, 8);
+        generate_tangents(&mut sphere.vertices, &mut sphere.indices);
+        sphere.vertices.iter().for_each(|vertex| {
+            assert!((vertex.tangent.xyz().norm() - 1.0).abs() < 1e-4);
+            assert!(vertex.tangent.xyz().dot(&vertex.normal).abs() < 1e-4);
+            assert_eq!(vertex.tangent.w.abs(), 1.0);
+        });
+    }
+
+    #[test]
+    fn mirrored_texture_coordinates_split_vertices() {
+        // Two quads sharing an edge, with the texture mirrored across it
+        let mut vertices = [
+            (0.0, 0.0, 0.0),
+            (1.0, 0.0, 1.0),
+            (2.0, 0.0, 0.0),
+            (0.0, 1.0, 0.0),
+            (1.0, 1.0, 1.0),
+            (2.0, 1.0, 0.0),
+        ]
+        .map(|(x, z, u)| Vertex {
+            position: nalgebra_glm::vec3(x, 0.0, z),
+            normal: nalgebra_glm::Vec3::y(),
+            uv_0: nalgebra_glm::vec2(u, z),
+            ..bytemuck::Zeroable::zeroed()
+        })
+        .to_vec();
+        let original = vec![0, 4, 1, 0, 3, 4, 1, 5, 2, 1, 4, 5];
+        let mut indices = original.clone();
+        let sources = generate_tangents(&mut vertices, &mut indices);
+
+        // The vertices on the shared edge get a copy for each side
+        assert_eq!(vertices.len(), 8);
+        assert_eq!(sources, vec![0, 1, 2, 3, 4, 5, 1, 4]);
+        indices.iter().enumerate().for_each(|(corner, index)| {
+            assert_eq!(sources[*index as usize], original[corner]);
+            let expected = if corner < 6 { 1.0 } else { -1.0 };
+            let tangent = vertices[*index as usize].tangent;
+            assert!((tangent.x - expected).abs() < 1e-5, "{tangent:?}");
+        });
+    }
+
+    #[test]
+    fn strips_and_fans_become_triangle_lists() {
+        assert_eq!(
+            triangulate(PrimitiveTopology::TriangleStrip, &[], 5),
+            vec![0, 1, 2, 1, 3, 2, 2, 3, 4]
+        );
+        assert_eq!(
+            triangulate(PrimitiveTopology::TriangleFan, &[4, 5, 6, 7], 8),
+            vec![5, 6, 4, 6, 7, 4]
+        );
+        assert_eq!(
+            triangulate(PrimitiveTopology::Triangles, &[0, 1, 2], 3),
+            vec![0, 1, 2]
+        );
+        assert!(triangulate(PrimitiveTopology::Lines, &[], 4).is_empty());
+    }
+
+    #[test]
+    fn welding_merges_identical_vertices() {
+        let cube = MeshData::cube(1.0);
+        let mut vertices = cube
+            .indices
+            .iter()
+            .map(|index| cube.vertices[*index as usize])
+            .collect::<Vec<_>>();
+        let mut indices = Vec::new();
+        let sources = weld_vertices(&mut vertices, &mut indices);
+        assert_eq!(vertices.len(), 24);
+        assert_eq!(sources.len(), 24);
+        indices
+            .chunks_exact(3)
+            .zip(cube.indices.chunks_exact(3))
+            .for_each(|(welded, original)| {
+                (0..3).for_each(|corner| {
+                    assert_eq!(
+                        vertices[welded[corner] as usize].position,
+                        cube.vertices[original[corner] as usize].position
+                    );
+                });
+            });
+    }
+
+    #[test]
+    fn cache_optimization_reduces_misses() {
+        let plane = MeshData::plane(1.0, 1.0, 32);
+        // Interleave triangles from distant parts of the grid to defeat the cache
+        let triangles = plane.indices.chunks_exact(3).collect::<Vec<_>>();
+        let (first, second) = triangles.split_at(triangles.len() / 2);
+        let mut indices = first
+            .iter()
+            .zip(second.iter())
+            .flat_map(|(a, b)| a.iter().chain(b.iter()).copied())
+            .collect::<Vec<_>>();
+        let before = average_cache_miss_ratio(&indices, 16);
+
+        optimize_vertex_cache(&mut indices, plane.vertices.len());
+        let after = average_cache_miss_ratio(&indices, 16);
+        assert!(after < before, "{after} is not lower than {before}");
+        assert!(after < 0.8, "{after}");
+
+        let sorted = |indices: &[u32]| {
+            let mut triangles = indices
+                .chunks_exact(3)
+                .map(|triangle| {
+                    // Rotate each triangle to start with its smallest index, preserving winding
+                    let start = (0..3).min_by_key(|corner| triangle[*corner]).unwrap();
+                    [0, 1, 2].map(|offset| triangle[(start + offset) % 3])
+                })
+                .collect::<Vec<_>>();
+            triangles.sort();
+            triangles
+        };
+        assert_eq!(sorted(&indices), sorted(&plane.indices));
+    }
+}
@@ -0,0 +1,385 @@
+use crate::world::{AxisAlignedBoundingBox, Primitive, PrimitiveTopology, World};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Ray {
+    pub origin: nalgebra_glm::Vec3,
+    pub direction: nalgebra_glm::Vec3,
+}
+
+impl Ray {
+    pub fn new(origin: nalgebra_glm::Vec3, direction: nalgebra_glm::Vec3) -> Self {
+        Self {
+            origin,
+            direction: direction.normalize(),
+        }
+    }
+
+    /// Builds a world space ray passing through a pixel of the viewport.
+    /// The screen position is measured in pixels from the top left corner.
+    pub fn from_screen(
+        screen_position: &nalgebra_glm::Vec2,
+        viewport_size: &nalgebra_glm::Vec2,
+        projection: &nalgebra_glm::Mat4,
+        view: &nalgebra_glm::Mat4,
+    ) -> Self {
+        let ndc = nalgebra_glm::vec2(
+            (2.0 * screen_position.x / viewport_size.x) - 1.0,
+            1.0 - (2.0 * screen_position.y / viewport_size.y),
+        );
+        let inverse_view_projection = nalgebra_glm::inverse(&(projection * view));
+        let unproject = |depth: f32| {
+            let point = inverse_view_projection * nalgebra_glm::vec4(ndc.x, ndc.y, depth, 1.0);
+            point.xyz() / point.w
+        };
+        // The midpoint is used instead of the far plane so that infinite projections still unproject
+        let (near, far) = (unproject(0.0), unproject(0.5));
+        Self::new(near, far - near)
+    }
+
+    pub fn at(&self, distance: f32) -> nalgebra_glm::Vec3 {
+        self.origin + self.direction * distance
+    }
+
+    /// Transforms the ray without renormalizing, so distances along the
+    /// transformed ray match distances along the original one
+    pub fn transform(&self, matrix: &nalgebra_glm::Mat4) -> Self {
+        Self {
+            origin: (matrix * self.origin.push(1.0)).xyz(),
+            direction: (matrix * self.direction.push(0.0)).xyz(),
+        }
+    }
+
+    /// Returns the distance to the first intersection with the box,
+    /// or zero if the ray starts inside of it
+    pub fn intersect_aabb(&self, aabb: &AxisAlignedBoundingBox) -> Option<f32> {
+        let mut near = 0.0_f32;
+        let mut far = f32::INFINITY;
+        for axis in 0..3 {
+            let inverse_direction = 1.0 / self.direction[axis];
+            let mut entry = (aabb.min[axis] - self.origin[axis]) * inverse_direction;
+            let mut exit = (aabb.max[axis] - self.origin[axis]) * inverse_direction;
+            if entry.is_nan() || exit.is_nan() {
+                // The ray is parallel to this slab and starts on its boundary
+                continue;
+            }
+            if entry > exit {
+                std::mem::swap(&mut entry, &mut exit);
+            }
+            near = near.max(entry);
+            far = far.min(exit);
+            if near > far {
+                return None;
+            }
+        }
+        Some(near)
+    }
+
+    /// Möller-Trumbore intersection, returning the distance
+    /// and the barycentric coordinates of the hit
+    pub fn intersect_triangle(
+        &self,
+        a: &nalgebra_glm::Vec3,
+        b: &nalgebra_glm::Vec3,
+        c: &nalgebra_glm::Vec3,
+    ) -> Option<(f32, nalgebra_glm::Vec3)> {
+        let edge_ab = b - a;
+        let edge_ac = c - a;
+        let p = self.direction.cross(&edge_ac);
+        let determinant = edge_ab.dot(&p);
+        if determinant.abs() < f32::EPSILON {
+            return None;
+        }
+        let inverse_determinant = 1.0 / determinant;
+        let to_origin = self.origin - a;
+        let u = to_origin.dot(&p) * inverse_determinant;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+        let q = to_origin.cross(&edge_ab);
+        let v = self.direction.dot(&q) * inverse_determinant;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+        let distance = edge_ac.dot(&q) * inverse_determinant;
+        if distance < 0.0 {
+            return None;
+        }
+        Some((distance, nalgebra_glm::vec3(1.0 - u - v, u, v)))
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct RayHit {
+    pub graph_node_index: petgraph::graph::NodeIndex,
+    pub node_index: usize,
+    pub primitive_index: usize,
+    pub triangle_index: usize,
+    pub distance: f32,
+    pub position: nalgebra_glm::Vec3,
+    pub barycentrics: nalgebra_glm::Vec3,
+    pub normal: nalgebra_glm::Vec3,
+}
+
+impl World {
+    /// Builds a ray through a pixel using the scene's default camera, if it has one
+    pub fn screen_ray(
+        &self,
+        scene_index: usize,
+        screen_position: &nalgebra_glm::Vec2,
+        viewport_size: &nalgebra_glm::Vec2,
+    ) -> Option<Ray> {
+        let (_, projection, view) = crate::world::create_camera_matrices(
+            self,
+            &self.scenes[scene_index],
+            viewport_size.x / viewport_size.y.max(1.0),
+        )?;
+        Some(Ray::from_screen(
+            screen_position,
+            viewport_size,
+            &projection,
+            &view,
+        ))
+    }
+
+    pub fn pick(
+        &self,
+        scene_index: usize,
+        screen_position: &nalgebra_glm::Vec2,
+        viewport_size: &nalgebra_glm::Vec2,
+    ) -> Option<RayHit> {
+        let ray = self.screen_ray(scene_index, screen_position, viewport_size)?;
+        self.raycast(scene_index, &ray)
+    }
+
+    /// Returns the closest triangle hit in the scene. Node bounding boxes
+    /// are tested before any triangles are, unless the node is morphed.
+    pub fn raycast(&self, scene_index: usize, ray: &Ray) -> Option<RayHit> {
+        self.scenes[scene_index]
+            .graph
+            .node_indices()
+            .fold(None, |closest_hit, graph_node_index| {
+                let max_distance = closest_hit.map_or(f32::INFINITY, |hit: RayHit| hit.distance);
+                self.raycast_node(scene_index, graph_node_index, ray, max_distance)
+                    .or(closest_hit)
+            })
+    }
+
+    /// Returns the closest hit against a single node's mesh that is nearer than `max_distance`
+    pub fn raycast_node(
+        &self,
+        scene_index: usize,
+        graph_node_index: petgraph::graph::NodeIndex,
+        ray: &Ray,
+        max_distance: f32,
+    ) -> Option<RayHit> {
+        let scene = &self.scenes[scene_index];
+        let node_index = scene.graph[graph_node_index];
+        let node = &self.nodes[node_index];
+        let mesh_index = node.mesh_index?;
+        let global_transform = self.global_transform(&scene.graph, graph_node_index);
+        let local_ray = ray.transform(&nalgebra_glm::inverse(&global_transform));
+        let weights = self.node_morph_weights(node_index);
+        // Morphed vertices can move past the bounds of the unmorphed mesh
+        let morphed = weights.iter().any(|weight| *weight != 0.0);
+        if let Some(aabb_index) = node.aabb_index.filter(|_| !morphed) {
+            match local_ray.intersect_aabb(&self.aabbs[aabb_index]) {
+                Some(distance) if distance <= max_distance => {}
+                _ => return None,
+            }
+        }
+        let mut closest_hit: Option<RayHit> = None;
+        self.meshes[mesh_index]
+            .primitives
+            .iter()
+            .enumerate()
+            .for_each(|(primitive_index, primitive)| {
+                let blended_vertices;
+                let vertices = if morphed {
+                    blended_vertices = self.blend_morph_targets(primitive, weights);
+                    &blended_vertices[..]
+                } else {
+                    &self.vertices[primitive.vertex_offset
+                        ..(primitive.vertex_offset + primitive.number_of_vertices)]
+                };
+                self.primitive_triangles(primitive)
+                    .into_iter()
+                    .enumerate()
+                    .for_each(|(triangle_index, triangle)| {
+                        let [a, b, c] = triangle.map(|index| vertices[index as usize].position);
+                        let Some((distance, barycentrics)) =
+                            local_ray.intersect_triangle(&a, &b, &c)
+                        else {
+                            return;
+                        };
+                        let closest_distance = closest_hit.map_or(max_distance, |hit| hit.distance);
+                        if distance >= closest_distance {
+                            return;
+                        }
+                        let [a, b, c] =
+                            [a, b, c].map(|position| (global_transform * position.push(1.0)).xyz());
+                        closest_hit = Some(RayHit {
+                            graph_node_index,
+                            node_index,
+                            primitive_index,
+                            triangle_index,
+                            distance,
+                            position: ray.at(distance),
+                            barycentrics,
+                            normal: (b - a).cross(&(c - a)).normalize(),
+                        });
+                    });
+            });
+        closest_hit
+    }
+
+    /// Returns the triangles of a primitive as indices relative to its first vertex.
+    /// Non-indexed primitives use their vertices in order, and primitives
+    /// that are not made of triangles produce no triangles.
+    pub fn primitive_triangles(&self, primitive: &Primitive) -> Vec<[u32; 3]> {
+        let indices = if primitive.number_of_indices > 0 {
+            self.indices
+                [primitive.index_offset..(primitive.index_offset + primitive.number_of_indices)]
+                .to_vec()
+        } else {
+            (0..primitive.number_of_vertices as u32).collect()
+        };
+        match primitive.topology {
+            PrimitiveTopology::Triangles => indices
+                .chunks_exact(3)
+                .map(|triangle| [triangle[0], triangle[1], triangle[2]])
+                .collect(),
+            PrimitiveTopology::TriangleStrip => indices
+                .windows(3)
+                .enumerate()
+                .map(|(index, triangle)| {
+                    if index % 2 == 0 {
+                        [triangle[0], triangle[1], triangle[2]]
+                    } else {
+                        [triangle[1], triangle[0], triangle[2]]
+                    }
+                })
+                .collect(),
+            PrimitiveTopology::TriangleFan => indices
+                .windows(2)
+                .skip(1)
+                .map(|edge| [indices[0], edge[0], edge[1]])
+                .collect(),
+            PrimitiveTopology::Points
+            | PrimitiveTopology::Lines
+            | PrimitiveTopology::LineLoop
+            | PrimitiveTopology::LineStrip => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Ray;
+    use crate::world::{
+        AxisAlignedBoundingBox, Mesh, MorphTargetVertex, Primitive, Scene, Vertex, World,
+    };
+
+    #[test]
+    fn ray_hits_triangle_with_barycentrics() {
+        let ray = Ray::new(
+            nalgebra_glm::vec3(0.25, 0.25, 5.0),
+            nalgebra_glm::vec3(0.0, 0.0, -1.0),
+        );
+        let (distance, barycentrics) = ray
+            .intersect_triangle(
+                &nalgebra_glm::vec3(0.0, 0.0, 0.0),
+                &nalgebra_glm::vec3(1.0, 0.0, 0.0),
+                &nalgebra_glm::vec3(0.0, 1.0, 0.0),
+            )
+            .unwrap();
+        assert!((distance - 5.0).abs() < 1e-5);
+        assert!((barycentrics - nalgebra_glm::vec3(0.5, 0.25, 0.25)).norm() < 1e-5);
+    }
+
+    #[test]
+    fn ray_misses_box_behind_it() {
+        let aabb = AxisAlignedBoundingBox::new(
+            nalgebra_glm::vec3(-1.0, -1.0, -1.0),
+            nalgebra_glm::vec3(1.0, 1.0, 1.0),
+        );
+        let towards = Ray::new(
+            nalgebra_glm::vec3(0.0, 0.0, 5.0),
+            nalgebra_glm::vec3(0.0, 0.0, -1.0),
+        );
+        let away = Ray::new(
+            nalgebra_glm::vec3(0.0, 0.0, 5.0),
+            nalgebra_glm::vec3(0.0, 0.0, 1.0),
+        );
+        assert_eq!(towards.intersect_aabb(&aabb), Some(4.0));
+        assert_eq!(away.intersect_aabb(&aabb), None);
+    }
+
+    #[test]
+    fn raycast_returns_hit_node_in_world_space() {
+        let vertices = [(-1.0, -1.0), (1.0, -1.0), (0.0, 1.0)]
+            .iter()
+            .map(|(x, y)| Vertex {
+                position: nalgebra_glm::vec3(*x, *y, 0.0),
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+        let mut world = World {
+            aabbs: vec![AxisAlignedBoundingBox::from_vertices(&vertices)],
+            vertices,
+            indices: vec![0, 1, 2],
+            ..Default::default()
+        };
+        world.meshes.push(Mesh {
+            primitives: vec![Primitive {
+                number_of_vertices: 3,
+                number_of_indices: 3,
+                number_of_morph_targets: 1,
+                ..Default::default()
+            }],
+            weights: vec![0.0],
+        });
+        world.morph_target_vertices = vec![
+            MorphTargetVertex {
+                position: nalgebra_glm::vec3(0.0, 3.0, 0.0),
+                ..Default::default()
+            };
+            3
+        ];
+        let node_index = world.add_node();
+        world.nodes[node_index].mesh_index = Some(0);
+        world.nodes[node_index].aabb_index = Some(0);
+        world.transforms[node_index].translation = nalgebra_glm::vec3(10.0, 0.0, 0.0);
+        let mut scene = Scene::default();
+        scene.graph.add_node(node_index);
+        world.scenes.push(scene);
+
+        let miss = Ray::new(
+            nalgebra_glm::vec3(0.0, 0.0, 5.0),
+            nalgebra_glm::vec3(0.0, 0.0, -1.0),
+        );
+        assert!(world.raycast(0, &miss).is_none());
+
+        let hit = world
+            .raycast(
+                0,
+                &Ray::new(
+                    nalgebra_glm::vec3(10.0, 0.0, 5.0),
+                    nalgebra_glm::vec3(0.0, 0.0, -1.0),
+                ),
+            )
+            .unwrap();
+        assert_eq!(hit.node_index, node_index);
+        assert!((hit.distance - 5.0).abs() < 1e-5);
+        assert!((hit.normal - nalgebra_glm::vec3(0.0, 0.0, 1.0)).norm() < 1e-5);
+
+        // The morphed triangle lies above the unmorphed bounds
+        let above = Ray::new(
+            nalgebra_glm::vec3(10.0, 3.0, 5.0),
+            nalgebra_glm::vec3(0.0, 0.0, -1.0),
+        );
+        assert!(world.raycast(0, &above).is_none());
+        world.meshes[0].weights = vec![1.0];
+        let hit = world.raycast(0, &above).unwrap();
+        assert!((hit.position - nalgebra_glm::vec3(10.0, 3.0, 0.0)).norm() < 1e-5);
+    }
+}
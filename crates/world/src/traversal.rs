@@ -0,0 +1,356 @@
+use crate::world::{Node, SceneGraph, World};
+use std::collections::VecDeque;
+
+/// A graph node reached while walking a scene, with its accumulated global transform
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SceneNode {
+    pub graph_node_index: petgraph::graph::NodeIndex,
+    pub node_index: usize,
+    pub depth: usize,
+    pub global_transform: nalgebra_glm::Mat4,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum NodeComponent {
+    Camera,
+    Light,
+    Mesh,
+    RigidBody,
+}
+
+impl NodeComponent {
+    pub fn index(&self, node: &Node) -> Option<usize> {
+        match self {
+            Self::Camera => node.camera_index,
+            Self::Light => node.light_index,
+            Self::Mesh => node.mesh_index,
+            Self::RigidBody => node.rigid_body_index,
+        }
+    }
+}
+
+/// Visits a scene's nodes depth first, parents before children
+pub struct DepthFirst<'a> {
+    world: &'a World,
+    graph: &'a SceneGraph,
+    stack: Vec<(petgraph::graph::NodeIndex, usize, nalgebra_glm::Mat4)>,
+}
+
+impl<'a> Iterator for DepthFirst<'a> {
+    type Item = SceneNode;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (graph_node_index, depth, parent_transform) = self.stack.pop()?;
+        let scene_node = visit(
+            self.world,
+            self.graph,
+            graph_node_index,
+            depth,
+            &parent_transform,
+        );
+        // Neighbors are listed newest first, so the oldest child ends up on top of the stack
+        self.stack.extend(
+            self.graph
+                .neighbors_directed(graph_node_index, petgraph::Direction::Outgoing)
+                .map(|child| (child, depth + 1, scene_node.global_transform)),
+        );
+        Some(scene_node)
+    }
+}
+
+/// Visits a scene's nodes breadth first, one depth at a time
+pub struct BreadthFirst<'a> {
+    world: &'a World,
+    graph: &'a SceneGraph,
+    queue: VecDeque<(petgraph::graph::NodeIndex, usize, nalgebra_glm::Mat4)>,
+}
+
+impl<'a> Iterator for BreadthFirst<'a> {
+    type Item = SceneNode;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (graph_node_index, depth, parent_transform) = self.queue.pop_front()?;
+        let scene_node = visit(
+            self.world,
+            self.graph,
+            graph_node_index,
+            depth,
+            &parent_transform,
+        );
+        self.queue.extend(
+            children(self.graph, graph_node_index)
+                .into_iter()
+                .map(|child| (child, depth + 1, scene_node.global_transform)),
+        );
+        Some(scene_node)
+    }
+}
+
+fn visit(
+    world: &World,
+    graph: &SceneGraph,
+    graph_node_index: petgraph::graph::NodeIndex,
+    depth: usize,
+    parent_transform: &nalgebra_glm::Mat4,
+) -> SceneNode {
+    let node_index = graph[graph_node_index];
+    let transform = world.transforms[world.nodes[node_index].transform_index].matrix();
+    SceneNode {
+        graph_node_index,
+        node_index,
+        depth,
+        global_transform: parent_transform * transform,
+    }
+}
+
+fn children(
+    graph: &SceneGraph,
+    graph_node_index: petgraph::graph::NodeIndex,
+) -> Vec<petgraph::graph::NodeIndex> {
+    let mut children = graph
+        .neighbors_directed(graph_node_index, petgraph::Direction::Outgoing)
+        .collect::<Vec<_>>();
+    children.reverse();
+    children
+}
+
+impl World {
+    /// Returns the scene's root graph nodes in the order they were added
+    pub fn roots(&self, scene_index: usize) -> Vec<petgraph::graph::NodeIndex> {
+        let graph = &self.scenes[scene_index].graph;
+        graph
+            .node_indices()
+            .filter(|graph_node_index| self.parent(scene_index, *graph_node_index).is_none())
+            .collect()
+    }
+
+    pub fn parent(
+        &self,
+        scene_index: usize,
+        graph_node_index: petgraph::graph::NodeIndex,
+    ) -> Option<petgraph::graph::NodeIndex> {
+        self.scenes[scene_index]
+            .graph
+            .neighbors_directed(graph_node_index, petgraph::Direction::Incoming)
+            .next()
+    }
+
+    /// Returns the node's children in the order they were added
+    pub fn children(
+        &self,
+        scene_index: usize,
+        graph_node_index: petgraph::graph::NodeIndex,
+    ) -> Vec<petgraph::graph::NodeIndex> {
+        children(&self.scenes[scene_index].graph, graph_node_index)
+    }
+
+    /// Returns the node's ancestors, starting with its parent and ending with its root
+    pub fn ancestors(
+        &self,
+        scene_index: usize,
+        graph_node_index: petgraph::graph::NodeIndex,
+    ) -> Vec<petgraph::graph::NodeIndex> {
+        std::iter::successors(self.parent(scene_index, graph_node_index), |parent| {
+            self.parent(scene_index, *parent)
+        })
+        .collect()
+    }
+
+    pub fn depth_first(&self, scene_index: usize) -> DepthFirst {
+        let graph = &self.scenes[scene_index].graph;
+        let mut roots = self.roots(scene_index);
+        roots.reverse();
+        DepthFirst {
+            world: self,
+            graph,
+            stack: roots
+                .into_iter()
+                .map(|root| (root, 0, nalgebra_glm::Mat4::identity()))
+                .collect(),
+        }
+    }
+
+    pub fn breadth_first(&self, scene_index: usize) -> BreadthFirst {
+        BreadthFirst {
+            world: self,
+            graph: &self.scenes[scene_index].graph,
+            queue: self
+                .roots(scene_index)
+                .into_iter()
+                .map(|root| (root, 0, nalgebra_glm::Mat4::identity()))
+                .collect(),
+        }
+    }
+
+    /// Visits the scene's nodes that have the given component, depth first
+    pub fn nodes_with_component(
+        &self,
+        scene_index: usize,
+        component: NodeComponent,
+    ) -> impl Iterator<Item = SceneNode> + '_ {
+        self.depth_first(scene_index).filter(move |scene_node| {
+            component
+                .index(&self.nodes[scene_node.node_index])
+                .is_some()
+        })
+    }
+
+    pub fn node_name(&self, node_index: usize) -> &str {
+        &self.metadata[self.nodes[node_index].metadata_index].name
+    }
+
+    /// Returns the first node with the given name, searching depth first
+    pub fn find_by_name(
+        &self,
+        scene_index: usize,
+        name: &str,
+    ) -> Option<petgraph::graph::NodeIndex> {
+        self.depth_first(scene_index)
+            .find(|scene_node| self.node_name(scene_node.node_index) == name)
+            .map(|scene_node| scene_node.graph_node_index)
+    }
+
+    /// Finds a node by the names of its ancestors and itself separated by `/`, starting from a root.
+    /// For example, `Scene Root/Helmet/Visor`.
+    pub fn find_by_path(
+        &self,
+        scene_index: usize,
+        path: &str,
+    ) -> Option<petgraph::graph::NodeIndex> {
+        let mut names = path.split('/');
+        let first = names.next()?;
+        let graph = &self.scenes[scene_index].graph;
+        let named = |graph_node_index: &petgraph::graph::NodeIndex, name: &str| {
+            self.node_name(graph[*graph_node_index]) == name
+        };
+        let root = self
+            .roots(scene_index)
+            .into_iter()
+            .find(|root| named(root, first))?;
+        names.try_fold(root, |parent, name| {
+            children(graph, parent)
+                .into_iter()
+                .find(|child| named(child, name))
+        })
+    }
+
+    /// Returns the names from the node's root down to the node, separated by `/`
+    pub fn node_path(
+        &self,
+        scene_index: usize,
+        graph_node_index: petgraph::graph::NodeIndex,
+    ) -> String {
+        let graph = &self.scenes[scene_index].graph;
+        let mut names = self
+            .ancestors(scene_index, graph_node_index)
+            .into_iter()
+            .rev()
+            .map(|ancestor| self.node_name(graph[ancestor]))
+            .collect::<Vec<_>>();
+        names.push(self.node_name(graph[graph_node_index]));
+        names.join("/")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NodeComponent;
+    use crate::world::{Scene, World};
+
+    // Scene Root
+    // ├── Helmet
+    // │   ├── Visor (mesh)
+    // │   └── Strap
+    // └── Lamp (light)
+    fn create_world() -> World {
+        let mut world = World::default();
+        world.scenes.push(Scene::default());
+        let mut add = |name: &str, parent: Option<petgraph::graph::NodeIndex>| {
+            let node_index = world.add_node();
+            world.metadata[node_index].name = name.to_string();
+            world.transforms[node_index].translation = nalgebra_glm::vec3(1.0, 0.0, 0.0);
+            let graph = &mut world.scenes[0].graph;
+            let graph_node_index = graph.add_node(node_index);
+            if let Some(parent) = parent {
+                graph.add_edge(parent, graph_node_index, ());
+            }
+            graph_node_index
+        };
+        let root = add("Scene Root", None);
+        let helmet = add("Helmet", Some(root));
+        add("Visor", Some(helmet));
+        add("Strap", Some(helmet));
+        add("Lamp", Some(root));
+        world.nodes[2].mesh_index = Some(0);
+        world.nodes[4].light_index = Some(0);
+        world
+    }
+
+    fn names(world: &World, nodes: impl Iterator<Item = super::SceneNode>) -> Vec<&str> {
+        nodes
+            .map(|scene_node| world.node_name(scene_node.node_index))
+            .collect()
+    }
+
+    #[test]
+    fn traversals_visit_children_in_order() {
+        let world = create_world();
+        assert_eq!(
+            names(&world, world.depth_first(0)),
+            vec!["Scene Root", "Helmet", "Visor", "Strap", "Lamp"]
+        );
+        assert_eq!(
+            names(&world, world.breadth_first(0)),
+            vec!["Scene Root", "Helmet", "Lamp", "Visor", "Strap"]
+        );
+    }
+
+    #[test]
+    fn traversals_accumulate_global_transforms() {
+        let world = create_world();
+        world.depth_first(0).for_each(|scene_node| {
+            assert_eq!(
+                scene_node.global_transform.m14,
+                (scene_node.depth + 1) as f32
+            );
+            assert_eq!(
+                scene_node.global_transform,
+                world.global_transform(&world.scenes[0].graph, scene_node.graph_node_index)
+            );
+        });
+    }
+
+    #[test]
+    fn nodes_are_found_by_name_and_path() {
+        let world = create_world();
+        let visor = world.find_by_path(0, "Scene Root/Helmet/Visor").unwrap();
+        assert_eq!(world.find_by_name(0, "Visor"), Some(visor));
+        assert_eq!(world.node_path(0, visor), "Scene Root/Helmet/Visor");
+        assert_eq!(world.find_by_path(0, "Scene Root/Lamp/Visor"), None);
+        assert_eq!(world.find_by_path(0, "Helmet"), None);
+
+        let helmet = world.parent(0, visor).unwrap();
+        assert_eq!(world.node_name(world.scenes[0].graph[helmet]), "Helmet");
+        assert_eq!(world.children(0, helmet).len(), 2);
+        assert_eq!(world.ancestors(0, visor).len(), 2);
+    }
+
+    #[test]
+    fn nodes_are_filtered_by_component() {
+        let world = create_world();
+        assert_eq!(
+            names(&world, world.nodes_with_component(0, NodeComponent::Mesh)),
+            vec!["Visor"]
+        );
+        assert_eq!(
+            names(&world, world.nodes_with_component(0, NodeComponent::Light)),
+            vec!["Lamp"]
+        );
+        assert_eq!(
+            world
+                .nodes_with_component(0, NodeComponent::RigidBody)
+                .count(),
+            0
+        );
+    }
+}
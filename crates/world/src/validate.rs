@@ -0,0 +1,689 @@
+use crate::world::{Primitive, World};
+
+/// Identifies the element of a world that a validation issue was found in
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Element {
+    Node(usize),
+    Texture(usize),
+    Material(usize),
+    Primitive {
+        mesh_index: usize,
+        primitive_index: usize,
+    },
+    Joint {
+        skin_index: usize,
+        joint_index: usize,
+    },
+    Channel {
+        animation_index: usize,
+        channel_index: usize,
+    },
+    GraphNode {
+        scene_index: usize,
+        graph_node_index: usize,
+    },
+}
+
+impl std::fmt::Display for Element {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Node(index) => write!(f, "node {index}"),
+            Self::Texture(index) => write!(f, "texture {index}"),
+            Self::Material(index) => write!(f, "material {index}"),
+            Self::Primitive {
+                mesh_index,
+                primitive_index,
+            } => write!(f, "primitive {primitive_index} of mesh {mesh_index}"),
+            Self::Joint {
+                skin_index,
+                joint_index,
+            } => write!(f, "joint {joint_index} of skin {skin_index}"),
+            Self::Channel {
+                animation_index,
+                channel_index,
+            } => write!(f, "channel {channel_index} of animation {animation_index}"),
+            Self::GraphNode {
+                scene_index,
+                graph_node_index,
+            } => write!(f, "graph node {graph_node_index} of scene {scene_index}"),
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum ValidationIssue {
+    #[error("{element} references {collection} {index}, but there are only {length}")]
+    IndexOutOfRange {
+        element: Element,
+        collection: &'static str,
+        index: usize,
+        length: usize,
+    },
+
+    #[error("{element} uses {collection} up to {end}, but there are only {length}")]
+    RangeOutOfBounds {
+        element: Element,
+        collection: &'static str,
+        end: usize,
+        length: usize,
+    },
+
+    #[error("{element} uses a range of {collection} whose end does not fit in memory")]
+    RangeOverflow {
+        element: Element,
+        collection: &'static str,
+    },
+
+    #[error(
+        "{element} has index {index}, but the primitive only has {number_of_vertices} vertices"
+    )]
+    VertexIndexOutOfRange {
+        element: Element,
+        index: u32,
+        number_of_vertices: usize,
+    },
+
+    #[error("{element} has {number_of_outputs} outputs for {number_of_inputs} inputs")]
+    MismatchedChannelOutputs {
+        element: Element,
+        number_of_inputs: usize,
+        number_of_outputs: usize,
+    },
+
+    #[error("{element} has {number_of_tangents} {tangents} for {number_of_outputs} outputs")]
+    MismatchedChannelTangents {
+        element: Element,
+        tangents: &'static str,
+        number_of_outputs: usize,
+        number_of_tangents: usize,
+    },
+
+    #[error("The graph of scene {scene_index} contains a cycle")]
+    CyclicSceneGraph { scene_index: usize },
+
+    #[error("{element} has {number_of_parents} parents")]
+    MultipleParents {
+        element: Element,
+        number_of_parents: usize,
+    },
+
+    #[error("Scene {scene_index} has no camera at its default camera graph node")]
+    MissingDefaultCamera { scene_index: usize },
+
+    #[error("The default scene {scene_index} does not exist, there are only {number_of_scenes}")]
+    MissingDefaultScene {
+        scene_index: usize,
+        number_of_scenes: usize,
+    },
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct ValidationReport {
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    fn check_index(
+        &mut self,
+        element: Element,
+        collection: &'static str,
+        index: Option<usize>,
+        length: usize,
+    ) {
+        if let Some(index) = index.filter(|index| *index >= length) {
+            self.issues.push(ValidationIssue::IndexOutOfRange {
+                element,
+                collection,
+                index,
+                length,
+            });
+        }
+    }
+
+    /// Checks that a range ends within a collection, where `None` is an end that overflowed
+    fn check_range(
+        &mut self,
+        element: Element,
+        collection: &'static str,
+        end: Option<usize>,
+        length: usize,
+    ) -> bool {
+        match end {
+            Some(end) if end > length => self.issues.push(ValidationIssue::RangeOutOfBounds {
+                element,
+                collection,
+                end,
+                length,
+            }),
+            Some(_) => return true,
+            None => self.issues.push(ValidationIssue::RangeOverflow {
+                element,
+                collection,
+            }),
+        }
+        false
+    }
+}
+
+impl std::fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.issues
+            .iter()
+            .try_for_each(|issue| writeln!(f, "{issue}"))
+    }
+}
+
+impl World {
+    /// Checks that every index in the world refers to existing data
+    /// and that every scene graph is a forest whose default camera, if set, is usable
+    pub fn validate(&self) -> ValidationReport {
+        let mut report = ValidationReport::default();
+        self.validate_nodes(&mut report);
+        self.validate_textures(&mut report);
+        self.validate_materials(&mut report);
+        self.validate_primitives(&mut report);
+        self.validate_skins(&mut report);
+        self.validate_animations(&mut report);
+        self.validate_scenes(&mut report);
+        report
+    }
+
+    fn validate_nodes(&self, report: &mut ValidationReport) {
+        self.nodes
+            .iter()
+            .enumerate()
+            .for_each(|(node_index, node)| {
+                let element = Element::Node(node_index);
+                [
+                    (
+                        "transforms",
+                        Some(node.transform_index),
+                        self.transforms.len(),
+                    ),
+                    ("metadata", Some(node.metadata_index), self.metadata.len()),
+                    ("cameras", node.camera_index, self.cameras.len()),
+                    ("meshes", node.mesh_index, self.meshes.len()),
+                    ("lights", node.light_index, self.lights.len()),
+                    (
+                        "primitive meshes",
+                        node.primitive_mesh_index,
+                        self.primitive_meshes.len(),
+                    ),
+                    ("bounding boxes", node.aabb_index, self.aabbs.len()),
+                    (
+                        "morph weights",
+                        node.morph_weights_index,
+                        self.morph_weights.len(),
+                    ),
+                    ("skins", node.skin_index, self.skins.len()),
+                ]
+                .into_iter()
+                .for_each(|(collection, index, length)| {
+                    report.check_index(element, collection, index, length)
+                });
+            });
+    }
+
+    fn validate_textures(&self, report: &mut ValidationReport) {
+        self.textures
+            .iter()
+            .enumerate()
+            .for_each(|(texture_index, texture)| {
+                let element = Element::Texture(texture_index);
+                report.check_index(
+                    element,
+                    "images",
+                    Some(texture.image_index),
+                    self.images.len(),
+                );
+                report.check_index(
+                    element,
+                    "samplers",
+                    texture.sampler_index,
+                    self.samplers.len(),
+                );
+            });
+    }
+
+    fn validate_materials(&self, report: &mut ValidationReport) {
+        self.materials
+            .iter()
+            .enumerate()
+            .for_each(|(material_index, material)| {
+                let element = Element::Material(material_index);
+                material.textures().for_each(|info| {
+                    report.check_index(
+                        element,
+                        "textures",
+                        Some(info.texture_index),
+                        self.textures.len(),
+                    )
+                });
+            });
+    }
+
+    fn validate_primitives(&self, report: &mut ValidationReport) {
+        self.meshes
+            .iter()
+            .enumerate()
+            .for_each(|(mesh_index, mesh)| {
+                mesh.primitives
+                    .iter()
+                    .enumerate()
+                    .for_each(|(primitive_index, primitive)| {
+                        let element = Element::Primitive {
+                            mesh_index,
+                            primitive_index,
+                        };
+                        self.validate_primitive(report, element, primitive);
+                    });
+            });
+    }
+
+    fn validate_primitive(
+        &self,
+        report: &mut ValidationReport,
+        element: Element,
+        primitive: &Primitive,
+    ) {
+        report.check_index(
+            element,
+            "materials",
+            primitive.material_index,
+            self.materials.len(),
+        );
+        report.check_range(
+            element,
+            "vertices",
+            primitive
+                .vertex_offset
+                .checked_add(primitive.number_of_vertices),
+            self.vertices.len(),
+        );
+        report.check_range(
+            element,
+            "morph target vertices",
+            primitive
+                .number_of_morph_targets
+                .checked_mul(primitive.number_of_vertices)
+                .and_then(|length| primitive.morph_target_offset.checked_add(length)),
+            self.morph_target_vertices.len(),
+        );
+
+        let index_ranges = std::iter::once((primitive.index_offset, primitive.number_of_indices))
+            .chain(
+                primitive
+                    .lods
+                    .iter()
+                    .map(|lod| (lod.index_offset, lod.number_of_indices)),
+            );
+        for (index_offset, number_of_indices) in index_ranges {
+            let index_end = index_offset.checked_add(number_of_indices);
+            if !report.check_range(element, "indices", index_end, self.indices.len()) {
+                continue;
+            }
+            let index_end = index_offset + number_of_indices;
+            // Indices are relative to the primitive's first vertex, so only the largest one matters
+            if let Some(&index) = self.indices[index_offset..index_end].iter().max() {
+                if index as usize >= primitive.number_of_vertices {
+                    report.issues.push(ValidationIssue::VertexIndexOutOfRange {
+                        element,
+                        index,
+                        number_of_vertices: primitive.number_of_vertices,
+                    });
+                }
+            }
+        }
+    }
+
+    fn validate_skins(&self, report: &mut ValidationReport) {
+        self.skins
+            .iter()
+            .enumerate()
+            .for_each(|(skin_index, skin)| {
+                skin.joints
+                    .iter()
+                    .enumerate()
+                    .for_each(|(joint_index, joint)| {
+                        report.check_index(
+                            Element::Joint {
+                                skin_index,
+                                joint_index,
+                            },
+                            "nodes",
+                            Some(joint.target_node_index),
+                            self.nodes.len(),
+                        )
+                    });
+            });
+    }
+
+    fn validate_animations(&self, report: &mut ValidationReport) {
+        self.animations
+            .iter()
+            .enumerate()
+            .for_each(|(animation_index, animation)| {
+                animation
+                    .channels
+                    .iter()
+                    .enumerate()
+                    .for_each(|(channel_index, channel)| {
+                        let element = Element::Channel {
+                            animation_index,
+                            channel_index,
+                        };
+                        report.check_index(
+                            element,
+                            "nodes",
+                            Some(channel.target_node_index),
+                            self.nodes.len(),
+                        );
+                        let number_of_outputs = channel.transformations.len();
+                        if !channel.transformations.matches_inputs(channel.inputs.len()) {
+                            report
+                                .issues
+                                .push(ValidationIssue::MismatchedChannelOutputs {
+                                    element,
+                                    number_of_inputs: channel.inputs.len(),
+                                    number_of_outputs,
+                                });
+                        }
+                        // Sampling a cubic spline reads a tangent on each side of every output
+                        let tangents = channel.tangents.iter().flat_map(|tangents| {
+                            [
+                                ("in tangents", &tangents.in_tangents),
+                                ("out tangents", &tangents.out_tangents),
+                            ]
+                        });
+                        for (name, set) in tangents {
+                            if set.len() != number_of_outputs {
+                                report
+                                    .issues
+                                    .push(ValidationIssue::MismatchedChannelTangents {
+                                        element,
+                                        tangents: name,
+                                        number_of_outputs,
+                                        number_of_tangents: set.len(),
+                                    });
+                            }
+                        }
+                    });
+            });
+    }
+
+    fn validate_scenes(&self, report: &mut ValidationReport) {
+        // Worlds without any scenes keep the default scene index at zero
+        let number_of_scenes = self.scenes.len();
+        if self.default_scene_index >= number_of_scenes.max(1) {
+            report.issues.push(ValidationIssue::MissingDefaultScene {
+                scene_index: self.default_scene_index,
+                number_of_scenes,
+            });
+        }
+
+        self.scenes
+            .iter()
+            .enumerate()
+            .for_each(|(scene_index, scene)| {
+                scene.graph.node_indices().for_each(|graph_node_index| {
+                    let element = Element::GraphNode {
+                        scene_index,
+                        graph_node_index: graph_node_index.index(),
+                    };
+                    report.check_index(
+                        element,
+                        "nodes",
+                        Some(scene.graph[graph_node_index]),
+                        self.nodes.len(),
+                    );
+                    let number_of_parents = scene
+                        .graph
+                        .neighbors_directed(graph_node_index, petgraph::Direction::Incoming)
+                        .count();
+                    if number_of_parents > 1 {
+                        report.issues.push(ValidationIssue::MultipleParents {
+                            element,
+                            number_of_parents,
+                        });
+                    }
+                });
+
+                if petgraph::algo::is_cyclic_directed(&scene.graph) {
+                    report
+                        .issues
+                        .push(ValidationIssue::CyclicSceneGraph { scene_index });
+                }
+
+                // Scenes may go without a default camera, but one that is set must be usable
+                let Some(camera_graph_node_index) = scene.default_camera_graph_node_index else {
+                    return;
+                };
+                let has_camera = scene
+                    .graph
+                    .node_weight(camera_graph_node_index)
+                    .and_then(|node_index| self.nodes.get(*node_index))
+                    .and_then(|node| node.camera_index)
+                    .is_some_and(|camera_index| camera_index < self.cameras.len());
+                if !has_camera {
+                    report
+                        .issues
+                        .push(ValidationIssue::MissingDefaultCamera { scene_index });
+                }
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Element, ValidationIssue};
+    use crate::world::{
+        Animation, Channel, ChannelTangents, Interpolation, Mesh, Primitive, Scene,
+        TransformationSet, Vertex, World,
+    };
+
+    fn create_world() -> World {
+        let mut world = World::default();
+        let mut scene = Scene::default();
+        let camera_node_index = world.add_node();
+        world.add_camera_to_node(camera_node_index);
+        scene.default_camera_graph_node_index = Some(scene.graph.add_node(camera_node_index));
+        world.scenes.push(scene);
+        world
+    }
+
+    #[test]
+    fn valid_world_has_no_issues() {
+        let mut world = create_world();
+        world.vertices = vec![Vertex::default(); 3];
+        world.indices = vec![0, 1, 2];
+        world.meshes.push(Mesh {
+            primitives: vec![Primitive {
+                number_of_vertices: 3,
+                number_of_indices: 3,
+                ..Default::default()
+            }],
+            ..Default::default()
+        });
+        let node_index = world.add_node();
+        world.nodes[node_index].mesh_index = Some(0);
+        world.add_child_node(
+            0,
+            world.scenes[0].default_camera_graph_node_index.unwrap(),
+            node_index,
+        );
+
+        let report = world.validate();
+        assert!(report.is_valid(), "{report}");
+    }
+
+    #[test]
+    fn broken_references_are_reported() {
+        let mut world = create_world();
+        world.vertices = vec![Vertex::default(); 3];
+        world.indices = vec![0, 1, 5];
+        world.meshes.push(Mesh {
+            primitives: vec![Primitive {
+                vertex_offset: 1,
+                number_of_vertices: 3,
+                number_of_indices: 3,
+                material_index: Some(2),
+                ..Default::default()
+            }],
+            ..Default::default()
+        });
+        world.nodes[0].mesh_index = Some(4);
+
+        let element = Element::Primitive {
+            mesh_index: 0,
+            primitive_index: 0,
+        };
+        assert_eq!(
+            world.validate().issues,
+            vec![
+                ValidationIssue::IndexOutOfRange {
+                    element: Element::Node(0),
+                    collection: "meshes",
+                    index: 4,
+                    length: 1,
+                },
+                ValidationIssue::IndexOutOfRange {
+                    element,
+                    collection: "materials",
+                    index: 2,
+                    length: 0,
+                },
+                ValidationIssue::RangeOutOfBounds {
+                    element,
+                    collection: "vertices",
+                    end: 4,
+                    length: 3,
+                },
+                ValidationIssue::VertexIndexOutOfRange {
+                    element,
+                    index: 5,
+                    number_of_vertices: 3,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn overflowing_ranges_are_reported() {
+        let mut world = create_world();
+        world.meshes.push(Mesh {
+            primitives: vec![Primitive {
+                vertex_offset: usize::MAX,
+                number_of_vertices: 2,
+                number_of_morph_targets: usize::MAX,
+                index_offset: 1,
+                number_of_indices: usize::MAX,
+                ..Default::default()
+            }],
+            ..Default::default()
+        });
+
+        let element = Element::Primitive {
+            mesh_index: 0,
+            primitive_index: 0,
+        };
+        assert_eq!(
+            world.validate().issues,
+            ["vertices", "morph target vertices", "indices"]
+                .map(|collection| ValidationIssue::RangeOverflow {
+                    element,
+                    collection,
+                })
+                .to_vec()
+        );
+    }
+
+    #[test]
+    fn channels_without_an_output_per_input_are_reported() {
+        let mut world = create_world();
+        let channel = |transformations| Channel {
+            target_node_index: 0,
+            inputs: vec![0.0, 1.0],
+            transformations,
+            interpolation: Interpolation::Linear,
+            tangents: None,
+        };
+        world.animations.push(Animation {
+            channels: vec![
+                channel(TransformationSet::Translations(vec![
+                    nalgebra_glm::Vec3::zeros(),
+                    nalgebra_glm::Vec3::zeros(),
+                ])),
+                channel(TransformationSet::Scales(vec![nalgebra_glm::Vec3::zeros()])),
+                channel(TransformationSet::MorphTargetWeights(vec![0.0; 4])),
+                channel(TransformationSet::MorphTargetWeights(vec![0.0; 3])),
+            ],
+            ..Default::default()
+        });
+
+        let mismatched =
+            |channel_index, number_of_outputs| ValidationIssue::MismatchedChannelOutputs {
+                element: Element::Channel {
+                    animation_index: 0,
+                    channel_index,
+                },
+                number_of_inputs: 2,
+                number_of_outputs,
+            };
+        assert_eq!(
+            world.validate().issues,
+            vec![mismatched(1, 1), mismatched(3, 3)]
+        );
+
+        let translations =
+            |count| TransformationSet::Translations(vec![nalgebra_glm::Vec3::zeros(); count]);
+        world.animations[0].channels = vec![Channel {
+            interpolation: Interpolation::CubicSpline,
+            tangents: Some(ChannelTangents {
+                in_tangents: translations(2),
+                out_tangents: translations(1),
+            }),
+            ..channel(translations(2))
+        }];
+        assert_eq!(
+            world.validate().issues,
+            vec![ValidationIssue::MismatchedChannelTangents {
+                element: Element::Channel {
+                    animation_index: 0,
+                    channel_index: 0,
+                },
+                tangents: "out tangents",
+                number_of_outputs: 2,
+                number_of_tangents: 1,
+            }]
+        );
+    }
+
+    #[test]
+    fn malformed_scene_graphs_are_reported() {
+        let mut world = create_world();
+        let graph = &mut world.scenes[0].graph;
+        let a = graph.add_node(0);
+        let b = graph.add_node(0);
+        let c = graph.add_node(0);
+        graph.add_edge(a, b, ());
+        graph.add_edge(b, c, ());
+        graph.add_edge(c, a, ());
+        graph.add_edge(a, c, ());
+        world.scenes[0].default_camera_graph_node_index = Some(petgraph::graph::NodeIndex::new(10));
+
+        let issues = world.validate().issues;
+        assert!(issues.contains(&ValidationIssue::CyclicSceneGraph { scene_index: 0 }));
+        assert!(issues.contains(&ValidationIssue::MultipleParents {
+            element: Element::GraphNode {
+                scene_index: 0,
+                graph_node_index: c.index(),
+            },
+            number_of_parents: 2,
+        }));
+        assert!(issues.contains(&ValidationIssue::MissingDefaultCamera { scene_index: 0 }));
+    }
+}
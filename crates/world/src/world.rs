@@ -1,4 +1,5 @@
 #[derive(Default, Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
 pub struct World {
     pub animations: Vec<Animation>,
     pub cameras: Vec<Camera>,
@@ -17,6 +18,10 @@ pub struct World {
     pub vertices: Vec<Vertex>,
     pub primitive_meshes: Vec<PrimitiveMesh>,
     pub aabbs: Vec<AxisAlignedBoundingBox>,
+    pub morph_target_vertices: Vec<MorphTargetVertex>,
+    pub morph_weights: Vec<Vec<f32>>,
+    /// The scene to show when the world is opened
+    pub default_scene_index: usize,
 }
 
 impl World {
@@ -50,6 +55,8 @@ impl World {
             rigid_body_index: None,
             primitive_mesh_index: None,
             aabb_index: None,
+            morph_weights_index: None,
+            skin_index: None,
         };
         self.nodes.push(node);
         node_index
@@ -91,9 +98,170 @@ impl World {
             None => transform,
         }
     }
+
+    pub fn morph_target(&self, primitive: &Primitive, target_index: usize) -> &[MorphTargetVertex] {
+        let offset = primitive.morph_target_offset + target_index * primitive.number_of_vertices;
+        &self.morph_target_vertices[offset..(offset + primitive.number_of_vertices)]
+    }
+
+    pub fn node_morph_weights(&self, node_index: usize) -> &[f32] {
+        let node = &self.nodes[node_index];
+        match (node.morph_weights_index, node.mesh_index) {
+            (Some(morph_weights_index), _) => &self.morph_weights[morph_weights_index],
+            (None, Some(mesh_index)) => &self.meshes[mesh_index].weights,
+            (None, None) => &[],
+        }
+    }
+
+    /// Returns the vertices of a primitive with its morph targets applied on the CPU
+    pub fn blend_morph_targets(&self, primitive: &Primitive, weights: &[f32]) -> Vec<Vertex> {
+        let mut vertices = self.vertices
+            [primitive.vertex_offset..(primitive.vertex_offset + primitive.number_of_vertices)]
+            .to_vec();
+        weights
+            .iter()
+            .take(primitive.number_of_morph_targets)
+            .enumerate()
+            .filter(|(_, weight)| **weight != 0.0)
+            .for_each(|(target_index, weight)| {
+                self.morph_target(primitive, target_index)
+                    .iter()
+                    .zip(vertices.iter_mut())
+                    .for_each(|(displacement, vertex)| {
+                        vertex.position += displacement.position * *weight;
+                        vertex.normal += displacement.normal * *weight;
+                        let tangent = vertex.tangent.xyz() + displacement.tangent * *weight;
+                        vertex.tangent = tangent.push(vertex.tangent.w);
+                    });
+            });
+        vertices.iter_mut().for_each(|vertex| {
+            if nalgebra_glm::length2(&vertex.normal) > 0.0 {
+                vertex.normal = vertex.normal.normalize();
+            }
+            let tangent = vertex.tangent.xyz();
+            if nalgebra_glm::length2(&tangent) > 0.0 {
+                vertex.tangent = tangent.normalize().push(vertex.tangent.w);
+            }
+        });
+        vertices
+    }
+
+    pub fn update_animation(&mut self, animation_index: usize, delta_time: f32) {
+        let World {
+            animations,
+            nodes,
+            transforms,
+            morph_weights,
+            ..
+        } = self;
+        let animation = &mut animations[animation_index];
+        animation.time += delta_time;
+        if animation.max_animation_time > 0.0 {
+            animation.time %= animation.max_animation_time;
+        }
+        let time = animation.time;
+        animation.channels.iter().for_each(|channel| {
+            let Some((previous, next, factor)) = channel.keyframes(time) else {
+                return;
+            };
+            let node = &nodes[channel.target_node_index];
+            let transform = &mut transforms[node.transform_index];
+            let tangents = channel.cubic_spline_tangents(previous, next);
+            let [from, from_tangent, to, to_tangent] = hermite_weights(factor);
+            match &channel.transformations {
+                TransformationSet::Translations(translations) => {
+                    transform.translation = match tangents {
+                        Some((
+                            TransformationSet::Translations(out_tangents),
+                            TransformationSet::Translations(in_tangents),
+                            span,
+                        )) => {
+                            translations[previous] * from
+                                + out_tangents[previous] * (from_tangent * span)
+                                + translations[next] * to
+                                + in_tangents[next] * (to_tangent * span)
+                        }
+                        _ => {
+                            nalgebra_glm::lerp(&translations[previous], &translations[next], factor)
+                        }
+                    };
+                }
+                TransformationSet::Rotations(rotations) => {
+                    let (start, end) = (rotations[previous], rotations[next]);
+                    transform.rotation = match tangents {
+                        Some((
+                            TransformationSet::Rotations(out_tangents),
+                            TransformationSet::Rotations(in_tangents),
+                            span,
+                        )) => {
+                            let rotation = (start * from
+                                + out_tangents[previous] * (from_tangent * span)
+                                + end * to
+                                + in_tangents[next] * (to_tangent * span))
+                                .normalize();
+                            nalgebra_glm::quat(rotation.x, rotation.y, rotation.z, rotation.w)
+                        }
+                        _ => nalgebra_glm::quat_slerp(
+                            &nalgebra_glm::quat(start.x, start.y, start.z, start.w),
+                            &nalgebra_glm::quat(end.x, end.y, end.z, end.w),
+                            factor,
+                        ),
+                    };
+                }
+                TransformationSet::Scales(scales) => {
+                    transform.scale = match tangents {
+                        Some((
+                            TransformationSet::Scales(out_tangents),
+                            TransformationSet::Scales(in_tangents),
+                            span,
+                        )) => {
+                            scales[previous] * from
+                                + out_tangents[previous] * (from_tangent * span)
+                                + scales[next] * to
+                                + in_tangents[next] * (to_tangent * span)
+                        }
+                        _ => nalgebra_glm::lerp(&scales[previous], &scales[next], factor),
+                    };
+                }
+                TransformationSet::MorphTargetWeights(weights) => {
+                    let Some(morph_weights_index) = node.morph_weights_index else {
+                        return;
+                    };
+                    let number_of_targets = weights.len() / channel.inputs.len();
+                    let keyframe = |weights: &'_ [f32], index: usize| -> Vec<f32> {
+                        weights[(index * number_of_targets)..][..number_of_targets].to_vec()
+                    };
+                    let (start, end) = (keyframe(weights, previous), keyframe(weights, next));
+                    morph_weights[morph_weights_index] = match tangents {
+                        Some((
+                            TransformationSet::MorphTargetWeights(out_tangents),
+                            TransformationSet::MorphTargetWeights(in_tangents),
+                            span,
+                        )) => {
+                            let out_tangent = keyframe(out_tangents, previous);
+                            let in_tangent = keyframe(in_tangents, next);
+                            (0..number_of_targets)
+                                .map(|target| {
+                                    start[target] * from
+                                        + out_tangent[target] * from_tangent * span
+                                        + end[target] * to
+                                        + in_tangent[target] * to_tangent * span
+                                })
+                                .collect()
+                        }
+                        _ => start
+                            .iter()
+                            .zip(end.iter())
+                            .map(|(start, end)| start + (end - start) * factor)
+                            .collect(),
+                    };
+                }
+            }
+        });
+    }
 }
 
-#[derive(Default, Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Default, Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
 pub struct PrimitiveMesh {
     pub shape: Shape,
     pub color: nalgebra_glm::Vec4,
@@ -124,6 +292,27 @@ pub struct Vertex {
     pub joint_0: nalgebra_glm::Vec4,
     pub weight_0: nalgebra_glm::Vec4,
     pub color_0: nalgebra_glm::Vec3,
+    /// The tangent's direction, with the bitangent's handedness in `w`
+    #[serde(default)]
+    pub tangent: nalgebra_glm::Vec4,
+}
+
+/// A per-vertex displacement belonging to a single morph target
+#[repr(C)]
+#[derive(
+    Default,
+    Debug,
+    Copy,
+    Clone,
+    serde::Serialize,
+    serde::Deserialize,
+    bytemuck::Pod,
+    bytemuck::Zeroable,
+)]
+pub struct MorphTargetVertex {
+    pub position: nalgebra_glm::Vec3,
+    pub normal: nalgebra_glm::Vec3,
+    pub tangent: nalgebra_glm::Vec3,
 }
 
 impl Default for Vertex {
@@ -136,24 +325,27 @@ impl Default for Vertex {
             joint_0: nalgebra_glm::Vec4::default(),
             weight_0: nalgebra_glm::Vec4::default(),
             color_0: nalgebra_glm::vec3(1.0, 1.0, 1.0),
+            tangent: nalgebra_glm::Vec4::default(),
         }
     }
 }
 
-pub type SceneGraph = petgraph::Graph<usize, ()>;
+pub type SceneGraph = petgraph::stable_graph::StableGraph<usize, ()>;
 
 #[derive(Default, Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Scene {
-    pub default_camera_graph_node_index: petgraph::graph::NodeIndex,
+    /// The graph node the scene is viewed through, which scenes without a camera lack
+    pub default_camera_graph_node_index: Option<petgraph::graph::NodeIndex>,
     pub graph: SceneGraph,
 }
 
-#[derive(Default, Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Default, Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
 pub struct Mesh {
     pub primitives: Vec<Primitive>,
+    pub weights: Vec<f32>,
 }
 
-#[derive(Copy, Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Copy, Clone, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
 pub struct Transform {
     pub translation: nalgebra_glm::Vec3,
     pub rotation: nalgebra_glm::Quat,
@@ -236,10 +428,14 @@ impl Transform {
     }
 }
 
-#[derive(Default, Debug, serde::Serialize, serde::Deserialize, Clone)]
+#[derive(Default, Debug, serde::Serialize, serde::Deserialize, Clone, PartialEq)]
 pub struct Camera {
     pub projection: Projection,
     pub orientation: Orientation,
+    /// Added by an importer for a scene without an authored camera,
+    /// so exporters leave it out rather than writing it back as authored
+    #[serde(default)]
+    pub synthetic: bool,
 }
 
 impl Camera {
@@ -251,7 +447,7 @@ impl Camera {
     }
 }
 
-#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone, PartialEq)]
 pub enum Projection {
     Perspective(PerspectiveCamera),
     Orthographic(OrthographicCamera),
@@ -263,19 +459,18 @@ impl Default for Projection {
     }
 }
 
+/// Returns the position, projection and view of the scene's default camera,
+/// or `None` when the scene has no default camera to look through
 pub fn create_camera_matrices(
     world: &crate::world::World,
     scene: &crate::world::Scene,
     aspect_ratio: f32,
-) -> (nalgebra_glm::Vec3, nalgebra_glm::Mat4, nalgebra_glm::Mat4) {
-    let camera_graph_node_index = scene.default_camera_graph_node_index;
-    let camera_node_index = scene.graph[camera_graph_node_index];
-    let camera_node = &world.nodes[camera_node_index];
-    let camera = &world.cameras[camera_node
-        .camera_index
-        .expect("Every scene requires a camera")];
+) -> Option<(nalgebra_glm::Vec3, nalgebra_glm::Mat4, nalgebra_glm::Mat4)> {
+    let camera_graph_node_index = scene.default_camera_graph_node_index?;
+    let camera_node_index = *scene.graph.node_weight(camera_graph_node_index)?;
+    let camera = &world.cameras[world.nodes[camera_node_index].camera_index?];
     let transform = Transform::from(world.global_transform(&scene.graph, camera_graph_node_index));
-    (
+    Some((
         transform.translation,
         camera.projection_matrix(aspect_ratio),
         {
@@ -291,10 +486,10 @@ pub fn create_camera_matrices(
             );
             nalgebra_glm::look_at(&eye, &target, &up)
         },
-    )
+    ))
 }
 
-#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone, PartialEq)]
 pub struct PerspectiveCamera {
     pub aspect_ratio: Option<f32>,
     pub y_fov_rad: f32,
@@ -328,7 +523,7 @@ impl PerspectiveCamera {
     }
 }
 
-#[derive(Default, Debug, serde::Serialize, serde::Deserialize, Clone)]
+#[derive(Default, Debug, serde::Serialize, serde::Deserialize, Clone, PartialEq)]
 pub struct OrthographicCamera {
     pub x_mag: f32,
     pub y_mag: f32,
@@ -361,7 +556,7 @@ impl OrthographicCamera {
     }
 }
 
-#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone, PartialEq)]
 pub struct Orientation {
     pub min_radius: f32,
     pub max_radius: f32,
@@ -446,7 +641,7 @@ impl Default for Orientation {
     }
 }
 
-#[derive(Default, Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Default, Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
 pub enum PrimitiveTopology {
     Points,
     Lines,
@@ -466,7 +661,7 @@ pub struct PrimitiveDrawCommand {
     pub indices: usize,
 }
 
-#[derive(Default, Debug, Copy, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Default, Debug, Copy, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
 pub struct Light {
     pub intensity: f32,
     pub range: f32,
@@ -474,7 +669,7 @@ pub struct Light {
     pub kind: LightKind,
 }
 
-#[derive(Debug, Copy, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Copy, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
 pub enum LightKind {
     Directional,
     Point,
@@ -490,13 +685,13 @@ impl Default for LightKind {
     }
 }
 
-#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
 pub struct Texture {
     pub image_index: usize,
     pub sampler_index: Option<usize>,
 }
 
-#[derive(Default, Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Default, Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
 pub struct Primitive {
     pub vertex_offset: usize,
     pub index_offset: usize,
@@ -504,9 +699,25 @@ pub struct Primitive {
     pub number_of_indices: usize,
     pub topology: PrimitiveTopology,
     pub material_index: Option<usize>,
+    pub morph_target_offset: usize,
+    pub number_of_morph_targets: usize,
+    /// Simplified versions of the primitive from most to least detailed, sharing its vertices
+    #[serde(default)]
+    pub lods: Vec<PrimitiveLod>,
 }
 
-#[derive(Default, Debug, Clone, serde::Serialize, serde::Deserialize)]
+/// A simplified version of a primitive's triangles
+#[derive(Default, Debug, Copy, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
+pub struct PrimitiveLod {
+    pub index_offset: usize,
+    pub number_of_indices: usize,
+    /// The quadric error of the simplification's costliest edge collapse, relative to the primitive's size.
+    /// This is the root of the area weighted mean squared distance from the moved vertex
+    /// to the planes of the original triangles around it, so parts of the surface can stray further.
+    pub error: f32,
+}
+
+#[derive(Default, Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
 pub struct Node {
     pub metadata_index: usize,
     pub transform_index: usize,
@@ -516,19 +727,40 @@ pub struct Node {
     pub rigid_body_index: Option<usize>,
     pub primitive_mesh_index: Option<usize>,
     pub aabb_index: Option<usize>,
+    pub morph_weights_index: Option<usize>,
+    /// The skin deforming this node's mesh
+    #[serde(default)]
+    pub skin_index: Option<usize>,
 }
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
 pub struct NodeMetadata {
     pub name: String,
 }
 
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub struct Image {
+    /// Tightly packed rows, with multi-byte channels in native byte order
+    #[serde(with = "crate::file::bytes")]
     pub pixels: Vec<u8>,
     pub format: ImageFormat,
     pub width: u32,
     pub height: u32,
+    /// Smaller copies of the image in the same format, each half the size of the one before
+    #[serde(default)]
+    pub mip_levels: Vec<MipLevel>,
+    /// A block compressed copy of the image, such as BC7 from a KTX2 container,
+    /// which renderers sample instead when the GPU supports its format
+    #[serde(default)]
+    pub compressed: Option<Box<Image>>,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct MipLevel {
+    #[serde(with = "crate::file::bytes")]
+    pub pixels: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
 }
 
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, serde::Serialize, serde::Deserialize)]
@@ -555,9 +787,11 @@ pub enum ImageFormat {
     R32G32F,
     R32G32B32F,
     R32G32B32A32F,
+    /// Blocks of 4x4 texels compressed into 16 bytes each
+    BC7,
 }
 
-#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
 pub struct Sampler {
     pub min_filter: MinFilter,
     pub mag_filter: MagFilter,
@@ -565,7 +799,7 @@ pub struct Sampler {
     pub wrap_t: WrappingMode,
 }
 
-#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
 pub enum WrappingMode {
     ClampToEdge,
     MirroredRepeat,
@@ -573,14 +807,14 @@ pub enum WrappingMode {
     Repeat,
 }
 
-#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
 pub enum MagFilter {
     Nearest = 1,
     #[default]
     Linear,
 }
 
-#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
 pub enum MinFilter {
     Nearest = 1,
     #[default]
@@ -591,14 +825,193 @@ pub enum MinFilter {
     LinearMipmapLinear,
 }
 
-#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
+/// A metallic roughness material. Missing texture slots sample as white.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
 pub struct Material {
     pub base_color_factor: nalgebra_glm::Vec4,
-    pub base_color_texture_index: usize,
-    pub emissive_texture_index: usize,
+    pub base_color_texture: Option<TextureInfo>,
+    pub emissive_texture: Option<TextureInfo>,
     pub emissive_factor: nalgebra_glm::Vec3,
     pub alpha_mode: AlphaMode,
     pub alpha_cutoff: Option<f32>,
+    /// Scales the emissive factor past one, from KHR_materials_emissive_strength
+    #[serde(default)]
+    pub emissive_strength: Option<f32>,
+    /// Skips lighting and shades with the base color alone, from KHR_materials_unlit
+    #[serde(default)]
+    pub unlit: bool,
+    /// Index of refraction, from KHR_materials_ior
+    #[serde(default)]
+    pub ior: Option<f32>,
+    #[serde(default)]
+    pub specular: Option<Specular>,
+    #[serde(default)]
+    pub transmission: Option<Transmission>,
+    #[serde(default)]
+    pub clearcoat: Option<Clearcoat>,
+}
+
+/// The glTF default material, used for primitives without one
+impl Default for Material {
+    fn default() -> Self {
+        Self {
+            base_color_factor: nalgebra_glm::vec4(1.0, 1.0, 1.0, 1.0),
+            base_color_texture: None,
+            emissive_texture: None,
+            emissive_factor: nalgebra_glm::Vec3::zeros(),
+            alpha_mode: AlphaMode::Opaque,
+            alpha_cutoff: None,
+            emissive_strength: None,
+            unlit: false,
+            ior: None,
+            specular: None,
+            transmission: None,
+            clearcoat: None,
+        }
+    }
+}
+
+impl Material {
+    /// Every texture slot of the material that holds a texture
+    pub fn textures(&self) -> impl Iterator<Item = &TextureInfo> {
+        let specular = self
+            .specular
+            .iter()
+            .flat_map(|specular| [&specular.texture, &specular.color_texture]);
+        let transmission = self
+            .transmission
+            .iter()
+            .map(|transmission| &transmission.texture);
+        let clearcoat = self.clearcoat.iter().flat_map(|clearcoat| {
+            [
+                &clearcoat.texture,
+                &clearcoat.roughness_texture,
+                &clearcoat.normal_texture,
+            ]
+        });
+        [&self.base_color_texture, &self.emissive_texture]
+            .into_iter()
+            .chain(specular)
+            .chain(transmission)
+            .chain(clearcoat)
+            .flatten()
+    }
+
+    pub fn textures_mut(&mut self) -> impl Iterator<Item = &mut TextureInfo> {
+        let specular = self
+            .specular
+            .iter_mut()
+            .flat_map(|specular| [&mut specular.texture, &mut specular.color_texture]);
+        let transmission = self
+            .transmission
+            .iter_mut()
+            .map(|transmission| &mut transmission.texture);
+        let clearcoat = self.clearcoat.iter_mut().flat_map(|clearcoat| {
+            [
+                &mut clearcoat.texture,
+                &mut clearcoat.roughness_texture,
+                &mut clearcoat.normal_texture,
+            ]
+        });
+        [&mut self.base_color_texture, &mut self.emissive_texture]
+            .into_iter()
+            .chain(specular)
+            .chain(transmission)
+            .chain(clearcoat)
+            .flatten()
+    }
+}
+
+/// Offset, rotation and scale applied to texture coordinates, from KHR_texture_transform
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
+pub struct TextureTransform {
+    pub offset: nalgebra_glm::Vec2,
+    /// Counter-clockwise rotation in radians
+    pub rotation: f32,
+    pub scale: nalgebra_glm::Vec2,
+    /// Overrides the texture coordinate set of the texture
+    pub tex_coord: Option<u32>,
+}
+
+impl Default for TextureTransform {
+    fn default() -> Self {
+        Self {
+            offset: nalgebra_glm::Vec2::zeros(),
+            rotation: 0.0,
+            scale: nalgebra_glm::vec2(1.0, 1.0),
+            tex_coord: None,
+        }
+    }
+}
+
+impl TextureTransform {
+    /// The matrix applied to homogeneous texture coordinates,
+    /// which is translation * rotation * scale
+    pub fn matrix(&self) -> nalgebra_glm::Mat3 {
+        let (sin, cos) = self.rotation.sin_cos();
+        nalgebra_glm::mat3(
+            cos * self.scale.x,
+            sin * self.scale.y,
+            self.offset.x,
+            -sin * self.scale.x,
+            cos * self.scale.y,
+            self.offset.y,
+            0.0,
+            0.0,
+            1.0,
+        )
+    }
+}
+
+#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
+pub struct TextureInfo {
+    pub texture_index: usize,
+    /// The texture coordinate set sampled, unless the transform overrides it
+    pub tex_coord: u32,
+    pub transform: Option<TextureTransform>,
+}
+
+/// KHR_materials_specular
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
+pub struct Specular {
+    pub factor: f32,
+    /// Strength in the alpha channel
+    pub texture: Option<TextureInfo>,
+    pub color_factor: nalgebra_glm::Vec3,
+    /// Color in the RGB channels, in sRGB
+    pub color_texture: Option<TextureInfo>,
+}
+
+impl Default for Specular {
+    fn default() -> Self {
+        Self {
+            factor: 1.0,
+            texture: None,
+            color_factor: nalgebra_glm::vec3(1.0, 1.0, 1.0),
+            color_texture: None,
+        }
+    }
+}
+
+/// KHR_materials_transmission
+#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
+pub struct Transmission {
+    pub factor: f32,
+    /// Transmission in the red channel
+    pub texture: Option<TextureInfo>,
+}
+
+/// KHR_materials_clearcoat
+#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
+pub struct Clearcoat {
+    pub factor: f32,
+    /// Intensity in the red channel
+    pub texture: Option<TextureInfo>,
+    pub roughness_factor: f32,
+    /// Roughness in the green channel
+    pub roughness_texture: Option<TextureInfo>,
+    pub normal_texture: Option<TextureInfo>,
+    pub normal_scale: f32,
 }
 
 #[derive(Default, Copy, Clone, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
@@ -609,22 +1022,89 @@ pub enum AlphaMode {
     Blend,
 }
 
-#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
 pub struct Animation {
     pub time: f32,
     pub channels: Vec<Channel>,
     pub max_animation_time: f32,
+    #[serde(default)]
+    pub name: String,
 }
 
-#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
 pub struct Channel {
     pub target_node_index: usize,
     pub inputs: Vec<f32>,
     pub transformations: TransformationSet,
     pub interpolation: Interpolation,
+    /// The in and out tangent of every keyframe, for cubic spline interpolation
+    #[serde(default)]
+    pub tangents: Option<ChannelTangents>,
 }
 
-#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
+pub struct ChannelTangents {
+    pub in_tangents: TransformationSet,
+    pub out_tangents: TransformationSet,
+}
+
+impl Channel {
+    /// Returns the keyframes surrounding `time` and the blend factor between them
+    pub fn keyframes(&self, time: f32) -> Option<(usize, usize, f32)> {
+        let last = self.inputs.len().checked_sub(1)?;
+        if time <= self.inputs[0] {
+            return Some((0, 0, 0.0));
+        }
+        if time >= self.inputs[last] {
+            return Some((last, last, 0.0));
+        }
+        let next = self.inputs.partition_point(|input| *input <= time);
+        let previous = next - 1;
+        let factor = match self.interpolation {
+            Interpolation::Step => 0.0,
+            Interpolation::Linear | Interpolation::CubicSpline => {
+                let span = self.inputs[next] - self.inputs[previous];
+                if span > 0.0 {
+                    (time - self.inputs[previous]) / span
+                } else {
+                    0.0
+                }
+            }
+        };
+        Some((previous, next, factor))
+    }
+
+    /// Returns the out tangent set and in tangent set to blend from `previous` to `next`
+    /// along with the time between them, when the channel is a cubic spline
+    pub fn cubic_spline_tangents(
+        &self,
+        previous: usize,
+        next: usize,
+    ) -> Option<(&TransformationSet, &TransformationSet, f32)> {
+        match (&self.interpolation, &self.tangents) {
+            (Interpolation::CubicSpline, Some(tangents)) => Some((
+                &tangents.out_tangents,
+                &tangents.in_tangents,
+                self.inputs[next] - self.inputs[previous],
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// The Hermite basis weights of the previous value, its out tangent,
+/// the next value and its in tangent
+fn hermite_weights(factor: f32) -> [f32; 4] {
+    let (squared, cubed) = (factor * factor, factor * factor * factor);
+    [
+        2.0 * cubed - 3.0 * squared + 1.0,
+        cubed - 2.0 * squared + factor,
+        -2.0 * cubed + 3.0 * squared,
+        cubed - squared,
+    ]
+}
+
+#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
 pub enum Interpolation {
     #[default]
     Linear,
@@ -632,7 +1112,7 @@ pub enum Interpolation {
     CubicSpline,
 }
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
 pub enum TransformationSet {
     Translations(Vec<nalgebra_glm::Vec3>),
     Rotations(Vec<nalgebra_glm::Vec4>),
@@ -640,18 +1120,44 @@ pub enum TransformationSet {
     MorphTargetWeights(Vec<f32>),
 }
 
-#[derive(Default, Debug, Clone, serde::Serialize, serde::Deserialize)]
+impl TransformationSet {
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Translations(translations) => translations.len(),
+            Self::Rotations(rotations) => rotations.len(),
+            Self::Scales(scales) => scales.len(),
+            Self::MorphTargetWeights(weights) => weights.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether there is one output for each of `number_of_inputs` keyframes,
+    /// where morph target weights hold the same number of weights for every keyframe
+    pub fn matches_inputs(&self, number_of_inputs: usize) -> bool {
+        match self {
+            Self::MorphTargetWeights(weights) => {
+                number_of_inputs > 0 && weights.len() % number_of_inputs == 0
+            }
+            transformations => transformations.len() == number_of_inputs,
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
 pub struct Skin {
     pub joints: Vec<Joint>,
 }
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
 pub struct Joint {
     pub target_node_index: usize,
     pub inverse_bind_matrix: nalgebra_glm::Mat4,
 }
 
-#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize, PartialEq)]
 pub struct AxisAlignedBoundingBox {
     pub min: nalgebra_glm::Vec3,
     pub max: nalgebra_glm::Vec3,
@@ -686,4 +1192,127 @@ impl AxisAlignedBoundingBox {
         self.min = nalgebra_glm::min2(&self.min, &other.min);
         self.max = nalgebra_glm::max2(&self.max, &other.max);
     }
+
+    pub fn intersects(&self, other: &AxisAlignedBoundingBox) -> bool {
+        (0..3).all(|axis| self.min[axis] <= other.max[axis] && self.max[axis] >= other.min[axis])
+    }
+
+    pub fn closest_point(&self, point: &nalgebra_glm::Vec3) -> nalgebra_glm::Vec3 {
+        nalgebra_glm::clamp_vec(point, &self.min, &self.max)
+    }
+
+    /// Returns the box enclosing this box after it has been transformed
+    pub fn transform(&self, matrix: &nalgebra_glm::Mat4) -> Self {
+        let translation = nalgebra_glm::vec3(matrix.m14, matrix.m24, matrix.m34);
+        let (mut min, mut max) = (translation, translation);
+        for row in 0..3 {
+            for column in 0..3 {
+                let a = matrix[(row, column)] * self.min[column];
+                let b = matrix[(row, column)] * self.max[column];
+                min[row] += a.min(b);
+                max[row] += a.max(b);
+            }
+        }
+        Self { min, max }
+    }
+}
+
+#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
+pub struct BoundingSphere {
+    pub center: nalgebra_glm::Vec3,
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    pub fn new(center: nalgebra_glm::Vec3, radius: f32) -> Self {
+        Self { center, radius }
+    }
+
+    pub fn intersects_aabb(&self, aabb: &AxisAlignedBoundingBox) -> bool {
+        nalgebra_glm::distance2(&aabb.closest_point(&self.center), &self.center)
+            <= self.radius * self.radius
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Animation, Channel, Interpolation, Mesh, MorphTargetVertex, Primitive, TransformationSet,
+        Vertex, World,
+    };
+
+    /// A world with one vertex on a mesh with two morph targets, instanced by node 0
+    fn morphed_world() -> World {
+        let mut world = World {
+            vertices: vec![Vertex {
+                normal: nalgebra_glm::vec3(0.0, 0.0, 1.0),
+                tangent: nalgebra_glm::vec4(1.0, 0.0, 0.0, -1.0),
+                ..Default::default()
+            }],
+            morph_target_vertices: vec![
+                MorphTargetVertex {
+                    position: nalgebra_glm::vec3(2.0, 0.0, 0.0),
+                    normal: nalgebra_glm::vec3(0.0, 1.0, -1.0),
+                    tangent: nalgebra_glm::vec3(-1.0, 1.0, 0.0),
+                },
+                MorphTargetVertex {
+                    position: nalgebra_glm::vec3(0.0, 4.0, 0.0),
+                    ..Default::default()
+                },
+            ],
+            meshes: vec![Mesh {
+                primitives: vec![Primitive {
+                    number_of_vertices: 1,
+                    number_of_morph_targets: 2,
+                    ..Default::default()
+                }],
+                weights: vec![0.0, 0.0],
+            }],
+            morph_weights: vec![vec![0.0, 0.0]],
+            ..Default::default()
+        };
+        let node_index = world.add_node();
+        world.nodes[node_index].mesh_index = Some(0);
+        world.nodes[node_index].morph_weights_index = Some(0);
+        world
+    }
+
+    #[test]
+    fn morph_targets_blend_positions_normals_and_tangents() {
+        let world = morphed_world();
+        let primitive = &world.meshes[0].primitives[0];
+        let [vertex] = world.blend_morph_targets(primitive, &[1.0, 0.25])[..] else {
+            panic!("expected one vertex");
+        };
+        assert_eq!(vertex.position, nalgebra_glm::vec3(2.0, 1.0, 0.0));
+        assert!(nalgebra_glm::distance(&vertex.normal, &nalgebra_glm::vec3(0.0, 1.0, 0.0)) < 1e-6);
+        assert!(
+            nalgebra_glm::distance(&vertex.tangent, &nalgebra_glm::vec4(0.0, 1.0, 0.0, -1.0))
+                < 1e-6
+        );
+    }
+
+    #[test]
+    fn morph_weight_channels_are_sampled_between_keyframes() {
+        let mut world = morphed_world();
+        world.animations.push(Animation {
+            channels: vec![Channel {
+                target_node_index: 0,
+                inputs: vec![0.0, 1.0, 2.0],
+                transformations: TransformationSet::MorphTargetWeights(vec![
+                    0.0, 1.0, //
+                    1.0, 0.0, //
+                    0.0, 0.0,
+                ]),
+                interpolation: Interpolation::Linear,
+                tangents: None,
+            }],
+            max_animation_time: 2.0,
+            ..Default::default()
+        });
+        world.update_animation(0, 0.25);
+        assert_eq!(world.node_morph_weights(0), [0.25, 0.75]);
+        world.update_animation(0, 1.25);
+        assert_eq!(world.node_morph_weights(0), [0.5, 0.0]);
+    }
 }